@@ -0,0 +1,63 @@
+//! Rough timings for drawing frames, run with `cargo bench`.
+//!
+//! This uses plain `std::time` rather than a benchmarking framework, so the numbers are only
+//! good for comparing before and after a change on the same machine.
+
+use std::hint::black_box;
+use std::time::Instant;
+use termbuffer::{App, Char, Color};
+
+const COLS: usize = 240;
+const ROWS: usize = 80;
+const ITERATIONS: u32 = 1000;
+
+/// Run `f` `ITERATIONS` times and print the average time taken.
+fn bench(name: &str, mut f: impl FnMut()) {
+    // Warm up.
+    for _ in 0..ITERATIONS / 10 {
+        f();
+    }
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        f();
+    }
+    let per_iter = start.elapsed() / ITERATIONS;
+    println!("{:<32} {:>10.1?}", name, per_iter);
+}
+
+fn main() {
+    println!("{} x {} cells, {} iterations", COLS, ROWS, ITERATIONS);
+
+    // Clearing the buffer for each frame, as `Frame::reset` used to and as it does now.
+    let mut buffer = vec![Char::default(); COLS * ROWS];
+    bench("clear buffer (push loop)", || {
+        buffer.clear();
+        for _ in 0..COLS * ROWS {
+            buffer.push(Char::default());
+        }
+        black_box(&buffer);
+    });
+    bench("clear buffer (fill)", || {
+        buffer.fill(Char::default());
+        buffer.resize(COLS * ROWS, Char::default());
+        black_box(&buffer);
+    });
+
+    let mut app = App::builder().test_backend(COLS, ROWS);
+    bench("draw empty frame", || {
+        app.draw().commit().unwrap();
+        app.backend_mut().clear_output();
+    });
+
+    let mut frame_number = 0;
+    bench("draw changing text", || {
+        frame_number += 1;
+        let mut draw = app.draw();
+        for row in 0..ROWS {
+            let text = format!("row {} of frame {}", row, frame_number);
+            draw.set_str(row, row % 20, &text, Color::Green, Color::Default);
+        }
+        draw.commit().unwrap();
+        app.backend_mut().clear_output();
+    });
+}
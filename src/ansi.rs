@@ -0,0 +1,397 @@
+use crate::glyph::Glyph;
+use crate::link::Link;
+use crate::screen::{Attributes, Char, Color, Style};
+use crate::span::{Line, Span, Text};
+use unicode_width::UnicodeWidthChar;
+
+/// Number of columns between tab stops.
+const TAB_WIDTH: usize = 8;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum State {
+    Ground,
+    Escape,
+    Csi,
+    /// An operating system command. Only hyperlinks (OSC 8) are understood.
+    Osc,
+    /// Saw ESC inside an OSC; expecting `\` to end it.
+    OscEscape,
+}
+
+/// A parser for text containing ANSI escape sequences, such as the output of
+/// `git diff --color`.
+///
+/// SGR (color and style) sequences, hyperlinks, cursor movement and line erasing are
+/// understood; other sequences are skipped. The result is a grid of cells that can be drawn
+/// into a frame with `Frame::set_text`.
+#[derive(Debug, Clone)]
+pub struct AnsiParser {
+    state: State,
+    style: Style,
+    params: Vec<u16>,
+    /// The contents of the OSC being parsed.
+    osc: Vec<u8>,
+    /// Bytes of a partially received UTF-8 character.
+    utf8: Vec<u8>,
+    row: usize,
+    col: usize,
+    cells: Vec<Vec<Char>>,
+}
+
+impl Default for AnsiParser {
+    fn default() -> Self {
+        AnsiParser::new()
+    }
+}
+
+impl AnsiParser {
+    pub fn new() -> AnsiParser {
+        AnsiParser {
+            state: State::Ground,
+            style: Style::default(),
+            params: Vec::new(),
+            osc: Vec::new(),
+            utf8: Vec::new(),
+            row: 0,
+            col: 0,
+            cells: vec![Vec::new()],
+        }
+    }
+
+    /// Parse some more input. Escape sequences and characters may be split across calls.
+    pub fn feed(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.byte(byte);
+        }
+    }
+
+    /// The parsed text, with runs of cells that share a style combined into spans.
+    pub fn text(&self) -> Text<'static> {
+        let lines = self.cells.iter().map(|row| {
+            let mut spans: Vec<Span<'static>> = Vec::new();
+            for ch in row {
+                match spans.last_mut() {
+                    _ if ch.is_continuation() => (),
+                    Some(span) if span.style == ch.style() => {
+                        span.text.to_mut().push_str(ch.glyph.as_str())
+                    }
+                    _ => spans.push(Span::new(ch.glyph.to_string(), ch.style())),
+                }
+            }
+            Line::new(spans)
+        });
+        Text::new(lines.collect())
+    }
+
+    fn byte(&mut self, byte: u8) {
+        match self.state {
+            State::Ground => self.ground(byte),
+            State::Escape => match byte {
+                b'[' => {
+                    self.params.clear();
+                    self.params.push(0);
+                    self.state = State::Csi;
+                }
+                b']' => {
+                    self.osc.clear();
+                    self.state = State::Osc;
+                }
+                _ => self.state = State::Ground,
+            },
+            State::Csi => match byte {
+                b'0'..=b'9' => {
+                    let param = self.params.last_mut().unwrap();
+                    *param = param
+                        .saturating_mul(10)
+                        .saturating_add((byte - b'0').into());
+                }
+                b';' | b':' => self.params.push(0),
+                0x40..=0x7e => {
+                    self.csi(byte);
+                    self.state = State::Ground;
+                }
+                _ => (),
+            },
+            State::Osc => match byte {
+                0x07 => {
+                    self.osc_end();
+                    self.state = State::Ground;
+                }
+                0x1b => self.state = State::OscEscape,
+                _ => self.osc.push(byte),
+            },
+            State::OscEscape => {
+                self.state = if byte == b'\\' {
+                    self.osc_end();
+                    State::Ground
+                } else {
+                    State::Osc
+                }
+            }
+        }
+    }
+
+    fn ground(&mut self, byte: u8) {
+        if !self.utf8.is_empty() || byte >= 0x80 {
+            self.utf8.push(byte);
+            match std::str::from_utf8(&self.utf8) {
+                Ok(s) => {
+                    let glyph = s.chars().next().unwrap();
+                    self.utf8.clear();
+                    self.put(glyph);
+                }
+                Err(e) if e.error_len().is_some() => {
+                    self.utf8.clear();
+                    self.put(char::REPLACEMENT_CHARACTER);
+                }
+                // Wait for the rest of the character.
+                Err(_) => (),
+            }
+            return;
+        }
+        match byte {
+            0x1b => self.state = State::Escape,
+            b'\n' => {
+                self.row += 1;
+                self.col = 0;
+            }
+            b'\r' => self.col = 0,
+            b'\t' => self.col = (self.col / TAB_WIDTH + 1) * TAB_WIDTH,
+            0x08 => self.col = self.col.saturating_sub(1),
+            0x00..=0x1f | 0x7f => (),
+            _ => self.put(byte as char),
+        }
+    }
+
+    /// Write a character at the cursor and advance it.
+    ///
+    /// Zero-width characters (such as combining accents) are added to the previous glyph.
+    fn put(&mut self, c: char) {
+        let (style, col) = (self.style.clone(), self.col);
+        let row = self.row_mut();
+        if c.width() == Some(0) && col > 0 && col <= row.len() {
+            let mut prev = col - 1;
+            while prev > 0 && row[prev].is_continuation() {
+                prev -= 1;
+            }
+            let mut cluster = row[prev].glyph.as_str().to_string();
+            cluster.push(c);
+            if let Some(glyph) = Glyph::new(&cluster) {
+                row[prev].glyph = glyph;
+            }
+            return;
+        }
+        let ch = style.char(c);
+        let width = ch.width().max(1);
+        if row.len() < col + width {
+            row.resize(col + width, Char::default());
+        }
+        row[col] = ch;
+        if width == 2 {
+            row[col + 1] = style.char('\0');
+        }
+        self.col += width;
+    }
+
+    fn row_mut(&mut self) -> &mut Vec<Char> {
+        if self.cells.len() <= self.row {
+            self.cells.resize(self.row + 1, Vec::new());
+        }
+        &mut self.cells[self.row]
+    }
+
+    fn csi(&mut self, action: u8) {
+        // Cursor movements treat a missing or zero parameter as 1.
+        let n = usize::from(self.params[0].max(1));
+        match action {
+            b'm' => self.sgr(),
+            b'A' => self.row = self.row.saturating_sub(n),
+            b'B' => self.row += n,
+            b'C' => self.col += n,
+            b'D' => self.col = self.col.saturating_sub(n),
+            b'G' => self.col = n - 1,
+            b'H' | b'f' => {
+                self.row = n - 1;
+                self.col = usize::from(self.params.get(1).copied().unwrap_or(1).max(1)) - 1;
+            }
+            b'K' => {
+                let (col, mode) = (self.col, self.params[0]);
+                let row = self.row_mut();
+                match mode {
+                    0 => row.truncate(col),
+                    1 => {
+                        for ch in row.iter_mut().take(col + 1) {
+                            *ch = Char::default();
+                        }
+                    }
+                    _ => row.clear(),
+                }
+            }
+            _ => (),
+        }
+    }
+
+    /// Handle a complete OSC. A hyperlink is `8;params;url`, and an empty url ends it.
+    fn osc_end(&mut self) {
+        let osc = String::from_utf8_lossy(&self.osc);
+        let mut parts = osc.splitn(3, ';');
+        if let (Some("8"), Some(_), Some(url)) = (parts.next(), parts.next(), parts.next()) {
+            self.style.link = Some(url).filter(|url| !url.is_empty()).map(Link::new);
+        }
+    }
+
+    fn sgr(&mut self) {
+        let mut params = self.params.iter().copied();
+        while let Some(param) = params.next() {
+            let style = &mut self.style;
+            match param {
+                // Resetting the style doesn't end a hyperlink.
+                0 => {
+                    *style = Style {
+                        link: style.link.take(),
+                        ..Style::default()
+                    }
+                }
+                1 => style.attrs.insert(Attributes::BOLD),
+                2 => style.attrs.insert(Attributes::DIM),
+                3 => style.attrs.insert(Attributes::ITALIC),
+                4 => style.attrs.insert(Attributes::UNDERLINE),
+                5 | 6 => style.attrs.insert(Attributes::BLINK),
+                7 => style.attrs.insert(Attributes::REVERSE),
+                9 => style.attrs.insert(Attributes::STRIKETHROUGH),
+                22 => style.attrs.remove(Attributes::BOLD | Attributes::DIM),
+                23 => style.attrs.remove(Attributes::ITALIC),
+                24 => style.attrs.remove(Attributes::UNDERLINE),
+                25 => style.attrs.remove(Attributes::BLINK),
+                27 => style.attrs.remove(Attributes::REVERSE),
+                29 => style.attrs.remove(Attributes::STRIKETHROUGH),
+                30..=37 => style.color_fg = ansi_color(param - 30),
+                38 => style.color_fg = extended_color(&mut params),
+                39 => style.color_fg = Color::Default,
+                40..=47 => style.color_bg = ansi_color(param - 40),
+                48 => style.color_bg = extended_color(&mut params),
+                49 => style.color_bg = Color::Default,
+                90..=97 => style.color_fg = ansi_color(param - 90 + 8),
+                100..=107 => style.color_bg = ansi_color(param - 100 + 8),
+                _ => (),
+            }
+        }
+    }
+}
+
+/// Parse text containing ANSI escape sequences in one go.
+pub fn parse_ansi(bytes: &[u8]) -> Text<'static> {
+    let mut parser = AnsiParser::new();
+    parser.feed(bytes);
+    parser.text()
+}
+
+/// The color for one of the 16 standard palette entries.
+fn ansi_color(idx: u16) -> Color {
+    match idx {
+        0 => Color::Black,
+        1 => Color::Red,
+        2 => Color::Green,
+        3 => Color::Yellow,
+        4 => Color::Blue,
+        5 => Color::Magenta,
+        6 => Color::Cyan,
+        7 => Color::White,
+        8 => Color::LightBlack,
+        9 => Color::LightRed,
+        10 => Color::LightGreen,
+        11 => Color::LightYellow,
+        12 => Color::LightBlue,
+        13 => Color::LightMagenta,
+        14 => Color::LightCyan,
+        _ => Color::LightWhite,
+    }
+}
+
+/// Parse the rest of a `38;...` or `48;...` color.
+fn extended_color(params: &mut impl Iterator<Item = u16>) -> Color {
+    match params.next() {
+        Some(5) => match params.next() {
+            Some(idx) if idx < 16 => ansi_color(idx),
+            Some(idx) => Color::Indexed(idx.min(255) as u8),
+            None => Color::Default,
+        },
+        Some(2) => {
+            let mut channel = || params.next().unwrap_or(0).min(255) as u8;
+            Color::Rgb(channel(), channel(), channel())
+        }
+        _ => Color::Default,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The text of each line, without styles.
+    fn plain(text: &Text) -> Vec<String> {
+        text.lines
+            .iter()
+            .map(|line| line.spans.iter().map(|span| &*span.text).collect())
+            .collect()
+    }
+
+    #[test]
+    fn colors_and_attributes_are_parsed() {
+        let text = parse_ansi(b"a\x1b[1;31mb\x1b[38;5;200;48;2;1;2;3mc\x1b[0;92md");
+        let spans = &text.lines[0].spans;
+        assert_eq!(plain(&text), ["abcd"]);
+        assert_eq!(spans[0].style, Style::default());
+        assert_eq!(
+            spans[1].style,
+            Style::new(Color::Red, Color::Default, Attributes::BOLD)
+        );
+        assert_eq!(
+            spans[2].style,
+            Style::new(Color::Indexed(200), Color::Rgb(1, 2, 3), Attributes::BOLD)
+        );
+        assert_eq!(
+            spans[3].style,
+            Style::new(Color::LightGreen, Color::Default, Attributes::empty())
+        );
+    }
+
+    #[test]
+    fn cursor_movement_and_erasing() {
+        let text = parse_ansi(b"hello\rj\x1b[2Cx\nworld\x1b[3D\x1b[K!\ttab");
+        assert_eq!(plain(&text), ["jelxo", "wo!     tab"]);
+        let text = parse_ansi(b"abc\x1b[2;3Hx\x1b[1;2H\x1b[1K");
+        assert_eq!(plain(&text), ["  c", "  x"]);
+    }
+
+    #[test]
+    fn input_can_be_split_anywhere() {
+        let input = "\x1b[4mé中\x1b]8;;http://example.com\x1b\\link\x1b]8;;\x07".as_bytes();
+        let mut parser = AnsiParser::new();
+        for byte in input {
+            parser.feed(&[*byte]);
+        }
+        let text = parser.text();
+        assert_eq!(text, parse_ansi(input));
+        assert_eq!(plain(&text), ["é中link"]);
+        let spans = &text.lines[0].spans;
+        assert_eq!(spans[0].style.attrs, Attributes::UNDERLINE);
+        assert_eq!(spans[1].text, "link");
+        assert_eq!(
+            spans[1].style.link.as_ref().unwrap().url(),
+            "http://example.com"
+        );
+    }
+
+    #[test]
+    fn combining_characters_join_the_previous_glyph() {
+        let text = parse_ansi("e\u{301}x".as_bytes());
+        assert_eq!(plain(&text), ["e\u{301}x"]);
+        assert_eq!(text.width(), 2);
+    }
+
+    #[test]
+    fn invalid_utf8_is_replaced() {
+        assert_eq!(plain(&parse_ansi(b"a\xffb")), ["a\u{fffd}b"]);
+    }
+}
@@ -0,0 +1,804 @@
+use crate::event::{Event, FOCUS_IN, FOCUS_OUT};
+use crate::kitty;
+use crate::osc;
+use crate::signal::{self, JobControl};
+use std::collections::VecDeque;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::mem;
+use std::os::unix::io::AsRawFd;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, TryRecvError};
+use std::sync::{Arc, Mutex};
+use std::task::Waker;
+use std::thread;
+use std::time::{Duration, Instant};
+use termion::{
+    raw::{IntoRawMode, RawTerminal},
+    terminal_size,
+};
+
+/// The terminal I/O layer that an `App` draws to and reads events from.
+///
+/// The screen is drawn by writing ANSI escape sequences to the backend, so a backend only has to
+/// deal with the parts that differ between platforms and transports: terminal modes, the size
+/// of the terminal, and decoding input.
+pub trait Backend: Write {
+    /// The size of the terminal as (columns, rows).
+    fn size(&self) -> io::Result<(usize, usize)>;
+
+    /// Put the terminal into raw mode (no line buffering or echo).
+    fn enable_raw_mode(&mut self) -> io::Result<()>;
+
+    /// Restore the terminal to the mode it was in before raw mode was enabled.
+    fn disable_raw_mode(&mut self) -> io::Result<()>;
+
+    /// The next pending input event, or `None` if there are no more events available right
+    /// now.
+    ///
+    /// This must not block.
+    fn next_event(&mut self) -> Option<io::Result<Event>>;
+
+    /// Wait for the next input event, for at most `timeout` if given.
+    ///
+    /// Returns `None` if the timeout expired, or if no more events will ever arrive.
+    fn wait_event(&mut self, timeout: Option<Duration>) -> Option<io::Result<Event>>;
+
+    /// Wake the given waker the next time an input event arrives.
+    ///
+    /// This is used by async event streams. Backends that can't do this will have their input
+    /// polled periodically instead.
+    fn set_waker(&mut self, _waker: Waker) {}
+
+    /// The job control request (e.g. from `SIGTSTP`) received since the last call, if any.
+    fn take_job_control(&mut self) -> Option<JobControl> {
+        None
+    }
+
+    /// Stop the process until it is continued (e.g. by the shell's `fg`).
+    ///
+    /// The terminal has already been restored to its normal state when this is called.
+    fn stop(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// The current time, which ticks and timeouts are measured against.
+    ///
+    /// Backends that simulate a terminal can override this to control time.
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    /// Wait for the given time, e.g. to limit the frame rate (see `App::tick`).
+    fn sleep(&mut self, duration: Duration) {
+        thread::sleep(duration)
+    }
+
+    /// Whether the terminal can be resized while waiting for input, so `App` has to keep
+    /// checking its size rather than waiting for as long as it takes for an event to arrive.
+    fn can_resize(&self) -> bool {
+        true
+    }
+}
+
+impl<B: Backend + ?Sized> Backend for Box<B> {
+    fn size(&self) -> io::Result<(usize, usize)> {
+        (**self).size()
+    }
+
+    fn enable_raw_mode(&mut self) -> io::Result<()> {
+        (**self).enable_raw_mode()
+    }
+
+    fn disable_raw_mode(&mut self) -> io::Result<()> {
+        (**self).disable_raw_mode()
+    }
+
+    fn next_event(&mut self) -> Option<io::Result<Event>> {
+        (**self).next_event()
+    }
+
+    fn wait_event(&mut self, timeout: Option<Duration>) -> Option<io::Result<Event>> {
+        (**self).wait_event(timeout)
+    }
+
+    fn set_waker(&mut self, waker: Waker) {
+        (**self).set_waker(waker)
+    }
+
+    fn take_job_control(&mut self) -> Option<JobControl> {
+        (**self).take_job_control()
+    }
+
+    fn stop(&mut self) -> io::Result<()> {
+        (**self).stop()
+    }
+
+    fn now(&self) -> Instant {
+        (**self).now()
+    }
+
+    fn sleep(&mut self, duration: Duration) {
+        (**self).sleep(duration)
+    }
+
+    fn can_resize(&self) -> bool {
+        (**self).can_resize()
+    }
+}
+
+/// A backend using the process's stdin/stdout via termion, or the controlling terminal.
+///
+/// Input is read on a dedicated thread, so waiting for events doesn't need to spin.
+pub struct TermionBackend {
+    output: Terminal,
+    /// `None` if input isn't read, in plain mode.
+    input: Option<InputThread>,
+}
+
+/// Where a `TermionBackend` writes to.
+enum Terminal {
+    Raw(RawTerminal<io::Stdout>),
+    /// The controlling terminal, opened from `/dev/tty`, with its settings from before raw
+    /// mode was enabled.
+    Tty(File, libc::termios),
+    /// Standard output, left as it is, for printing plain text.
+    Plain(io::Stdout),
+}
+
+impl Terminal {
+    fn writer(&mut self) -> &mut dyn Write {
+        match self {
+            Terminal::Raw(output) => output,
+            Terminal::Tty(file, _) => file,
+            Terminal::Plain(output) => output,
+        }
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        // `RawTerminal` restores its own settings.
+        if let Terminal::Tty(file, termios) = self {
+            unsafe { libc::tcsetattr(file.as_raw_fd(), libc::TCSADRAIN, termios) };
+        }
+    }
+}
+
+impl TermionBackend {
+    /// Create a backend over stdin/stdout. This puts the terminal into raw mode.
+    pub fn new() -> io::Result<TermionBackend> {
+        let output = io::stdout().into_raw_mode()?;
+        signal::install();
+        Ok(TermionBackend {
+            output: Terminal::Raw(output),
+            input: Some(InputThread::spawn(
+                libc::STDIN_FILENO,
+                Box::new(unfiltered),
+                false,
+                || (),
+            )),
+        })
+    }
+
+    /// Create a backend over the controlling terminal, `/dev/tty`, rather than stdin/stdout,
+    /// so they can be redirected (see `AppBuilder::dev_tty`). This puts the terminal into raw
+    /// mode.
+    ///
+    /// Fails if the process has no controlling terminal.
+    pub fn dev_tty() -> io::Result<TermionBackend> {
+        let file = OpenOptions::new().read(true).write(true).open("/dev/tty")?;
+        let fd = file.as_raw_fd();
+        let mut termios = unsafe { mem::zeroed() };
+        check(unsafe { libc::tcgetattr(fd, &mut termios) })?;
+        // As with `WriterBackend`, the input thread gets its own copy of the file descriptor.
+        let input_fd = check(unsafe { libc::dup(fd) })?;
+        signal::install();
+        let mut backend = TermionBackend {
+            output: Terminal::Tty(file, termios),
+            input: Some(InputThread::spawn(
+                input_fd,
+                Box::new(unfiltered),
+                false,
+                move || unsafe {
+                    libc::close(input_fd);
+                },
+            )),
+        };
+        backend.enable_raw_mode()?;
+        Ok(backend)
+    }
+
+    /// Create a backend that writes to stdout without touching the terminal modes, and doesn't
+    /// read any input, for when stdin or stdout isn't a terminal (see `AppBuilder::plain`).
+    ///
+    /// If the size of the terminal can't be read, it is taken from the `COLUMNS` and `LINES`
+    /// environment variables, or assumed to be 80x24.
+    pub fn plain() -> TermionBackend {
+        TermionBackend {
+            output: Terminal::Plain(io::stdout()),
+            input: None,
+        }
+    }
+}
+
+/// Turns the bytes read from the input into the bytes to decode, for input that comes wrapped in
+/// some protocol (e.g. telnet).
+pub(crate) type InputFilter = Box<dyn FnMut(&[u8], &mut Vec<u8>) + Send>;
+
+/// An input filter for input that is just what the terminal sent.
+pub(crate) fn unfiltered(input: &[u8], out: &mut Vec<u8>) {
+    out.extend_from_slice(input);
+}
+
+/// Input decoded on a dedicated thread.
+struct InputThread {
+    events: Receiver<io::Result<termion::event::Event>>,
+    /// Whether to report the end of the input as an error, rather than just a lack of events.
+    report_eof: bool,
+    closed: bool,
+    waker: Arc<Mutex<Option<Waker>>>,
+    /// Stops the input thread reading while raw mode is disabled, so it doesn't steal input
+    /// meant for other programs.
+    paused: Arc<AtomicBool>,
+}
+
+impl InputThread {
+    /// Start reading from `fd`, calling `done` when the thread stops.
+    fn spawn(
+        fd: libc::c_int,
+        filter: InputFilter,
+        report_eof: bool,
+        done: impl FnOnce() + Send + 'static,
+    ) -> InputThread {
+        let (tx, events) = mpsc::channel();
+        let waker = Arc::new(Mutex::new(None));
+        let paused = Arc::new(AtomicBool::new(false));
+        let (thread_waker, thread_paused) = (waker.clone(), paused.clone());
+        thread::spawn(move || {
+            read_input(fd, filter, tx, thread_waker, thread_paused);
+            done();
+        });
+        InputThread {
+            events,
+            report_eof,
+            closed: false,
+            waker,
+            paused,
+        }
+    }
+
+    fn set_paused(&self, paused: bool) {
+        self.paused.store(paused, Ordering::SeqCst);
+    }
+
+    fn next_event(&mut self) -> Option<io::Result<Event>> {
+        match self.events.try_recv() {
+            Ok(evt) => Some(evt.map(Event::from)),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => self.eof(None),
+        }
+    }
+
+    fn wait_event(&mut self, timeout: Option<Duration>) -> Option<io::Result<Event>> {
+        let evt = match timeout {
+            Some(timeout) => match self.events.recv_timeout(timeout) {
+                Ok(evt) => evt,
+                Err(RecvTimeoutError::Timeout) => return None,
+                Err(RecvTimeoutError::Disconnected) => return self.eof(Some(timeout)),
+            },
+            None => match self.events.recv() {
+                Ok(evt) => evt,
+                Err(_) => return self.eof(None),
+            },
+        };
+        Some(evt.map(Event::from))
+    }
+
+    /// What to return once the input has been closed: an error the first time if it is
+    /// reported, then nothing.
+    fn eof(&mut self, timeout: Option<Duration>) -> Option<io::Result<Event>> {
+        if self.report_eof && !self.closed {
+            self.closed = true;
+            return Some(Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "the input has been closed",
+            )));
+        }
+        // Don't let callers waiting for events spin.
+        if let Some(timeout) = timeout {
+            thread::sleep(timeout);
+        }
+        None
+    }
+
+    fn set_waker(&mut self, waker: Waker) {
+        *self.waker.lock().unwrap() = Some(waker);
+    }
+}
+
+/// How long the input thread waits for input before checking whether it has been paused.
+const INPUT_POLL_TIMEOUT_MS: libc::c_int = 50;
+
+/// Read and decode input from `fd` until it is closed or the receiving end hangs up.
+fn read_input(
+    fd: libc::c_int,
+    mut filter: InputFilter,
+    tx: mpsc::Sender<io::Result<termion::event::Event>>,
+    waker: Arc<Mutex<Option<Waker>>>,
+    paused: Arc<AtomicBool>,
+) {
+    let mut buf = [0; 1024];
+    // The start of a sequence that was cut off at the end of the last read.
+    let mut partial = Vec::new();
+    loop {
+        // Only the reading thread is left holding the flag once the backend has been dropped.
+        if Arc::strong_count(&paused) == 1 {
+            return;
+        }
+        if paused.load(Ordering::SeqCst) {
+            thread::sleep(Duration::from_millis(INPUT_POLL_TIMEOUT_MS as u64));
+            continue;
+        }
+        let mut pollfd = libc::pollfd {
+            fd,
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        // We must not read anything once paused, so check again after waiting.
+        let ready = unsafe { libc::poll(&mut pollfd, 1, INPUT_POLL_TIMEOUT_MS) };
+        if ready <= 0 || paused.load(Ordering::SeqCst) {
+            continue;
+        }
+        let len = unsafe { libc::read(fd, buf.as_mut_ptr() as *mut libc::c_void, buf.len()) };
+        if len <= 0 {
+            return;
+        }
+        filter(&buf[..len as usize], &mut partial);
+        let (events, used) = parse_input(&partial);
+        partial.drain(..used);
+        for evt in events {
+            if tx.send(evt).is_err() {
+                // The backend has been dropped.
+                return;
+            }
+        }
+        if let Some(waker) = waker.lock().unwrap().take() {
+            waker.wake();
+        }
+    }
+}
+
+/// Decode a chunk of input into events.
+///
+/// Also returns how many bytes were used: a clipboard reply that hasn't been fully received
+/// yet is left for next time.
+fn parse_input(bytes: &[u8]) -> (Vec<io::Result<termion::event::Event>>, usize) {
+    let mut events = Vec::new();
+    let mut rest = bytes;
+    while let Some((&byte, tail)) = rest.split_first() {
+        // An escape on its own is the escape key, rather than the start of a sequence.
+        if byte == 0x1b && tail.is_empty() {
+            events.push(Ok(termion::event::Event::Key(termion::event::Key::Esc)));
+            rest = tail;
+            break;
+        }
+        // termion doesn't know about focus reports, so pass them on as unsupported events.
+        if rest.starts_with(FOCUS_IN) || rest.starts_with(FOCUS_OUT) {
+            let (report, tail) = rest.split_at(FOCUS_IN.len());
+            events.push(Ok(termion::event::Event::Unsupported(report.to_vec())));
+            rest = tail;
+            continue;
+        }
+        // Nor about the kitty keyboard protocol, or clipboard replies.
+        let len = match osc::reply_len(rest) {
+            Some(None) if rest.len() < osc::MAX_REPLY_LEN => break,
+            Some(None) => Some(rest.len()),
+            Some(len) => len,
+            None => kitty::sequence_len(rest),
+        };
+        if let Some(len) = len {
+            let (sequence, tail) = rest.split_at(len);
+            events.push(Ok(termion::event::Event::Unsupported(sequence.to_vec())));
+            rest = tail;
+            continue;
+        }
+        let mut iter = tail.iter().map(|&b| Ok(b));
+        events.push(termion::event::parse_event(byte, &mut iter));
+        rest = &tail[tail.len() - iter.len()..];
+    }
+    (events, bytes.len() - rest.len())
+}
+
+impl Write for TermionBackend {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.output.writer().write(buf)
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        self.output.writer().write_all(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.output.writer().flush()
+    }
+}
+
+impl Backend for TermionBackend {
+    fn size(&self) -> io::Result<(usize, usize)> {
+        match (&self.output, terminal_size()) {
+            (Terminal::Tty(file, _), _) => tty_size(file.as_raw_fd()),
+            (_, Ok((cols, rows))) => Ok((cols as usize, rows as usize)),
+            (Terminal::Plain(_), Err(_)) => Ok((env_size("COLUMNS", 80), env_size("LINES", 24))),
+            (_, Err(e)) => Err(e),
+        }
+    }
+
+    fn enable_raw_mode(&mut self) -> io::Result<()> {
+        match &self.output {
+            Terminal::Raw(output) => output.activate_raw_mode()?,
+            Terminal::Tty(file, termios) => {
+                let mut raw = *termios;
+                unsafe { libc::cfmakeraw(&mut raw) };
+                check(unsafe { libc::tcsetattr(file.as_raw_fd(), libc::TCSADRAIN, &raw) })?;
+            }
+            Terminal::Plain(_) => {}
+        }
+        if let Some(input) = &self.input {
+            input.set_paused(false);
+        }
+        Ok(())
+    }
+
+    fn disable_raw_mode(&mut self) -> io::Result<()> {
+        if let Some(input) = &self.input {
+            input.set_paused(true);
+        }
+        match &self.output {
+            Terminal::Raw(output) => output.suspend_raw_mode(),
+            Terminal::Tty(file, termios) => {
+                check(unsafe { libc::tcsetattr(file.as_raw_fd(), libc::TCSADRAIN, termios) })?;
+                Ok(())
+            }
+            Terminal::Plain(_) => Ok(()),
+        }
+    }
+
+    fn next_event(&mut self) -> Option<io::Result<Event>> {
+        self.input.as_mut()?.next_event()
+    }
+
+    fn set_waker(&mut self, waker: Waker) {
+        if let Some(input) = &mut self.input {
+            input.set_waker(waker);
+        }
+    }
+
+    fn take_job_control(&mut self) -> Option<JobControl> {
+        self.input.as_ref()?;
+        signal::take()
+    }
+
+    fn stop(&mut self) -> io::Result<()> {
+        signal::stop()
+    }
+
+    fn wait_event(&mut self, timeout: Option<Duration>) -> Option<io::Result<Event>> {
+        match &mut self.input {
+            Some(input) => input.wait_event(timeout),
+            None => {
+                // No input will ever arrive, but wait as asked so callers don't spin.
+                thread::sleep(timeout?);
+                None
+            }
+        }
+    }
+}
+
+/// A size read from an environment variable, like `COLUMNS`, or `default` if it isn't set.
+fn env_size(var: &str, default: usize) -> usize {
+    std::env::var(var)
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .filter(|&size| size > 0)
+        .unwrap_or(default)
+}
+
+/// How a `WriterBackend` finds out the size of its terminal.
+enum Size {
+    Fixed(usize, usize),
+    Callback(Box<dyn Fn() -> io::Result<(usize, usize)> + Send>),
+}
+
+/// A backend over any writer with a file descriptor, such as a TCP stream or a pseudoterminal.
+///
+/// Input is read from the same file descriptor, on a dedicated thread. Once the input has been
+/// closed, the next event is an `UnexpectedEof` error.
+///
+/// There's no general way to find out the size of the terminal at the other end, so it is
+/// supplied by the caller: either fixed and changed with `resize`, or from a callback.
+///
+/// If the file descriptor is a terminal, raw mode is switched on it. Otherwise enabling raw
+/// mode does nothing, and it's up to the other end (e.g. a telnet client) to not buffer or
+/// echo input.
+///
+/// The capabilities of the terminal are normally detected from `TERM`, which describes the
+/// local terminal rather than this one, so they should be set with
+/// `AppBuilder::capabilities`.
+pub struct WriterBackend<W: Write + AsRawFd> {
+    output: W,
+    size: Size,
+    /// The settings of the terminal from before raw mode was enabled, if it is one.
+    termios: Option<libc::termios>,
+    raw_mode: bool,
+    input: InputThread,
+}
+
+impl<W: Write + AsRawFd> WriterBackend<W> {
+    /// Create a backend over `output`, with a fixed size.
+    pub fn new(output: W, cols: usize, rows: usize) -> io::Result<WriterBackend<W>> {
+        WriterBackend::with_size(output, Size::Fixed(cols, rows), Box::new(unfiltered))
+    }
+
+    /// Create a backend over `output` that calls `size` whenever it needs the size of the
+    /// terminal, as (columns, rows).
+    pub fn with_size_fn(
+        output: W,
+        size: impl Fn() -> io::Result<(usize, usize)> + Send + 'static,
+    ) -> io::Result<WriterBackend<W>> {
+        WriterBackend::with_size(output, Size::Callback(Box::new(size)), Box::new(unfiltered))
+    }
+
+    /// Create a backend over a terminal (e.g. the slave end of a pseudoterminal), which knows
+    /// its own size.
+    pub fn tty(output: W) -> io::Result<WriterBackend<W>> {
+        let fd = output.as_raw_fd();
+        WriterBackend::with_size_fn(output, move || tty_size(fd))
+    }
+
+    /// Create a backend whose input is passed through `filter` before being decoded.
+    #[cfg(feature = "net")]
+    pub(crate) fn filtered(
+        output: W,
+        size: impl Fn() -> io::Result<(usize, usize)> + Send + 'static,
+        filter: InputFilter,
+    ) -> io::Result<WriterBackend<W>> {
+        WriterBackend::with_size(output, Size::Callback(Box::new(size)), filter)
+    }
+
+    fn with_size(output: W, size: Size, filter: InputFilter) -> io::Result<WriterBackend<W>> {
+        let fd = output.as_raw_fd();
+        let termios = if unsafe { libc::isatty(fd) } == 1 {
+            let mut termios = unsafe { mem::zeroed() };
+            check(unsafe { libc::tcgetattr(fd, &mut termios) })?;
+            Some(termios)
+        } else {
+            None
+        };
+        // The input thread gets its own copy of the file descriptor, so it can't end up reading
+        // from an unrelated file if the writer is dropped and the number reused.
+        let input_fd = check(unsafe { libc::dup(fd) })?;
+        let input = InputThread::spawn(input_fd, filter, true, move || unsafe {
+            libc::close(input_fd);
+        });
+        let mut backend = WriterBackend {
+            output,
+            size,
+            termios,
+            raw_mode: false,
+            input,
+        };
+        backend.enable_raw_mode()?;
+        Ok(backend)
+    }
+
+    /// Change the size of the terminal, e.g. when the other end reports that it has been
+    /// resized. This replaces any size callback.
+    ///
+    /// The app sends a resize event the next time it checks for events.
+    pub fn resize(&mut self, cols: usize, rows: usize) {
+        self.size = Size::Fixed(cols, rows);
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.output
+    }
+
+    /// The underlying writer. Anything written to it directly will be overwritten the next
+    /// time the screen is redrawn.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.output
+    }
+}
+
+/// The size of the terminal `fd` as (columns, rows).
+fn tty_size(fd: libc::c_int) -> io::Result<(usize, usize)> {
+    let mut size: libc::winsize = unsafe { mem::zeroed() };
+    check(unsafe { libc::ioctl(fd, libc::TIOCGWINSZ, &mut size) })?;
+    Ok((usize::from(size.ws_col), usize::from(size.ws_row)))
+}
+
+/// Turn the -1 returned by a failed libc call into an error.
+fn check(ret: libc::c_int) -> io::Result<libc::c_int> {
+    if ret < 0 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(ret)
+    }
+}
+
+impl<W: Write + AsRawFd> Write for WriterBackend<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.output.write(buf)
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        self.output.write_all(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.output.flush()
+    }
+}
+
+impl<W: Write + AsRawFd> Backend for WriterBackend<W> {
+    fn size(&self) -> io::Result<(usize, usize)> {
+        match &self.size {
+            Size::Fixed(cols, rows) => Ok((*cols, *rows)),
+            Size::Callback(size) => size(),
+        }
+    }
+
+    fn enable_raw_mode(&mut self) -> io::Result<()> {
+        if let Some(termios) = &self.termios {
+            let mut raw = *termios;
+            unsafe { libc::cfmakeraw(&mut raw) };
+            let fd = self.output.as_raw_fd();
+            check(unsafe { libc::tcsetattr(fd, libc::TCSADRAIN, &raw) })?;
+        }
+        self.raw_mode = true;
+        self.input.set_paused(false);
+        Ok(())
+    }
+
+    fn disable_raw_mode(&mut self) -> io::Result<()> {
+        self.input.set_paused(true);
+        if let Some(termios) = &self.termios {
+            let fd = self.output.as_raw_fd();
+            check(unsafe { libc::tcsetattr(fd, libc::TCSADRAIN, termios) })?;
+        }
+        self.raw_mode = false;
+        Ok(())
+    }
+
+    fn next_event(&mut self) -> Option<io::Result<Event>> {
+        self.input.next_event()
+    }
+
+    fn wait_event(&mut self, timeout: Option<Duration>) -> Option<io::Result<Event>> {
+        self.input.wait_event(timeout)
+    }
+
+    fn set_waker(&mut self, waker: Waker) {
+        self.input.set_waker(waker);
+    }
+}
+
+impl<W: Write + AsRawFd> Drop for WriterBackend<W> {
+    fn drop(&mut self) {
+        if self.raw_mode {
+            let _ = self.disable_raw_mode();
+        }
+    }
+}
+
+/// An in-memory backend for testing.
+///
+/// Everything written to the terminal is recorded, and input events are queued up manually
+/// with `push_event`.
+///
+/// Nothing can arrive while waiting, so waiting with a timeout and no events queued (e.g. with
+/// `App::poll_event`) just sleeps for the timeout, and waiting without one (e.g. with
+/// `App::wait_event`) fails straight away with an `UnexpectedEof` error, rather than hanging.
+/// To test timing, such as ticks or timeouts, use `SimBackend`.
+#[derive(Debug, Clone, Default)]
+pub struct TestBackend {
+    cols: usize,
+    rows: usize,
+    raw_mode: bool,
+    output: Vec<u8>,
+    events: VecDeque<Event>,
+}
+
+impl TestBackend {
+    /// Create a test backend with the given size.
+    pub fn new(cols: usize, rows: usize) -> TestBackend {
+        TestBackend {
+            cols,
+            rows,
+            raw_mode: true,
+            output: Vec::new(),
+            events: VecDeque::new(),
+        }
+    }
+
+    /// Change the reported size of the terminal.
+    pub fn resize(&mut self, cols: usize, rows: usize) {
+        self.cols = cols;
+        self.rows = rows;
+    }
+
+    /// Queue an event to be returned from `App::events`.
+    pub fn push_event(&mut self, event: Event) {
+        self.events.push_back(event);
+    }
+
+    /// Whether the terminal is currently in raw mode.
+    pub fn is_raw_mode(&self) -> bool {
+        self.raw_mode
+    }
+
+    /// All the bytes written to the terminal so far.
+    pub fn output(&self) -> &[u8] {
+        &self.output
+    }
+
+    /// Forget the bytes written so far.
+    pub fn clear_output(&mut self) {
+        self.output.clear();
+    }
+}
+
+impl Write for TestBackend {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.output.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Backend for TestBackend {
+    fn size(&self) -> io::Result<(usize, usize)> {
+        Ok((self.cols, self.rows))
+    }
+
+    fn enable_raw_mode(&mut self) -> io::Result<()> {
+        self.raw_mode = true;
+        Ok(())
+    }
+
+    fn disable_raw_mode(&mut self) -> io::Result<()> {
+        self.raw_mode = false;
+        Ok(())
+    }
+
+    fn next_event(&mut self) -> Option<io::Result<Event>> {
+        self.events.pop_front().map(Ok)
+    }
+
+    /// No events can arrive in the meantime, so with none queued this times out, or fails if
+    /// there is no timeout.
+    fn wait_event(&mut self, timeout: Option<Duration>) -> Option<io::Result<Event>> {
+        if !self.events.is_empty() {
+            return self.next_event();
+        }
+        match timeout {
+            Some(timeout) => {
+                thread::sleep(timeout);
+                None
+            }
+            None => Some(Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "no events are queued on the test backend",
+            ))),
+        }
+    }
+
+    /// The size only changes with `resize`, which can't be called while waiting.
+    fn can_resize(&self) -> bool {
+        false
+    }
+}
@@ -0,0 +1,105 @@
+use crate::rect::Rect;
+use crate::screen::{Frame, Style};
+use crate::span::Line;
+use crate::table::apply_style;
+
+/// A bar along the bottom of a region, with text on the left, center and right.
+///
+/// If the text doesn't all fit, the right part is kept whole if possible, then the left, and
+/// anything cut off ends with `…`.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Hash)]
+pub struct StatusBar<'a> {
+    pub left: Line<'a>,
+    pub center: Line<'a>,
+    pub right: Line<'a>,
+    /// The style of the whole bar, underneath the styles of the spans.
+    pub style: Style,
+}
+
+impl<'a> StatusBar<'a> {
+    pub fn new(left: impl Into<Line<'a>>, right: impl Into<Line<'a>>) -> StatusBar<'a> {
+        StatusBar {
+            left: left.into(),
+            right: right.into(),
+            ..StatusBar::default()
+        }
+    }
+
+    /// Draw the bar in the bottom row of a region.
+    pub fn render_into(&self, frame: &mut Frame, region: Rect) {
+        let region = region.intersection(frame.area());
+        if !region.is_empty() {
+            let row = region.bottom() - 1;
+            draw_bar(
+                frame,
+                row,
+                region,
+                [&self.left, &self.center, &self.right],
+                &self.style,
+            );
+        }
+    }
+}
+
+/// A bar along the top of a region, with text on the left, center and right.
+///
+/// If the text doesn't all fit, the right part is kept whole if possible, then the left, and
+/// anything cut off ends with `…`.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Hash)]
+pub struct TitleBar<'a> {
+    pub left: Line<'a>,
+    pub center: Line<'a>,
+    pub right: Line<'a>,
+    /// The style of the whole bar, underneath the styles of the spans.
+    pub style: Style,
+}
+
+impl<'a> TitleBar<'a> {
+    /// A title bar with a title in the center.
+    pub fn new(title: impl Into<Line<'a>>) -> TitleBar<'a> {
+        TitleBar {
+            center: title.into(),
+            ..TitleBar::default()
+        }
+    }
+
+    /// Draw the bar in the top row of a region.
+    pub fn render_into(&self, frame: &mut Frame, region: Rect) {
+        let region = region.intersection(frame.area());
+        if !region.is_empty() {
+            let row = region.row;
+            draw_bar(
+                frame,
+                row,
+                region,
+                [&self.left, &self.center, &self.right],
+                &self.style,
+            );
+        }
+    }
+}
+
+fn draw_bar(frame: &mut Frame, row: usize, region: Rect, parts: [&Line; 3], style: &Style) {
+    let [left, center, right] = parts;
+    let (start, end) = (region.col, region.right());
+    frame.fill_rect(row, start, 1, region.width, style.char(' '));
+    // Keep a space between the parts, if there's more than one.
+    let gap = |a: &Line, b: &Line| usize::from(a.width() > 0 && b.width() > 0);
+
+    let right_start = end - right.width().min(region.width);
+    frame.write_line_truncated(row, right_start, end, right);
+    let left_end = (start + left.width()).min(right_start.saturating_sub(gap(left, right)));
+    if left_end > start {
+        frame.write_line_truncated(row, start, left_end, left);
+    }
+    // The center is centered in the whole bar, but moved over if it would cover the other parts.
+    let min = left_end + gap(left, center);
+    let max = right_start.saturating_sub(gap(center, right));
+    let center_start = (start + region.width.saturating_sub(center.width()) / 2).max(min);
+    let center_end = (center_start + center.width()).min(max);
+    let center_start = center_start.min(center_end.saturating_sub(center.width()).max(min));
+    if center_end > center_start {
+        frame.write_line_truncated(row, center_start, center_end, center);
+    }
+    apply_style(frame, row, Rect::new(row, start, 1, region.width), style);
+}
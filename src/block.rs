@@ -0,0 +1,92 @@
+use crate::border::{BorderStyle, BoxOptions};
+use crate::layout::Padding;
+use crate::rect::Rect;
+use crate::region::Region;
+use crate::screen::{Frame, Style};
+use crate::span::Line;
+use crate::text::Align;
+
+/// A border with an optional title, wrapped around some content.
+///
+/// Drawing a block gives back a `Region` for the inside of the border (and padding), so the
+/// content can't draw over the border.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct Block<'a> {
+    /// The border to draw, or `None` for no border.
+    pub border: Option<BorderStyle>,
+    pub border_style: Style,
+    /// Text to show in the top border. It is cut off if it doesn't fit.
+    pub title: Option<Line<'a>>,
+    pub title_align: Align,
+    /// Space between the border and the content.
+    pub padding: Padding,
+}
+
+impl<'a> Block<'a> {
+    /// A block with a single-line border and no title.
+    pub fn new() -> Block<'a> {
+        Block {
+            border: Some(BorderStyle::default()),
+            border_style: Style::default(),
+            title: None,
+            title_align: Align::Left,
+            padding: Padding::default(),
+        }
+    }
+
+    pub fn title(mut self, title: impl Into<Line<'a>>) -> Block<'a> {
+        self.title = Some(title.into());
+        self
+    }
+
+    /// The area left for the content of a block drawn in `area`.
+    pub fn inner(&self, area: Rect) -> Rect {
+        let border = usize::from(self.border.is_some());
+        area.inner(Padding::uniform(border)).inner(self.padding)
+    }
+
+    /// Draw the block into an area of the frame, and return a region for its content.
+    pub fn render_into<'f>(&self, frame: &'f mut Frame, area: Rect) -> Region<'f> {
+        let area = area.intersection(frame.area());
+        if let Some(border) = self.border {
+            frame.draw_box(
+                area,
+                BoxOptions {
+                    border,
+                    title: None,
+                    color_fg: self.border_style.color_fg,
+                    color_bg: self.border_style.color_bg,
+                    attrs: self.border_style.attrs,
+                },
+            );
+        }
+        if let (Some(title), false) = (&self.title, area.is_empty()) {
+            // The title goes between the corners, if there is a border.
+            let inset = usize::from(self.border.is_some());
+            let start = area.col + inset;
+            let end = area.right().saturating_sub(inset).max(start);
+            let space = end - start;
+            let col = match self.title_align {
+                Align::Left => start,
+                Align::Center => start + space.saturating_sub(title.width()) / 2,
+                Align::Right => start + space.saturating_sub(title.width()),
+            };
+            frame.write_line(area.row, col, end, title);
+        }
+        frame.region(self.inner(area))
+    }
+}
+
+impl<'a> Default for Block<'a> {
+    fn default() -> Block<'a> {
+        Block::new()
+    }
+}
+
+impl<'a> Region<'a> {
+    /// Draw a block around the edge of this region, and return a region for its content.
+    pub fn block(&mut self, block: &Block) -> Region<'_> {
+        let area = self.area();
+        block.render_into(self.frame_mut(), area)
+    }
+}
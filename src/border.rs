@@ -0,0 +1,99 @@
+use crate::rect::Rect;
+use crate::screen::{Attributes, Char, Color, Frame, Style};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// The set of box-drawing characters used for a border.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+pub enum BorderStyle {
+    /// `┌─┐`
+    #[default]
+    Single,
+    /// `╔═╗`
+    Double,
+    /// `╭─╮`
+    Rounded,
+    /// `┏━┓`
+    Heavy,
+}
+
+/// The characters making up a border.
+struct BorderSet {
+    horizontal: char,
+    vertical: char,
+    top_left: char,
+    top_right: char,
+    bottom_left: char,
+    bottom_right: char,
+}
+
+impl BorderStyle {
+    fn set(self) -> BorderSet {
+        let (horizontal, vertical, top_left, top_right, bottom_left, bottom_right) = match self {
+            BorderStyle::Single => ('─', '│', '┌', '┐', '└', '┘'),
+            BorderStyle::Double => ('═', '║', '╔', '╗', '╚', '╝'),
+            BorderStyle::Rounded => ('─', '│', '╭', '╮', '╰', '╯'),
+            BorderStyle::Heavy => ('━', '┃', '┏', '┓', '┗', '┛'),
+        };
+        BorderSet {
+            horizontal,
+            vertical,
+            top_left,
+            top_right,
+            bottom_left,
+            bottom_right,
+        }
+    }
+}
+
+/// Options for `Frame::draw_box`.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+pub struct BoxOptions<'a> {
+    pub border: BorderStyle,
+    /// Text to show in the top border. It is cut off if it doesn't fit.
+    pub title: Option<&'a str>,
+    pub color_fg: Color,
+    pub color_bg: Color,
+    pub attrs: Attributes,
+}
+
+impl Frame {
+    /// Draw a border around the edge of a region. The inside of the region is left untouched.
+    ///
+    /// Any part of the box that lies outside the frame is not drawn.
+    pub fn draw_box(&mut self, region: Rect, options: BoxOptions) {
+        if region.is_empty() {
+            return;
+        }
+        let set = options.border.set();
+        let ch = |glyph: char| Char {
+            glyph: glyph.into(),
+            color_fg: options.color_fg,
+            color_bg: options.color_bg,
+            attrs: options.attrs,
+            link: None,
+        };
+        let (top, bottom) = (region.row, region.bottom() - 1);
+        let (left, right) = (region.col, region.right() - 1);
+        for col in left + 1..right {
+            self.set_clipped(top, col, ch(set.horizontal));
+            self.set_clipped(bottom, col, ch(set.horizontal));
+        }
+        for row in top + 1..bottom {
+            self.set_clipped(row, left, ch(set.vertical));
+            self.set_clipped(row, right, ch(set.vertical));
+        }
+        self.set_clipped(top, left, ch(set.top_left));
+        self.set_clipped(top, right, ch(set.top_right));
+        self.set_clipped(bottom, left, ch(set.bottom_left));
+        self.set_clipped(bottom, right, ch(set.bottom_right));
+        if let (Some(title), true) = (options.title, top < self.rows()) {
+            let style = Style::new(options.color_fg, options.color_bg, options.attrs);
+            self.write_run(
+                top,
+                left + 1,
+                right,
+                title.graphemes(true).map(|glyph| style.char(glyph)),
+            );
+        }
+    }
+}
@@ -0,0 +1,141 @@
+use crate::backend::Backend;
+use crate::event::Event;
+use crate::screen::Frame;
+use crate::{App, AppBuilder, Error};
+use std::ops::{Deref, DerefMut};
+
+/// Identifies a terminal attached to a `Broadcast`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct ClientId(u64);
+
+/// One frame shown on several terminals at once, e.g. so others can watch or pair on a
+/// session.
+///
+/// Each attached terminal is driven by its own `App`, so only the changes it needs are sent to
+/// it. The frame keeps its size whatever the size of the terminals, and is drawn in the middle
+/// of each one as with `AppBuilder::fixed_size`.
+///
+/// It derefs to a `Frame`, which is kept between calls to `render`. To attach terminals of
+/// different kinds, use boxed backends (`Broadcast<Box<dyn Backend>>`).
+pub struct Broadcast<B: Backend> {
+    frame: Frame,
+    builder: AppBuilder,
+    clients: Vec<(ClientId, App<B>)>,
+    next_id: u64,
+}
+
+impl<B: Backend> Broadcast<B> {
+    /// Create a broadcast of a blank frame of the given size, with no terminals attached.
+    pub fn new(rows: usize, cols: usize) -> Broadcast<B> {
+        Broadcast {
+            frame: Frame::new(rows, cols),
+            builder: AppBuilder::default(),
+            clients: Vec::new(),
+            next_id: 0,
+        }
+    }
+
+    /// The builder used to build the app for each terminal attached from now on. Its fixed
+    /// size is replaced with the size of the frame.
+    pub fn builder(mut self, builder: AppBuilder) -> Broadcast<B> {
+        self.builder = builder;
+        self
+    }
+
+    /// Start showing the frame on another terminal. It is drawn on the next call to `render`.
+    pub fn attach(&mut self, backend: B) -> Result<ClientId, Error> {
+        let app = self
+            .builder
+            .clone()
+            .fixed_size(self.frame.rows(), self.frame.columns())
+            .build_with_backend(backend)?;
+        let id = ClientId(self.next_id);
+        self.next_id += 1;
+        self.clients.push((id, app));
+        Ok(id)
+    }
+
+    /// Stop showing the frame on a terminal, returning its app. Dropping the app restores the
+    /// terminal.
+    pub fn detach(&mut self, id: ClientId) -> Option<App<B>> {
+        let idx = self.clients.iter().position(|(client, _)| *client == id)?;
+        Some(self.clients.remove(idx).1)
+    }
+
+    /// The terminals currently attached, in the order they were attached.
+    pub fn clients(&self) -> impl Iterator<Item = ClientId> + '_ {
+        self.clients.iter().map(|(id, _)| *id)
+    }
+
+    pub fn client(&self, id: ClientId) -> Option<&App<B>> {
+        self.clients
+            .iter()
+            .find(|(client, _)| *client == id)
+            .map(|(_, app)| app)
+    }
+
+    pub fn client_mut(&mut self, id: ClientId) -> Option<&mut App<B>> {
+        self.clients
+            .iter_mut()
+            .find(|(client, _)| *client == id)
+            .map(|(_, app)| app)
+    }
+
+    /// Change the size of the frame, clearing it.
+    pub fn resize(&mut self, rows: usize, cols: usize) {
+        self.frame.reset(rows, cols);
+        for (_, app) in &mut self.clients {
+            if let Some(fixed) = &mut app.fixed {
+                fixed.frame.reset(rows, cols);
+            }
+        }
+    }
+
+    /// Draw the frame on every attached terminal.
+    ///
+    /// Terminals that can't be drawn to (e.g. because the connection was closed) are detached,
+    /// and returned along with the error.
+    pub fn render(&mut self) -> Vec<(ClientId, App<B>, Error)> {
+        let mut failed = Vec::new();
+        let mut idx = 0;
+        while idx < self.clients.len() {
+            let app = &mut self.clients[idx].1;
+            let mut draw = app.draw();
+            draw.blit(&self.frame, 0, 0);
+            match draw.commit() {
+                Ok(()) => idx += 1,
+                Err(e) => {
+                    let (id, app) = self.clients.remove(idx);
+                    failed.push((id, app, e));
+                }
+            }
+        }
+        failed
+    }
+
+    /// All the events received from every terminal since the last call, along with which
+    /// terminal they came from.
+    ///
+    /// Resizes are handled when rendering, but are passed on in case the layout depends on
+    /// them.
+    pub fn events(&mut self) -> Vec<(ClientId, Result<Event, Error>)> {
+        let mut events = Vec::new();
+        for (id, app) in &mut self.clients {
+            events.extend(app.events().map(|evt| (*id, evt)));
+        }
+        events
+    }
+}
+
+impl<B: Backend> Deref for Broadcast<B> {
+    type Target = Frame;
+    fn deref(&self) -> &Frame {
+        &self.frame
+    }
+}
+
+impl<B: Backend> DerefMut for Broadcast<B> {
+    fn deref_mut(&mut self) -> &mut Frame {
+        &mut self.frame
+    }
+}
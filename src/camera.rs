@@ -0,0 +1,98 @@
+use crate::rect::Rect;
+use crate::screen::Frame;
+use std::ops::{Deref, DerefMut};
+
+/// A buffer of cells that can be bigger than the terminal, such as the map of a game, shown
+/// through a `Camera`.
+///
+/// It derefs to a `Frame`, so it is drawn on in the same way.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct World {
+    frame: Frame,
+}
+
+impl World {
+    /// Create a blank world of the given size.
+    pub fn new(rows: usize, cols: usize) -> World {
+        World {
+            frame: Frame::new(rows, cols),
+        }
+    }
+}
+
+impl Deref for World {
+    type Target = Frame;
+    fn deref(&self) -> &Frame {
+        &self.frame
+    }
+}
+
+impl DerefMut for World {
+    fn deref_mut(&mut self) -> &mut Frame {
+        &mut self.frame
+    }
+}
+
+/// Which part of a `World` to show.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Camera {
+    /// The row of the world shown at the top of the view.
+    pub row: usize,
+    /// The column of the world shown at the left of the view.
+    pub col: usize,
+    /// A cell of the world to keep in the middle of the view, e.g. the player.
+    pub follow: Option<(usize, usize)>,
+    /// Whether to keep the view inside the world, rather than showing blank space past its
+    /// edges.
+    pub clamp: bool,
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Camera::new()
+    }
+}
+
+impl Camera {
+    pub fn new() -> Camera {
+        Camera {
+            row: 0,
+            col: 0,
+            follow: None,
+            clamp: true,
+        }
+    }
+
+    /// Move the camera to follow its target and stay inside the world, for a view of the given
+    /// size.
+    pub fn update(&mut self, world: &World, height: usize, width: usize) {
+        if let Some((row, col)) = self.follow {
+            self.row = row.saturating_sub(height / 2);
+            self.col = col.saturating_sub(width / 2);
+        }
+        if self.clamp {
+            self.row = self.row.min(world.rows().saturating_sub(height));
+            self.col = self.col.min(world.columns().saturating_sub(width));
+        }
+    }
+
+    /// Copy the visible part of the world into a region of a frame, after updating the camera
+    /// for the size of the region.
+    pub fn render_into(&mut self, world: &World, frame: &mut Frame, region: Rect) {
+        let region = region.intersection(frame.area());
+        self.update(world, region.height, region.width);
+        let view = Rect::new(self.row, self.col, region.height, region.width);
+        frame.blit_rect(world, view, region.row, region.col);
+    }
+
+    /// The cell of the world shown at a position in the view, e.g. for a mouse click.
+    pub fn to_world(&self, row: usize, col: usize) -> (usize, usize) {
+        (self.row + row, self.col + col)
+    }
+
+    /// The position in the view of a cell of the world, or `None` if it is above or left of
+    /// the view.
+    pub fn to_view(&self, row: usize, col: usize) -> Option<(usize, usize)> {
+        Some((row.checked_sub(self.row)?, col.checked_sub(self.col)?))
+    }
+}
@@ -0,0 +1,482 @@
+use crate::glyph::Glyph;
+use crate::rect::Rect;
+use crate::screen::{Attributes, Char, Color, Frame};
+
+/// A grid of pixels drawn with braille characters, giving 2 x 4 pixels per cell.
+///
+/// Each cell has a single foreground color, so pixels that share a cell share the color of the
+/// last one set. Pixel `(0, 0)` is the top-left corner.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct BrailleCanvas {
+    width: usize,
+    height: usize,
+    /// The cells, a row at a time. Each has a bit per dot, in the order used by the braille
+    /// block in Unicode.
+    cells: Vec<(u8, Color)>,
+}
+
+/// The bits of a braille character for each dot, indexed by `[y][x]` within the cell.
+const BRAILLE_DOTS: [[u8; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+impl BrailleCanvas {
+    /// Create an empty canvas of the given size in pixels.
+    pub fn new(width: usize, height: usize) -> BrailleCanvas {
+        BrailleCanvas {
+            width,
+            height,
+            cells: vec![(0, Color::Default); cell_cols(width) * cell_rows(height)],
+        }
+    }
+
+    /// The width of the canvas in pixels.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// The height of the canvas in pixels.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Clear all the pixels.
+    pub fn clear(&mut self) {
+        self.cells.fill((0, Color::Default));
+    }
+
+    /// Turn a pixel on, doing nothing if it is outside the canvas.
+    pub fn set_pixel(&mut self, x: usize, y: usize, color: Color) {
+        if x < self.width && y < self.height {
+            let cell = &mut self.cells[y / 4 * cell_cols(self.width) + x / 2];
+            cell.0 |= BRAILLE_DOTS[y % 4][x % 2];
+            cell.1 = color;
+        }
+    }
+
+    /// Turn a pixel off, doing nothing if it is outside the canvas.
+    pub fn unset_pixel(&mut self, x: usize, y: usize) {
+        if x < self.width && y < self.height {
+            self.cells[y / 4 * cell_cols(self.width) + x / 2].0 &= !BRAILLE_DOTS[y % 4][x % 2];
+        }
+    }
+
+    /// Whether a pixel is on. Pixels outside the canvas are off.
+    pub fn pixel(&self, x: usize, y: usize) -> bool {
+        x < self.width
+            && y < self.height
+            && self.cells[y / 4 * cell_cols(self.width) + x / 2].0 & BRAILLE_DOTS[y % 4][x % 2] != 0
+    }
+
+    /// Draw a straight line between two pixels (inclusive). Any part of the line outside the
+    /// canvas is cut off.
+    pub fn draw_line(&mut self, x0: usize, y0: usize, x1: usize, y1: usize, color: Color) {
+        line(x0, y0, x1, y1, |x, y| self.set_pixel(x, y, color));
+    }
+
+    /// Draw the outline of a circle centred on a pixel. Any part outside the canvas is cut off.
+    pub fn draw_circle(&mut self, cx: usize, cy: usize, radius: usize, color: Color) {
+        self.draw_ellipse(cx, cy, radius, radius, color);
+    }
+
+    /// Draw a filled circle centred on a pixel. Any part outside the canvas is cut off.
+    pub fn fill_circle(&mut self, cx: usize, cy: usize, radius: usize, color: Color) {
+        self.fill_ellipse(cx, cy, radius, radius, color);
+    }
+
+    /// Draw the outline of an ellipse centred on a pixel, with the given horizontal and
+    /// vertical radii. Any part outside the canvas is cut off.
+    pub fn draw_ellipse(&mut self, cx: usize, cy: usize, rx: usize, ry: usize, color: Color) {
+        ellipse(cx, cy, rx, ry, false, |x, y| self.set_pixel(x, y, color));
+    }
+
+    /// Draw a filled ellipse centred on a pixel, with the given horizontal and vertical radii.
+    /// Any part outside the canvas is cut off.
+    pub fn fill_ellipse(&mut self, cx: usize, cy: usize, rx: usize, ry: usize, color: Color) {
+        ellipse(cx, cy, rx, ry, true, |x, y| self.set_pixel(x, y, color));
+    }
+
+    /// Draw the canvas into a region of a frame, starting from the top-left of each.
+    ///
+    /// Cells with no pixels set are left as they are, so whatever is behind the canvas shows
+    /// through. Anything that doesn't fit in the region is cut off.
+    pub fn render_into(&self, frame: &mut Frame, region: Rect) {
+        let cols = cell_cols(self.width);
+        let region = region.intersection(frame.area());
+        for (row, cells) in (region.row..region.bottom()).zip(self.cells.chunks(cols.max(1))) {
+            for (col, &(dots, color)) in (region.col..region.right()).zip(cells) {
+                if dots == 0 {
+                    continue;
+                }
+                // Keep the background of whatever is behind.
+                let ch = Char {
+                    glyph: Glyph::from(char::from_u32(0x2800 + u32::from(dots)).unwrap()),
+                    color_fg: color,
+                    ..frame.get(row, col)
+                };
+                frame.set_clipped(row, col, ch);
+            }
+        }
+    }
+}
+
+/// A grid of pixels drawn with half-block characters, giving 1 x 2 pixels per cell.
+///
+/// Unlike `BrailleCanvas`, every pixel has its own color, which makes this good for low
+/// resolution images and sprites. Pixel `(0, 0)` is the top-left corner.
+///
+/// Images are drawn from decoded RGBA pixels with `draw_rgba` or `from_rgba`. This crate
+/// doesn't depend on the `image` crate, so it doesn't load image files itself; with `image`,
+/// a file can be drawn like this:
+///
+/// ```ignore
+/// let image = image::open("logo.png")?.to_rgba8();
+/// let (width, height) = image.dimensions();
+/// let canvas =
+///     HalfBlockCanvas::from_rgba(40, 20, width as usize, height as usize, image.as_raw());
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct HalfBlockCanvas {
+    width: usize,
+    height: usize,
+    /// The pixels, a row at a time. `None` is a pixel that hasn't been set.
+    pixels: Vec<Option<Color>>,
+}
+
+impl HalfBlockCanvas {
+    /// Create an empty canvas of the given size in pixels.
+    pub fn new(width: usize, height: usize) -> HalfBlockCanvas {
+        HalfBlockCanvas {
+            width,
+            height,
+            pixels: vec![None; width * height],
+        }
+    }
+
+    /// The width of the canvas in pixels.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// The height of the canvas in pixels.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Clear all the pixels.
+    pub fn clear(&mut self) {
+        self.pixels.fill(None);
+    }
+
+    /// Set the color of a pixel, doing nothing if it is outside the canvas.
+    pub fn set_pixel(&mut self, x: usize, y: usize, color: Color) {
+        if x < self.width && y < self.height {
+            self.pixels[y * self.width + x] = Some(color);
+        }
+    }
+
+    /// Clear a pixel, doing nothing if it is outside the canvas.
+    pub fn unset_pixel(&mut self, x: usize, y: usize) {
+        if x < self.width && y < self.height {
+            self.pixels[y * self.width + x] = None;
+        }
+    }
+
+    /// The color of a pixel, or `None` if it hasn't been set or is outside the canvas.
+    pub fn pixel(&self, x: usize, y: usize) -> Option<Color> {
+        if x < self.width && y < self.height {
+            self.pixels[y * self.width + x]
+        } else {
+            None
+        }
+    }
+
+    /// Draw a straight line between two pixels (inclusive). Any part of the line outside the
+    /// canvas is cut off.
+    pub fn draw_line(&mut self, x0: usize, y0: usize, x1: usize, y1: usize, color: Color) {
+        line(x0, y0, x1, y1, |x, y| self.set_pixel(x, y, color));
+    }
+
+    /// Draw the outline of a circle centred on a pixel. Any part outside the canvas is cut off.
+    pub fn draw_circle(&mut self, cx: usize, cy: usize, radius: usize, color: Color) {
+        self.draw_ellipse(cx, cy, radius, radius, color);
+    }
+
+    /// Draw a filled circle centred on a pixel. Any part outside the canvas is cut off.
+    pub fn fill_circle(&mut self, cx: usize, cy: usize, radius: usize, color: Color) {
+        self.fill_ellipse(cx, cy, radius, radius, color);
+    }
+
+    /// Draw the outline of an ellipse centred on a pixel, with the given horizontal and
+    /// vertical radii. Any part outside the canvas is cut off.
+    pub fn draw_ellipse(&mut self, cx: usize, cy: usize, rx: usize, ry: usize, color: Color) {
+        ellipse(cx, cy, rx, ry, false, |x, y| self.set_pixel(x, y, color));
+    }
+
+    /// Draw a filled ellipse centred on a pixel, with the given horizontal and vertical radii.
+    /// Any part outside the canvas is cut off.
+    pub fn fill_ellipse(&mut self, cx: usize, cy: usize, rx: usize, ry: usize, color: Color) {
+        ellipse(cx, cy, rx, ry, true, |x, y| self.set_pixel(x, y, color));
+    }
+
+    /// Create a canvas the size of `cols` by `rows` cells, showing an image scaled to fit it.
+    ///
+    /// See `HalfBlockCanvas::draw_rgba`.
+    pub fn from_rgba(
+        cols: usize,
+        rows: usize,
+        width: usize,
+        height: usize,
+        rgba: &[u8],
+    ) -> HalfBlockCanvas {
+        let mut canvas = HalfBlockCanvas::new(cols, rows * 2);
+        canvas.draw_rgba(width, height, rgba);
+        canvas
+    }
+
+    /// Draw an image over the whole canvas, scaling it to fit. The image is given as rows of
+    /// RGBA bytes, such as from `image::RgbaImage::as_raw`.
+    ///
+    /// Each pixel of the canvas is the average of the pixels of the image it covers. Pixels
+    /// that come out mostly transparent are left as they were.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there aren't `width * height * 4` bytes of pixels.
+    pub fn draw_rgba(&mut self, width: usize, height: usize, rgba: &[u8]) {
+        assert_eq!(rgba.len(), width * height * 4, "wrong number of pixels");
+        if width == 0 || height == 0 {
+            return;
+        }
+        // The pixels of the image covered by a pixel of the canvas, along one axis.
+        let span = |pos: usize, size: usize, image_size: usize| {
+            let start = pos * image_size / size;
+            let end = ((pos + 1) * image_size / size).max(start + 1);
+            start..end
+        };
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let (mut sum, mut count) = ([0u64; 4], 0u64);
+                for image_y in span(y, self.height, height) {
+                    for image_x in span(x, self.width, width) {
+                        let px = &rgba[(image_y * width + image_x) * 4..][..4];
+                        let alpha = u64::from(px[3]);
+                        for (sum, &channel) in sum.iter_mut().zip(&px[..3]) {
+                            *sum += u64::from(channel) * alpha;
+                        }
+                        sum[3] += alpha;
+                        count += 1;
+                    }
+                }
+                if sum[3] < 128 * count {
+                    continue;
+                }
+                let channel = |i: usize| (sum[i] / sum[3]) as u8;
+                self.set_pixel(x, y, Color::Rgb(channel(0), channel(1), channel(2)));
+            }
+        }
+    }
+
+    /// Draw the canvas into a region of a frame, starting from the top-left of each.
+    ///
+    /// Pixels that haven't been set show the background of whatever is behind the canvas.
+    /// Anything that doesn't fit in the region is cut off.
+    pub fn render_into(&self, frame: &mut Frame, region: Rect) {
+        let region = region.intersection(frame.area());
+        let rows = self.height.div_ceil(2);
+        for (row, y) in (region.row..region.bottom()).zip((0..rows).map(|row| row * 2)) {
+            for (col, x) in (region.col..region.right()).zip(0..self.width) {
+                let behind = frame.get(row, col);
+                let (glyph, color_fg, color_bg) = match (self.pixel(x, y), self.pixel(x, y + 1)) {
+                    (Some(top), Some(bottom)) => ('▀', top, bottom),
+                    (Some(top), None) => ('▀', top, behind.color_bg),
+                    (None, Some(bottom)) => ('▄', bottom, behind.color_bg),
+                    (None, None) => continue,
+                };
+                let ch = Char {
+                    glyph: Glyph::from(glyph),
+                    color_fg,
+                    color_bg,
+                    attrs: Attributes::empty(),
+                    link: None,
+                };
+                frame.set_clipped(row, col, ch);
+            }
+        }
+    }
+}
+
+impl Frame {
+    /// Draw a straight line of the given character between two cells (inclusive), where `x` is
+    /// the column and `y` the row. Any part of the line outside the frame is cut off.
+    pub fn draw_line(&mut self, x0: usize, y0: usize, x1: usize, y1: usize, ch: Char) {
+        line(x0, y0, x1, y1, |x, y| self.set_clipped(y, x, ch.clone()));
+    }
+
+    /// Draw the outline of a circle of the given character centred on a cell.
+    ///
+    /// Cells are usually about twice as tall as they are wide, so this looks like a tall
+    /// ellipse; use `Frame::draw_ellipse` with `rx` twice `ry` for something rounder. Any part
+    /// outside the frame is cut off.
+    pub fn draw_circle(&mut self, cx: usize, cy: usize, radius: usize, ch: Char) {
+        self.draw_ellipse(cx, cy, radius, radius, ch);
+    }
+
+    /// Draw a filled circle of the given character centred on a cell. Any part outside the
+    /// frame is cut off.
+    pub fn fill_circle(&mut self, cx: usize, cy: usize, radius: usize, ch: Char) {
+        self.fill_ellipse(cx, cy, radius, radius, ch);
+    }
+
+    /// Draw the outline of an ellipse of the given character centred on a cell, with the given
+    /// horizontal (columns) and vertical (rows) radii. Any part outside the frame is cut off.
+    pub fn draw_ellipse(&mut self, cx: usize, cy: usize, rx: usize, ry: usize, ch: Char) {
+        ellipse(cx, cy, rx, ry, false, |x, y| {
+            self.set_clipped(y, x, ch.clone())
+        });
+    }
+
+    /// Draw a filled ellipse of the given character centred on a cell, with the given
+    /// horizontal (columns) and vertical (rows) radii. Any part outside the frame is cut off.
+    pub fn fill_ellipse(&mut self, cx: usize, cy: usize, rx: usize, ry: usize, ch: Char) {
+        ellipse(cx, cy, rx, ry, true, |x, y| {
+            self.set_clipped(y, x, ch.clone())
+        });
+    }
+}
+
+/// The number of cells needed for a width in braille pixels.
+fn cell_cols(width: usize) -> usize {
+    width.div_ceil(2)
+}
+
+/// The number of cells needed for a height in braille pixels.
+fn cell_rows(height: usize) -> usize {
+    height.div_ceil(4)
+}
+
+/// Call `plot` for each point on the line between two points (inclusive), using Bresenham's
+/// algorithm.
+pub(crate) fn line(x0: usize, y0: usize, x1: usize, y1: usize, mut plot: impl FnMut(usize, usize)) {
+    let (x0, y0, x1, y1) = (x0 as isize, y0 as isize, x1 as isize, y1 as isize);
+    let (dx, dy) = ((x1 - x0).abs(), -(y1 - y0).abs());
+    let (sx, sy) = (if x0 < x1 { 1 } else { -1 }, if y0 < y1 { 1 } else { -1 });
+    let (mut x, mut y, mut err) = (x0, y0, dx + dy);
+    loop {
+        plot(x as usize, y as usize);
+        if x == x1 && y == y1 {
+            break;
+        }
+        let e2 = 2 * err;
+        if e2 >= dy {
+            err += dy;
+            x += sx;
+        }
+        if e2 <= dx {
+            err += dx;
+            y += sy;
+        }
+    }
+}
+
+/// Call `plot` for each point on (or, if `filled`, inside) an ellipse, using the midpoint
+/// algorithm. Points with negative coordinates are skipped.
+pub(crate) fn ellipse(
+    cx: usize,
+    cy: usize,
+    rx: usize,
+    ry: usize,
+    filled: bool,
+    mut plot: impl FnMut(usize, usize),
+) {
+    let (cx, cy, rx, ry) = (cx as i64, cy as i64, rx as i64, ry as i64);
+    let mut put = |x: i64, y: i64| {
+        if x >= 0 && y >= 0 {
+            plot(x as usize, y as usize);
+        }
+    };
+    // Plot the four points (or two spans) for an offset from the centre.
+    let mut quadrants = |dx: i64, dy: i64| {
+        for y in [cy - dy, cy + dy] {
+            if filled {
+                for x in cx - dx..=cx + dx {
+                    put(x, y);
+                }
+            } else {
+                put(cx - dx, y);
+                put(cx + dx, y);
+            }
+        }
+    };
+    if ry == 0 {
+        for dx in 0..=rx {
+            quadrants(dx, 0);
+        }
+        return;
+    }
+    let (rx2, ry2) = (rx * rx, ry * ry);
+    let (mut x, mut y) = (0, ry);
+    let (mut px, mut py) = (0, 2 * rx2 * y);
+    // Region 1, where the slope is shallower than -1. The decision variables are scaled by 4
+    // to stay in integers.
+    let mut p = 4 * ry2 - 4 * rx2 * ry + rx2;
+    while px < py {
+        quadrants(x, y);
+        x += 1;
+        px += 2 * ry2;
+        if p < 0 {
+            p += 4 * (ry2 + px);
+        } else {
+            y -= 1;
+            py -= 2 * rx2;
+            p += 4 * (ry2 + px - py);
+        }
+    }
+    // Region 2, where the slope is steeper.
+    p = ry2 * (2 * x + 1) * (2 * x + 1) + 4 * rx2 * (y - 1) * (y - 1) - 4 * rx2 * ry2;
+    while y >= 0 {
+        quadrants(x, y);
+        y -= 1;
+        py -= 2 * rx2;
+        if p > 0 {
+            p += 4 * (rx2 - py);
+        } else {
+            x += 1;
+            px += 2 * ry2;
+            p += 4 * (rx2 - py + px);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn images_are_scaled_by_averaging() {
+        // A 4x2 image: the left half red, the right half blue with one transparent pixel.
+        let mut rgba = Vec::new();
+        rgba.extend_from_slice(&[255, 0, 0, 255, 255, 0, 0, 255]);
+        rgba.extend_from_slice(&[0, 0, 255, 255, 0, 0, 0, 0]);
+        rgba.extend_from_slice(&[255, 0, 0, 255, 255, 0, 0, 255]);
+        rgba.extend_from_slice(&[0, 0, 255, 255, 0, 0, 255, 255]);
+        let mut canvas = HalfBlockCanvas::new(2, 1);
+        canvas.draw_rgba(4, 2, &rgba);
+        assert_eq!(canvas.pixel(0, 0), Some(Color::Rgb(255, 0, 0)));
+        // Transparent pixels don't count towards the color, only towards the coverage.
+        assert_eq!(canvas.pixel(1, 0), Some(Color::Rgb(0, 0, 255)));
+    }
+
+    #[test]
+    fn mostly_transparent_pixels_are_left_alone() {
+        let mut canvas = HalfBlockCanvas::new(1, 1);
+        canvas.set_pixel(0, 0, Color::Green);
+        canvas.draw_rgba(1, 2, &[255, 0, 0, 255, 0, 0, 0, 0]);
+        assert_eq!(canvas.pixel(0, 0), Some(Color::Green));
+    }
+
+    #[test]
+    fn from_rgba_has_two_pixels_per_cell() {
+        let canvas = HalfBlockCanvas::from_rgba(3, 2, 1, 1, &[1, 2, 3, 255]);
+        assert_eq!((canvas.width(), canvas.height()), (3, 4));
+        assert_eq!(canvas.pixel(2, 3), Some(Color::Rgb(1, 2, 3)));
+    }
+}
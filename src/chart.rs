@@ -0,0 +1,185 @@
+use crate::canvas::BrailleCanvas;
+use crate::rect::Rect;
+use crate::screen::{Color, Frame, Style};
+use unicode_width::UnicodeWidthStr;
+
+/// Blocks for eighths of a cell, from empty to full.
+const BARS: [char; 9] = [' ', '▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// A compact bar chart of a series of values, one column per value, like `▂▃▅▇█▆▃`.
+///
+/// If there are more values than columns, only the last values are shown, so a sparkline of a
+/// growing series scrolls along.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sparkline<'a> {
+    pub data: &'a [f64],
+    /// The value shown as an empty bar. Defaults to 0, or the smallest value if that's less.
+    pub min: Option<f64>,
+    /// The value shown as a full bar. Defaults to the largest value.
+    pub max: Option<f64>,
+    pub style: Style,
+}
+
+impl<'a> Sparkline<'a> {
+    pub fn new(data: &'a [f64]) -> Sparkline<'a> {
+        Sparkline {
+            data,
+            min: None,
+            max: None,
+            style: Style::default(),
+        }
+    }
+
+    /// Draw the sparkline into a region of a frame, with the bars growing up from the bottom.
+    pub fn render_into(&self, frame: &mut Frame, region: Rect) {
+        let region = region.intersection(frame.area());
+        let data = &self.data[self.data.len().saturating_sub(region.width)..];
+        let (min, max) = range(data, self.min, self.max, true);
+        let eighths = region.height * 8;
+        for (col, &value) in (region.col..region.right()).zip(data) {
+            if value.is_nan() {
+                continue;
+            }
+            let filled = (scale(value, min, max) * eighths as f64).round() as usize;
+            for (i, row) in (region.row..region.bottom()).rev().enumerate() {
+                let bar = BARS[filled.saturating_sub(i * 8).min(8)];
+                frame.set_clipped(row, col, self.style.char(bar));
+            }
+        }
+    }
+}
+
+/// A line chart of a series of values, drawn with braille characters, with optional axes and
+/// labels for the range of values.
+///
+/// The values are spread evenly across the width of the plot. `NaN` values leave a gap in the
+/// line.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LinePlot<'a> {
+    pub data: &'a [f64],
+    /// The value at the bottom of the plot. Defaults to the smallest value.
+    pub min: Option<f64>,
+    /// The value at the top of the plot. Defaults to the largest value.
+    pub max: Option<f64>,
+    pub color: Color,
+    /// Whether to draw axes along the left and bottom of the plot.
+    pub axes: bool,
+    /// Whether to label the top and bottom of the plot with the maximum and minimum values.
+    pub labels: bool,
+    /// The number of decimal places to show in labels.
+    pub precision: usize,
+    /// The style of the axes and labels.
+    pub axis_style: Style,
+}
+
+impl<'a> LinePlot<'a> {
+    pub fn new(data: &'a [f64]) -> LinePlot<'a> {
+        LinePlot {
+            data,
+            min: None,
+            max: None,
+            color: Color::default(),
+            axes: true,
+            labels: true,
+            precision: 1,
+            axis_style: Style::default(),
+        }
+    }
+
+    /// Draw the plot into a region of a frame.
+    pub fn render_into(&self, frame: &mut Frame, region: Rect) {
+        let region = region.intersection(frame.area());
+        let (min, max) = range(self.data, self.min, self.max, false);
+        let mut plot = region;
+        if self.labels && !region.is_empty() {
+            let top = format!("{:.*}", self.precision, max);
+            let bottom = format!("{:.*}", self.precision, min);
+            let width = top.width().max(bottom.width()).min(region.width);
+            // Labels are right-aligned against the plot.
+            let style = &self.axis_style;
+            let label = |frame: &mut Frame, row: usize, text: &str| {
+                let col = region.col + width - text.width().min(width);
+                frame.write_run(
+                    row,
+                    col,
+                    region.col + width,
+                    text.chars().map(|c| style.char(c)),
+                );
+            };
+            label(frame, region.row, &top);
+            // The bottom label goes level with the bottom of the plot, above the x axis.
+            let bottom_row = region.bottom() - 1 - usize::from(self.axes);
+            if bottom_row > region.row && bottom_row < region.bottom() {
+                label(frame, bottom_row, &bottom);
+            }
+            plot.col += width;
+            plot.width -= width;
+        }
+        if self.axes && !plot.is_empty() {
+            let style = &self.axis_style;
+            let bottom = plot.bottom() - 1;
+            for row in plot.row..bottom {
+                frame.set_clipped(row, plot.col, style.char('│'));
+            }
+            frame.set_clipped(bottom, plot.col, style.char('└'));
+            for col in plot.col + 1..plot.right() {
+                frame.set_clipped(bottom, col, style.char('─'));
+            }
+            plot = Rect::new(plot.row, plot.col + 1, plot.height - 1, plot.width - 1);
+        }
+        if plot.is_empty() || self.data.is_empty() {
+            return;
+        }
+        let mut canvas = BrailleCanvas::new(plot.width * 2, plot.height * 4);
+        let (w, h) = (canvas.width() - 1, canvas.height() - 1);
+        let last = (self.data.len() - 1).max(1);
+        let point = |i: usize, value: f64| {
+            let x = i * w / last;
+            let y = ((1.0 - scale(value, min, max)) * h as f64).round() as usize;
+            (x, y)
+        };
+        let mut prev = None;
+        for (i, &value) in self.data.iter().enumerate() {
+            if value.is_nan() {
+                prev = None;
+                continue;
+            }
+            let (x, y) = point(i, value);
+            match prev {
+                Some((x0, y0)) => canvas.draw_line(x0, y0, x, y, self.color),
+                None => canvas.set_pixel(x, y, self.color),
+            }
+            prev = Some((x, y));
+        }
+        canvas.render_into(frame, plot);
+    }
+}
+
+/// The range of values to show, from the given bounds or else the data. If `from_zero`, the
+/// default minimum is 0 unless the data goes below it.
+fn range(data: &[f64], min: Option<f64>, max: Option<f64>, from_zero: bool) -> (f64, f64) {
+    let values = || data.iter().copied().filter(|v| !v.is_nan());
+    let data_min = values().fold(f64::INFINITY, f64::min);
+    let data_max = values().fold(f64::NEG_INFINITY, f64::max);
+    let default_min = if from_zero {
+        data_min.min(0.0)
+    } else {
+        data_min
+    };
+    let (min, max) = (min.unwrap_or(default_min), max.unwrap_or(data_max));
+    if min.is_finite() && max.is_finite() {
+        (min, max)
+    } else {
+        (0.0, 0.0)
+    }
+}
+
+/// Where a value lies between `min` and `max`, from 0 to 1. If the range is empty, everything
+/// is in the middle.
+fn scale(value: f64, min: f64, max: f64) -> f64 {
+    if max > min {
+        ((value - min) / (max - min)).clamp(0.0, 1.0)
+    } else {
+        0.5
+    }
+}
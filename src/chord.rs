@@ -0,0 +1,92 @@
+use crate::keymap::{KeyMap, KeyMatch};
+use std::time::{Duration, Instant};
+use termion::event::Key;
+
+/// Something produced by a `ChordMatcher`.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum Chord<A> {
+    /// A sequence of keys matched a binding.
+    Action(A),
+    /// A key that isn't part of a binding, to be handled as usual.
+    Key(Key),
+}
+
+/// Matches Emacs-style key sequences (chords), like Ctrl-X followed by Ctrl-C.
+///
+/// Keys that could be the start of a binding are held back until the sequence is complete. If
+/// the next key doesn't continue the sequence, or nothing is pressed before the timeout, the
+/// held keys are given back one by one as `Chord::Key`.
+#[derive(Debug, Clone)]
+pub struct ChordMatcher<A> {
+    pub keymap: KeyMap<A>,
+    /// How long to wait for the next key of a sequence.
+    pub timeout: Duration,
+    pending: Vec<Key>,
+    /// When the last pending key was pressed.
+    last: Option<Instant>,
+}
+
+impl<A: Clone> ChordMatcher<A> {
+    /// A matcher for the sequences in `keymap`, which waits one second for the next key.
+    pub fn new(keymap: KeyMap<A>) -> ChordMatcher<A> {
+        ChordMatcher {
+            keymap,
+            timeout: Duration::from_secs(1),
+            pending: Vec::new(),
+            last: None,
+        }
+    }
+
+    /// The keys held back so far.
+    pub fn pending(&self) -> &[Key] {
+        &self.pending
+    }
+
+    /// When the pending keys will time out, if there are any. Pass this (less the current time)
+    /// to `App::poll_event`, and call `expire` when it comes back empty.
+    pub fn deadline(&self) -> Option<Instant> {
+        self.last.map(|last| last + self.timeout)
+    }
+
+    /// Handle a key press, returning any actions and keys that are now resolved.
+    pub fn feed(&mut self, key: Key) -> Vec<Chord<A>> {
+        let mut out = self.expire();
+        self.pending.push(key);
+        self.last = Some(Instant::now());
+        self.resolve(&mut out);
+        out
+    }
+
+    /// Give back the pending keys if they have timed out.
+    pub fn expire(&mut self) -> Vec<Chord<A>> {
+        match self.deadline() {
+            Some(deadline) if Instant::now() >= deadline => self.flush(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Give back the pending keys now, without waiting for the timeout.
+    pub fn flush(&mut self) -> Vec<Chord<A>> {
+        self.last = None;
+        self.pending.drain(..).map(Chord::Key).collect()
+    }
+
+    /// Match the pending keys against the keymap, until they are a prefix of some binding or
+    /// there are none left.
+    fn resolve(&mut self, out: &mut Vec<Chord<A>>) {
+        while !self.pending.is_empty() {
+            match self.keymap.lookup(&self.pending) {
+                KeyMatch::Action(action) => {
+                    out.push(Chord::Action(action.clone()));
+                    self.pending.clear();
+                }
+                KeyMatch::Prefix => return,
+                KeyMatch::None => {
+                    // The first key can't start a binding, but the ones after it might.
+                    out.push(Chord::Key(self.pending.remove(0)));
+                }
+            }
+        }
+        self.last = None;
+    }
+}
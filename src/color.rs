@@ -0,0 +1,168 @@
+use crate::screen::Color;
+use std::env;
+
+/// How many colors the terminal can display.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum ColorSupport {
+    /// No colors at all. All color escapes are left out of the output.
+    NoColor,
+    /// The 16 standard ANSI colors.
+    Ansi16,
+    /// The 256-color palette.
+    Indexed256,
+    /// 24-bit RGB color.
+    TrueColor,
+}
+
+impl ColorSupport {
+    /// Guess the color support of the terminal from the `COLORTERM` and `TERM` environment
+    /// variables.
+    ///
+    /// If `NO_COLOR` is set to a non-empty value, colors are disabled (see
+    /// <https://no-color.org>).
+    pub fn detect() -> ColorSupport {
+        if env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty()) {
+            return ColorSupport::NoColor;
+        }
+        let colorterm = env::var("COLORTERM").unwrap_or_default();
+        let term = env::var("TERM").unwrap_or_default();
+        ColorSupport::from_env(&colorterm, &term)
+    }
+
+    fn from_env(colorterm: &str, term: &str) -> ColorSupport {
+        if colorterm == "truecolor" || colorterm == "24bit" || term.ends_with("-direct") {
+            ColorSupport::TrueColor
+        } else if term.contains("256color") {
+            ColorSupport::Indexed256
+        } else {
+            ColorSupport::Ansi16
+        }
+    }
+}
+
+/// The 16 standard colors, with the RGB values xterm uses for them.
+const ANSI_COLORS: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::Red, (205, 0, 0)),
+    (Color::Green, (0, 205, 0)),
+    (Color::Yellow, (205, 205, 0)),
+    (Color::Blue, (0, 0, 238)),
+    (Color::Magenta, (205, 0, 205)),
+    (Color::Cyan, (0, 205, 205)),
+    (Color::White, (229, 229, 229)),
+    (Color::LightBlack, (127, 127, 127)),
+    (Color::LightRed, (255, 0, 0)),
+    (Color::LightGreen, (0, 255, 0)),
+    (Color::LightYellow, (255, 255, 0)),
+    (Color::LightBlue, (92, 92, 255)),
+    (Color::LightMagenta, (255, 0, 255)),
+    (Color::LightCyan, (0, 255, 255)),
+    (Color::LightWhite, (255, 255, 255)),
+];
+
+/// The levels used for each channel of the 6x6x6 color cube in the 256-color palette.
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+impl Color {
+    /// The closest color that a terminal with the given color support can display.
+    pub fn downgrade(self, support: ColorSupport) -> Color {
+        let color = match self {
+            Color::Rgba(r, g, b, _) => Color::Rgb(r, g, b),
+            color => color,
+        };
+        match (color, support) {
+            (_, ColorSupport::TrueColor) => color,
+            (_, ColorSupport::NoColor) => Color::Default,
+            (Color::Rgb(r, g, b), ColorSupport::Indexed256) => Color::Indexed(nearest_256(r, g, b)),
+            (Color::Rgb(r, g, b), ColorSupport::Ansi16) => nearest_16(r, g, b),
+            (Color::Indexed(idx), ColorSupport::Ansi16) => {
+                let (r, g, b) = indexed_to_rgb(idx);
+                nearest_16(r, g, b)
+            }
+            _ => color,
+        }
+    }
+
+    /// Blend this color over another, if it has an alpha channel.
+    ///
+    /// The terminal's default color is assumed to be `default`.
+    pub(crate) fn blend_over(self, below: Color, default: Color) -> Color {
+        let (r, g, b, a) = match self {
+            Color::Rgba(r, g, b, a) => (r, g, b, u16::from(a)),
+            color => return color,
+        };
+        let (br, bg, bb) = below.rgb().or_else(|| default.rgb()).unwrap_or((0, 0, 0));
+        let mix = |top: u8, bottom: u8| {
+            ((u16::from(top) * a + u16::from(bottom) * (255 - a) + 127) / 255) as u8
+        };
+        Color::Rgb(mix(r, br), mix(g, bg), mix(b, bb))
+    }
+}
+
+/// The RGB value of an entry in the 256-color palette.
+pub(crate) fn indexed_to_rgb(idx: u8) -> (u8, u8, u8) {
+    match idx {
+        0..=15 => ANSI_COLORS[idx as usize].1,
+        16..=231 => {
+            let idx = (idx - 16) as usize;
+            (
+                CUBE_LEVELS[idx / 36],
+                CUBE_LEVELS[idx / 6 % 6],
+                CUBE_LEVELS[idx % 6],
+            )
+        }
+        _ => {
+            let level = 8 + (idx - 232) * 10;
+            (level, level, level)
+        }
+    }
+}
+
+fn distance((r1, g1, b1): (u8, u8, u8), (r2, g2, b2): (u8, u8, u8)) -> u32 {
+    let d = |a: u8, b: u8| (i32::from(a) - i32::from(b)).unsigned_abs();
+    d(r1, r2).pow(2) + d(g1, g2).pow(2) + d(b1, b2).pow(2)
+}
+
+fn nearest_16(r: u8, g: u8, b: u8) -> Color {
+    ANSI_COLORS
+        .iter()
+        .min_by_key(|(_, rgb)| distance(*rgb, (r, g, b)))
+        .map(|(color, _)| *color)
+        .unwrap()
+}
+
+/// The nearest entry in the color cube or grayscale ramp. The first 16 entries are skipped
+/// because their exact values vary between terminals.
+fn nearest_256(r: u8, g: u8, b: u8) -> u8 {
+    let level = |c: u8| {
+        (0..CUBE_LEVELS.len())
+            .min_by_key(|&i| (i32::from(CUBE_LEVELS[i]) - i32::from(c)).unsigned_abs())
+            .unwrap()
+    };
+    let cube = 16 + 36 * level(r) + 6 * level(g) + level(b);
+    let avg = ((u32::from(r) + u32::from(g) + u32::from(b)) / 3) as u8;
+    let gray = 232 + (avg.saturating_sub(3) / 10).min(23) as usize;
+    let (cube, gray) = (cube as u8, gray as u8);
+    if distance(indexed_to_rgb(gray), (r, g, b)) < distance(indexed_to_rgb(cube), (r, g, b)) {
+        gray
+    } else {
+        cube
+    }
+}
+
+impl Color {
+    /// The RGB value of the color, or `None` for the terminal's default color.
+    ///
+    /// The 16 standard colors use the values xterm uses, but terminals differ.
+    pub(crate) fn rgb(self) -> Option<(u8, u8, u8)> {
+        match self {
+            Color::Default => None,
+            Color::Rgb(r, g, b) | Color::Rgba(r, g, b, _) => Some((r, g, b)),
+            Color::Indexed(idx) => Some(indexed_to_rgb(idx)),
+            named => ANSI_COLORS
+                .iter()
+                .find(|(color, _)| *color == named)
+                .map(|(_, rgb)| *rgb),
+        }
+    }
+}
@@ -0,0 +1,31 @@
+/// The shape of the terminal's cursor (see `App::set_cursor_shape`).
+///
+/// Not all terminals support every shape, or blinking.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CursorShape {
+    /// Whatever the user has configured the terminal to use.
+    #[default]
+    Default,
+    BlinkingBlock,
+    SteadyBlock,
+    BlinkingUnderline,
+    SteadyUnderline,
+    BlinkingBar,
+    SteadyBar,
+}
+
+impl CursorShape {
+    /// The DECSCUSR escape to switch to this shape.
+    pub(crate) fn sequence(self) -> &'static str {
+        match self {
+            CursorShape::Default => "\x1b[0 q",
+            CursorShape::BlinkingBlock => "\x1b[1 q",
+            CursorShape::SteadyBlock => "\x1b[2 q",
+            CursorShape::BlinkingUnderline => "\x1b[3 q",
+            CursorShape::SteadyUnderline => "\x1b[4 q",
+            CursorShape::BlinkingBar => "\x1b[5 q",
+            CursorShape::SteadyBar => "\x1b[6 q",
+        }
+    }
+}
@@ -0,0 +1,281 @@
+use crate::input::{next_boundary, previous_boundary};
+use crate::rect::Rect;
+use crate::screen::{Attributes, Color, Frame, Style};
+use termion::event::Key;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// A multi-line text editor, with a cursor and a scrolling viewport.
+///
+/// The text is stored as a list of lines, without their line endings.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct Editor {
+    lines: Vec<String>,
+    /// The line the cursor is on.
+    line: usize,
+    /// The byte index of the cursor in its line. This is always on a grapheme boundary.
+    index: usize,
+    /// The column the cursor tries to stay in when moving up and down.
+    goal: Option<usize>,
+    /// The first visible line.
+    top: usize,
+    /// The number of columns scrolled off the left edge.
+    left: usize,
+    /// The number of rows in the viewport when it was last drawn, used for Page Up and Down.
+    height: usize,
+    pub style: Style,
+    /// The style of the cell under the cursor.
+    pub cursor_style: Style,
+}
+
+impl Default for Editor {
+    fn default() -> Editor {
+        Editor::new()
+    }
+}
+
+impl Editor {
+    pub fn new() -> Editor {
+        Editor {
+            lines: vec![String::new()],
+            line: 0,
+            index: 0,
+            goal: None,
+            top: 0,
+            left: 0,
+            height: 1,
+            style: Style::default(),
+            cursor_style: Style::new(Color::Default, Color::Default, Attributes::REVERSE),
+        }
+    }
+
+    /// The text, with lines joined by `\n`.
+    pub fn text(&self) -> String {
+        self.lines.join("\n")
+    }
+
+    /// Replace the text, moving the cursor to the start.
+    pub fn set_text(&mut self, text: &str) {
+        self.lines = text.lines().map(String::from).collect();
+        if self.lines.is_empty() || text.ends_with('\n') {
+            self.lines.push(String::new());
+        }
+        self.line = 0;
+        self.index = 0;
+        self.goal = None;
+        self.top = 0;
+        self.left = 0;
+    }
+
+    pub fn lines(&self) -> &[String] {
+        &self.lines
+    }
+
+    /// The line the cursor is on, and the byte index of the cursor in that line.
+    pub fn cursor(&self) -> (usize, usize) {
+        (self.line, self.index)
+    }
+
+    /// Insert a character at the cursor. `\n` starts a new line.
+    pub fn insert(&mut self, ch: char) {
+        self.goal = None;
+        if ch == '\n' {
+            let rest = self.lines[self.line].split_off(self.index);
+            self.line += 1;
+            self.index = 0;
+            self.lines.insert(self.line, rest);
+            return;
+        }
+        let line = &mut self.lines[self.line];
+        line.insert(self.index, ch);
+        // A combining character joins the grapheme before it.
+        self.index = next_boundary(line, previous_boundary(line, self.index + ch.len_utf8()));
+    }
+
+    /// Insert some text at the cursor, leaving the cursor after it.
+    pub fn insert_str(&mut self, text: &str) {
+        for ch in text.chars().filter(|&ch| ch != '\r') {
+            self.insert(ch);
+        }
+    }
+
+    /// Delete the grapheme before the cursor, like Backspace. At the start of a line, the line
+    /// is joined onto the one above.
+    pub fn delete_backward(&mut self) {
+        self.goal = None;
+        if self.index == 0 {
+            if self.line > 0 {
+                let rest = self.lines.remove(self.line);
+                self.line -= 1;
+                self.index = self.lines[self.line].len();
+                self.lines[self.line].push_str(&rest);
+            }
+            return;
+        }
+        let line = &mut self.lines[self.line];
+        let start = previous_boundary(line, self.index);
+        line.replace_range(start..self.index, "");
+        self.index = start;
+    }
+
+    /// Delete the grapheme after the cursor, like Delete. At the end of a line, the line below
+    /// is joined onto it.
+    pub fn delete_forward(&mut self) {
+        self.goal = None;
+        let line = &mut self.lines[self.line];
+        if self.index < line.len() {
+            let end = next_boundary(line, self.index);
+            line.replace_range(self.index..end, "");
+        } else if self.line + 1 < self.lines.len() {
+            let rest = self.lines.remove(self.line + 1);
+            self.lines[self.line].push_str(&rest);
+        }
+    }
+
+    /// Move the cursor back one grapheme, onto the end of the previous line if needed.
+    pub fn move_left(&mut self) {
+        self.goal = None;
+        if self.index > 0 {
+            self.index = previous_boundary(&self.lines[self.line], self.index);
+        } else if self.line > 0 {
+            self.line -= 1;
+            self.index = self.lines[self.line].len();
+        }
+    }
+
+    /// Move the cursor forward one grapheme, onto the start of the next line if needed.
+    pub fn move_right(&mut self) {
+        self.goal = None;
+        if self.index < self.lines[self.line].len() {
+            self.index = next_boundary(&self.lines[self.line], self.index);
+        } else if self.line + 1 < self.lines.len() {
+            self.line += 1;
+            self.index = 0;
+        }
+    }
+
+    /// Move the cursor up some lines, keeping it in the same column where possible.
+    pub fn move_up(&mut self, lines: usize) {
+        self.move_to_line(self.line.saturating_sub(lines));
+    }
+
+    /// Move the cursor down some lines, keeping it in the same column where possible.
+    pub fn move_down(&mut self, lines: usize) {
+        self.move_to_line((self.line + lines).min(self.lines.len() - 1));
+    }
+
+    fn move_to_line(&mut self, line: usize) {
+        let goal = match self.goal {
+            Some(goal) => goal,
+            None => self.lines[self.line][..self.index].width(),
+        };
+        self.goal = Some(goal);
+        self.line = line;
+        let mut col = 0;
+        self.index = self.lines[line]
+            .grapheme_indices(true)
+            .find(|(_, glyph)| {
+                col += glyph.width();
+                col > goal
+            })
+            .map_or(self.lines[line].len(), |(index, _)| index);
+    }
+
+    /// Move the cursor to the start of its line.
+    pub fn move_home(&mut self) {
+        self.goal = None;
+        self.index = 0;
+    }
+
+    /// Move the cursor to the end of its line.
+    pub fn move_end(&mut self) {
+        self.goal = None;
+        self.index = self.lines[self.line].len();
+    }
+
+    /// Edit the text in response to a key press.
+    ///
+    /// Handles printable characters, Enter, Tab, Backspace, Delete, the arrow keys, Home, End,
+    /// Page Up and Page Down. Returns `false` if the key wasn't used.
+    pub fn handle_key(&mut self, key: Key) -> bool {
+        match key {
+            Key::Char(ch) if ch == '\n' || ch == '\t' || !ch.is_control() => self.insert(ch),
+            Key::Backspace => self.delete_backward(),
+            Key::Delete => self.delete_forward(),
+            Key::Left => self.move_left(),
+            Key::Right => self.move_right(),
+            Key::Up => self.move_up(1),
+            Key::Down => self.move_down(1),
+            Key::PageUp => self.move_up(self.height.max(1)),
+            Key::PageDown => self.move_down(self.height.max(1)),
+            Key::Home => self.move_home(),
+            Key::End => self.move_end(),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Draw the visible part of the text into a region, scrolling to keep the cursor in view.
+    pub fn render_into(&mut self, frame: &mut Frame, region: Rect) {
+        let region = region.intersection(frame.area());
+        frame.fill_rect(
+            region.row,
+            region.col,
+            region.height,
+            region.width,
+            self.style.char(' '),
+        );
+        if region.is_empty() {
+            return;
+        }
+        self.height = region.height;
+        self.scroll_to_cursor(region);
+        let lines = self.lines.iter().skip(self.top);
+        for (row, line) in (region.row..region.bottom()).zip(lines) {
+            let mut pos = 0;
+            let chars = line.graphemes(true).filter_map(|glyph| {
+                let start = pos;
+                pos += glyph.width();
+                if pos <= self.left {
+                    None
+                } else if start < self.left {
+                    // A wide glyph cut in half by the left edge.
+                    Some(self.style.char(' '))
+                } else {
+                    Some(self.style.char(glyph))
+                }
+            });
+            frame.write_run(row, region.col, region.right(), chars);
+        }
+        let row = region.row + self.line - self.top;
+        let col = region.col + self.lines[self.line][..self.index].width() - self.left;
+        let mut cell = frame.get(row, col);
+        if self.cursor_style.color_fg != Color::Default {
+            cell.color_fg = self.cursor_style.color_fg;
+        }
+        if self.cursor_style.color_bg != Color::Default {
+            cell.color_bg = self.cursor_style.color_bg;
+        }
+        cell.attrs.insert(self.cursor_style.attrs);
+        frame.set_clipped(row, col, cell);
+    }
+
+    fn scroll_to_cursor(&mut self, region: Rect) {
+        if self.line < self.top {
+            self.top = self.line;
+        } else if self.line >= self.top + region.height {
+            self.top = self.line + 1 - region.height;
+        }
+        let line = &self.lines[self.line];
+        let col = line[..self.index].width();
+        let end = col
+            + line[self.index..next_boundary(line, self.index)]
+                .width()
+                .max(1);
+        if col < self.left {
+            self.left = col;
+        } else if end > self.left + region.width {
+            self.left = end.saturating_sub(region.width).min(col);
+        }
+    }
+}
@@ -0,0 +1,73 @@
+use std::fmt;
+use std::io;
+
+/// An error from building an app, drawing, or reading events.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// Reading from or writing to the terminal failed.
+    Io(io::Error),
+    /// Standard input or output isn't a terminal, e.g. because it has been redirected to a file
+    /// or a pipe.
+    NotATty,
+    /// The terminal is smaller than the size set with `AppBuilder::min_size`.
+    TerminalTooSmall {
+        cols: usize,
+        rows: usize,
+        min_cols: usize,
+        min_rows: usize,
+    },
+    /// The terminal can't move the cursor, so can't be drawn on. Holds the value of `TERM`.
+    ///
+    /// This can be overridden by giving the capabilities with `AppBuilder::capabilities`.
+    UnsupportedTerminal(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "terminal I/O error: {}", e),
+            Error::NotATty => f.write_str("standard input or output is not a terminal"),
+            Error::TerminalTooSmall {
+                cols,
+                rows,
+                min_cols,
+                min_rows,
+            } => write!(
+                f,
+                "the terminal is {}x{}, but needs to be at least {}x{}",
+                cols, rows, min_cols, min_rows
+            ),
+            Error::UnsupportedTerminal(term) if term.is_empty() => {
+                f.write_str("TERM is not set, so the terminal can't be drawn on")
+            }
+            Error::UnsupportedTerminal(term) => {
+                write!(f, "the terminal (TERM={}) can't be drawn on", term)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Error {
+        Error::Io(e)
+    }
+}
+
+impl From<Error> for io::Error {
+    fn from(e: Error) -> io::Error {
+        match e {
+            Error::Io(e) => e,
+            e => io::Error::other(e),
+        }
+    }
+}
@@ -0,0 +1,113 @@
+use crate::kitty::{self, KeyEvent};
+use crate::osc;
+use termion::event::{Key, MouseButton, MouseEvent};
+
+/// An event received from the terminal.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Event {
+    /// A key press.
+    Key(Key),
+    /// A key press, repeat or release, with all the modifiers held.
+    ///
+    /// These are only received if the kitty keyboard protocol is enabled with
+    /// `AppBuilder::kitty_keyboard`, and the terminal supports it. Some keys may still arrive
+    /// as `Event::Key`; use `Event::key_event` to handle both the same way.
+    KeyEvent(KeyEvent),
+    /// A mouse button press, release or wheel use at specific coordinates.
+    ///
+    /// These are only received if mouse capture is enabled with `AppBuilder::mouse`.
+    Mouse(Mouse),
+    /// An event that cannot currently be evaluated.
+    Unsupported(Vec<u8>),
+    /// The terminal was resized to the given number of columns and rows.
+    Resize(usize, usize),
+    /// Sent at a fixed rate if `AppBuilder::tick_rate` is set.
+    Tick,
+    /// The process is about to be suspended (e.g. the user ran `kill -TSTP`).
+    ///
+    /// The suspension happens on the next call to `App::events` or one of the `App` methods
+    /// that wait for events, so there is a chance to react first.
+    Suspend,
+    /// The process has been continued after being suspended. The next frame will be fully
+    /// redrawn.
+    Resume,
+    /// The contents of the clipboard, in reply to `App::request_clipboard`.
+    Clipboard(String),
+    /// The terminal window gained focus.
+    ///
+    /// These are only received if focus reporting is enabled with `AppBuilder::focus_events`.
+    FocusGained,
+    /// The terminal window lost focus.
+    FocusLost,
+}
+
+impl Event {
+    /// The key event, for both `Event::Key` (as a press) and `Event::KeyEvent`.
+    pub fn key_event(&self) -> Option<KeyEvent> {
+        match self {
+            Event::Key(key) => Some(KeyEvent::from(*key)),
+            Event::KeyEvent(key) => Some(*key),
+            _ => None,
+        }
+    }
+}
+
+/// Sent by the terminal when it gains or loses focus, if focus reporting is on.
+pub(crate) const FOCUS_IN: &[u8] = b"\x1b[I";
+pub(crate) const FOCUS_OUT: &[u8] = b"\x1b[O";
+
+impl From<termion::event::Event> for Event {
+    fn from(evt: termion::event::Event) -> Event {
+        match evt {
+            termion::event::Event::Key(key) => Event::Key(key),
+            termion::event::Event::Mouse(mouse) => Event::Mouse(Mouse::from(mouse)),
+            termion::event::Event::Unsupported(bytes) => match &bytes[..] {
+                FOCUS_IN => Event::FocusGained,
+                FOCUS_OUT => Event::FocusLost,
+                _ => {
+                    if let Some(text) = osc::parse_clipboard_reply(&bytes) {
+                        Event::Clipboard(text)
+                    } else if let Some(key) = kitty::parse(&bytes) {
+                        Event::KeyEvent(key)
+                    } else {
+                        Event::Unsupported(bytes)
+                    }
+                }
+            },
+        }
+    }
+}
+
+/// A mouse event. The position is 0-based and matches the rows and columns of `Frame`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Mouse {
+    pub action: MouseAction,
+    pub row: usize,
+    pub col: usize,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum MouseAction {
+    /// A mouse button was pressed (this includes the wheel being scrolled).
+    Press(MouseButton),
+    /// A mouse button was released.
+    Release,
+    /// The mouse was moved with a button held down.
+    Hold,
+}
+
+impl From<MouseEvent> for Mouse {
+    fn from(evt: MouseEvent) -> Mouse {
+        let (action, col, row) = match evt {
+            MouseEvent::Press(button, col, row) => (MouseAction::Press(button), col, row),
+            MouseEvent::Release(col, row) => (MouseAction::Release, col, row),
+            MouseEvent::Hold(col, row) => (MouseAction::Hold, col, row),
+        };
+        // Terminal coordinates are 1-based.
+        Mouse {
+            action,
+            row: usize::from(row.saturating_sub(1)),
+            col: usize::from(col.saturating_sub(1)),
+        }
+    }
+}
@@ -0,0 +1,380 @@
+use crate::glyph::Glyph;
+use crate::link::Link;
+use crate::screen::{Attributes, Char, Color, Frame, Style};
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read};
+use std::path::Path;
+
+/// Magic bytes at the start of a saved frame.
+const FRAME_MAGIC: &[u8; 4] = b"TBFR";
+/// The version of the saved frame format.
+const FRAME_VERSION: u8 = 3;
+/// The most cells a saved frame can have, so a corrupt header can't make us allocate a huge
+/// frame. This is far more than any real terminal has.
+const MAX_FRAME_CELLS: usize = 1 << 24;
+
+impl Frame {
+    /// Write the frame as text with SGR color and style escapes, but no cursor movement.
+    ///
+    /// The output can be `cat`-ed to a terminal or saved as a `.ans` screenshot. Each row is
+    /// followed by a newline, and all styles are reset at the end of each row.
+    pub fn write_ansi(&self, writer: &mut impl io::Write) -> io::Result<()> {
+        use termion::style::Reset;
+        for row in 0..self.rows() {
+            let mut prev = Style::default();
+            for col in 0..self.columns() {
+                let ch = self.get(row, col);
+                if ch.is_continuation() {
+                    continue;
+                }
+                if ch.color_fg != prev.color_fg {
+                    ch.color_fg.write_fg(writer)?;
+                }
+                if ch.color_bg != prev.color_bg {
+                    ch.color_bg.write_bg(writer)?;
+                }
+                if ch.attrs != prev.attrs {
+                    ch.attrs.write_change(prev.attrs, writer)?;
+                }
+                if ch.link != prev.link {
+                    Link::write_change(prev.link.as_ref(), ch.link.as_ref(), writer)?;
+                }
+                prev = ch.style();
+                write!(writer, "{}", ch.glyph)?;
+            }
+            Link::write_change(prev.link.as_ref(), None, writer)?;
+            if prev != Style::default() {
+                write!(writer, "{}", Reset)?;
+            }
+            writeln!(writer)?;
+        }
+        Ok(())
+    }
+
+    /// Write the text of the frame, without any colors or styles.
+    ///
+    /// Each row is followed by a newline, with any spaces at the end of it left out.
+    pub fn write_text(&self, writer: &mut impl io::Write) -> io::Result<()> {
+        for row in 0..self.rows() {
+            let text: String = self
+                .row(row)
+                .iter()
+                .filter(|ch| !ch.is_continuation())
+                .map(|ch| ch.glyph.as_str())
+                .collect();
+            writeln!(writer, "{}", text.trim_end_matches(' '))?;
+        }
+        Ok(())
+    }
+
+    /// Save the frame to a file, in a simple binary format that can be read back with
+    /// `Frame::load`.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_to(&mut writer)?;
+        io::Write::flush(&mut writer)
+    }
+
+    /// Load a frame saved with `Frame::save`.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Frame> {
+        Frame::read_from(&mut BufReader::new(File::open(path)?))
+    }
+
+    /// Write the frame in the format used by `Frame::save`.
+    pub fn write_to(&self, writer: &mut impl io::Write) -> io::Result<()> {
+        writer.write_all(FRAME_MAGIC)?;
+        writer.write_all(&[FRAME_VERSION])?;
+        writer.write_all(&(self.rows() as u32).to_le_bytes())?;
+        writer.write_all(&(self.columns() as u32).to_le_bytes())?;
+        for row in 0..self.rows() {
+            for col in 0..self.columns() {
+                let ch = self.get(row, col);
+                let glyph = ch.glyph.as_str();
+                writer.write_all(&[glyph.len() as u8])?;
+                writer.write_all(glyph.as_bytes())?;
+                write_color(writer, ch.color_fg)?;
+                write_color(writer, ch.color_bg)?;
+                writer.write_all(&[ch.attrs.bits()])?;
+                // Links are saved as the length of the URL (0 for none), then the URL.
+                let url = ch.link.as_ref().map_or("", Link::url);
+                writer.write_all(&(url.len() as u32).to_le_bytes())?;
+                writer.write_all(url.as_bytes())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Read a frame in the format used by `Frame::save`.
+    pub fn read_from(reader: &mut impl Read) -> io::Result<Frame> {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != FRAME_MAGIC {
+            return Err(invalid_data("not a saved frame"));
+        }
+        if read_u8(reader)? != FRAME_VERSION {
+            return Err(invalid_data("unsupported frame version"));
+        }
+        let rows = read_u32(reader)? as usize;
+        let cols = read_u32(reader)? as usize;
+        if rows
+            .checked_mul(cols)
+            .is_none_or(|cells| cells > MAX_FRAME_CELLS)
+        {
+            return Err(invalid_data("the saved frame is too big"));
+        }
+        let mut frame = Frame::new(rows, cols);
+        for row in 0..rows {
+            for col in 0..cols {
+                let mut glyph = vec![0; usize::from(read_u8(reader)?)];
+                reader.read_exact(&mut glyph)?;
+                let glyph = std::str::from_utf8(&glyph)
+                    .ok()
+                    .and_then(|glyph| match glyph {
+                        "" => Some(Glyph::EMPTY),
+                        glyph => Glyph::new(glyph),
+                    })
+                    .ok_or_else(|| invalid_data("invalid glyph"))?;
+                let color_fg = read_color(reader)?;
+                let color_bg = read_color(reader)?;
+                let attrs = Attributes::from_bits(read_u8(reader)?);
+                let len = u64::from(read_u32(reader)?);
+                let mut url = Vec::new();
+                reader.by_ref().take(len).read_to_end(&mut url)?;
+                if url.len() as u64 != len {
+                    return Err(io::ErrorKind::UnexpectedEof.into());
+                }
+                let url = String::from_utf8(url).map_err(|_| invalid_data("invalid link"))?;
+                let link = Some(url)
+                    .filter(|url| !url.is_empty())
+                    .map(|url| Link::new(&url));
+                // Continuation cells are filled in when the wide glyph to their left is set.
+                if glyph.is_nul() {
+                    continue;
+                }
+                frame.set(
+                    row,
+                    col,
+                    Char {
+                        glyph,
+                        color_fg,
+                        color_bg,
+                        attrs,
+                        link,
+                    },
+                );
+            }
+        }
+        Ok(frame)
+    }
+
+    /// Render the frame as a standalone HTML `<pre>` element, with colors and attributes as
+    /// inline styles, and links as `<a>` elements.
+    ///
+    /// The default foreground and background colors are left to the surrounding page, except
+    /// where reverse video needs them, where light gray on black is assumed.
+    pub fn to_html(&self) -> String {
+        let mut html = String::from("<pre style=\"font-family: monospace\">");
+        for row in 0..self.rows() {
+            if row > 0 {
+                html.push('\n');
+            }
+            let mut col = 0;
+            while col < self.columns() {
+                let style = self.get(row, col).style();
+                let css = css(&style);
+                if let Some(link) = &style.link {
+                    html.push_str("<a href=\"");
+                    push_escaped(&mut html, link.url());
+                    html.push_str("\">");
+                }
+                if !css.is_empty() {
+                    write!(html, "<span style=\"{}\">", css).unwrap();
+                }
+                while col < self.columns() && self.get(row, col).style() == style {
+                    let ch = self.get(row, col);
+                    if !ch.is_continuation() {
+                        push_escaped(&mut html, ch.glyph.as_str());
+                    }
+                    col += 1;
+                }
+                if !css.is_empty() {
+                    html.push_str("</span>");
+                }
+                if style.link.is_some() {
+                    html.push_str("</a>");
+                }
+            }
+        }
+        html.push_str("</pre>");
+        html
+    }
+}
+
+/// Inline CSS for a style.
+fn css(style: &Style) -> String {
+    let (mut fg, mut bg) = (style.color_fg.rgb(), style.color_bg.rgb());
+    if style.attrs.contains(Attributes::REVERSE) {
+        let default_fg = Color::White.rgb();
+        let default_bg = Color::Black.rgb();
+        let (new_fg, new_bg) = (bg.or(default_bg), fg.or(default_fg));
+        fg = new_fg;
+        bg = new_bg;
+    }
+    let mut css = String::new();
+    if let Some((r, g, b)) = fg {
+        write!(css, "color: #{:02x}{:02x}{:02x}; ", r, g, b).unwrap();
+    }
+    if let Some((r, g, b)) = bg {
+        write!(css, "background-color: #{:02x}{:02x}{:02x}; ", r, g, b).unwrap();
+    }
+    if style.attrs.contains(Attributes::BOLD) {
+        css.push_str("font-weight: bold; ");
+    }
+    if style.attrs.contains(Attributes::DIM) {
+        css.push_str("opacity: 0.5; ");
+    }
+    if style.attrs.contains(Attributes::ITALIC) {
+        css.push_str("font-style: italic; ");
+    }
+    match (
+        style.attrs.contains(Attributes::UNDERLINE),
+        style.attrs.contains(Attributes::STRIKETHROUGH),
+    ) {
+        (true, true) => css.push_str("text-decoration: underline line-through; "),
+        (true, false) => css.push_str("text-decoration: underline; "),
+        (false, true) => css.push_str("text-decoration: line-through; "),
+        (false, false) => (),
+    }
+    css.truncate(css.trim_end().len());
+    css
+}
+
+fn push_escaped(html: &mut String, glyph: &str) {
+    for c in glyph.chars() {
+        match c {
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            '&' => html.push_str("&amp;"),
+            '"' => html.push_str("&quot;"),
+            c => html.push(c),
+        }
+    }
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+fn read_u8(reader: &mut impl Read) -> io::Result<u8> {
+    let mut buf = [0; 1];
+    reader.read_exact(&mut buf)?;
+    Ok(buf[0])
+}
+
+fn read_u32(reader: &mut impl Read) -> io::Result<u32> {
+    let mut buf = [0; 4];
+    reader.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+/// The named colors, in the order of their tags in the saved format.
+const NAMED_COLORS: [Color; 17] = [
+    Color::Default,
+    Color::Black,
+    Color::Blue,
+    Color::Cyan,
+    Color::Green,
+    Color::LightBlack,
+    Color::LightBlue,
+    Color::LightCyan,
+    Color::LightGreen,
+    Color::LightMagenta,
+    Color::LightRed,
+    Color::LightWhite,
+    Color::LightYellow,
+    Color::Magenta,
+    Color::Red,
+    Color::White,
+    Color::Yellow,
+];
+const TAG_INDEXED: u8 = 0x80;
+const TAG_RGB: u8 = 0x81;
+const TAG_RGBA: u8 = 0x82;
+
+/// Colors are saved as a tag byte, followed by the palette index or RGB channels if needed.
+fn write_color(writer: &mut impl io::Write, color: Color) -> io::Result<()> {
+    match color {
+        Color::Indexed(idx) => writer.write_all(&[TAG_INDEXED, idx]),
+        Color::Rgb(r, g, b) => writer.write_all(&[TAG_RGB, r, g, b]),
+        Color::Rgba(r, g, b, a) => writer.write_all(&[TAG_RGBA, r, g, b, a]),
+        named => {
+            let tag = NAMED_COLORS.iter().position(|c| *c == named).unwrap();
+            writer.write_all(&[tag as u8])
+        }
+    }
+}
+
+fn read_color(reader: &mut impl Read) -> io::Result<Color> {
+    match read_u8(reader)? {
+        TAG_INDEXED => Ok(Color::Indexed(read_u8(reader)?)),
+        TAG_RGB => Ok(Color::Rgb(
+            read_u8(reader)?,
+            read_u8(reader)?,
+            read_u8(reader)?,
+        )),
+        TAG_RGBA => Ok(Color::Rgba(
+            read_u8(reader)?,
+            read_u8(reader)?,
+            read_u8(reader)?,
+            read_u8(reader)?,
+        )),
+        tag if usize::from(tag) < NAMED_COLORS.len() => Ok(NAMED_COLORS[usize::from(tag)]),
+        _ => Err(invalid_data("invalid color")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn saved(frame: &Frame) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        frame.write_to(&mut bytes).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn saved_frames_round_trip() {
+        let mut frame = Frame::new(2, 3);
+        frame.set(0, 1, Char::new('中'));
+        let mut ch = Char::new('x');
+        ch.link = Some(Link::new("https://example.com"));
+        frame.set(1, 2, ch);
+        let read = Frame::read_from(&mut &saved(&frame)[..]).unwrap();
+        assert_eq!(read, frame);
+    }
+
+    #[test]
+    fn oversized_headers_are_rejected() {
+        let mut bytes = FRAME_MAGIC.to_vec();
+        bytes.push(FRAME_VERSION);
+        bytes.extend_from_slice(&u32::MAX.to_le_bytes());
+        bytes.extend_from_slice(&u32::MAX.to_le_bytes());
+        let err = Frame::read_from(&mut &bytes[..]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn truncated_frames_are_rejected() {
+        let mut frame = Frame::new(1, 1);
+        let mut ch = Char::new('x');
+        ch.link = Some(Link::new("https://example.com"));
+        frame.set(0, 0, ch);
+        let bytes = saved(&frame);
+        for len in 0..bytes.len() {
+            let err = Frame::read_from(&mut &bytes[..len]).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof, "at {}", len);
+        }
+    }
+}
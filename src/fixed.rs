@@ -0,0 +1,78 @@
+use crate::rect::Rect;
+use crate::screen::{Char, Frame};
+
+/// A frame of a fixed size that is drawn in the middle of the terminal, whatever its size (see
+/// `AppBuilder::fixed_size`).
+#[derive(Debug, Clone)]
+pub(crate) struct FixedSize {
+    pub(crate) frame: Frame,
+    /// Fills the terminal around the frame.
+    pub(crate) letterbox: Char,
+}
+
+impl FixedSize {
+    /// The part of the frame that fits in a terminal of the given size, and where it goes on
+    /// the terminal. If the terminal is too small, the middle of the frame is shown.
+    pub(crate) fn viewport(&self, rows: usize, cols: usize) -> (Rect, Rect) {
+        let (src_row, dst_row, height) = center(self.frame.rows(), rows);
+        let (src_col, dst_col, width) = center(self.frame.columns(), cols);
+        (
+            Rect::new(src_row, src_col, height, width),
+            Rect::new(dst_row, dst_col, height, width),
+        )
+    }
+
+    /// Draw the frame into the middle of a frame the size of the terminal.
+    pub(crate) fn draw(&self, target: &mut Frame) {
+        let (src, dst) = self.viewport(target.rows(), target.columns());
+        target.fill_rect(
+            0,
+            0,
+            target.rows(),
+            target.columns(),
+            self.letterbox.clone(),
+        );
+        target.fill_rect(dst.row, dst.col, dst.height, dst.width, Char::default());
+        target.blit_rect(&self.frame, src, dst.row, dst.col);
+    }
+
+    /// Where a cell of the frame is on the terminal, if it is shown.
+    pub(crate) fn to_terminal(
+        &self,
+        (row, col): (usize, usize),
+        rows: usize,
+        cols: usize,
+    ) -> Option<(usize, usize)> {
+        let (src, dst) = self.viewport(rows, cols);
+        if src.contains(row, col) {
+            Some((row - src.row + dst.row, col - src.col + dst.col))
+        } else {
+            None
+        }
+    }
+
+    /// Which cell of the frame is at a position on the terminal, if any.
+    pub(crate) fn to_frame(
+        &self,
+        (row, col): (usize, usize),
+        rows: usize,
+        cols: usize,
+    ) -> Option<(usize, usize)> {
+        let (src, dst) = self.viewport(rows, cols);
+        if dst.contains(row, col) {
+            Some((row - dst.row + src.row, col - dst.col + src.col))
+        } else {
+            None
+        }
+    }
+}
+
+/// Fit a length of `size` into `available`, returning the start in the source, the start in
+/// the destination, and the length that fits.
+fn center(size: usize, available: usize) -> (usize, usize, usize) {
+    if size <= available {
+        (0, (available - size) / 2, size)
+    } else {
+        ((size - available) / 2, 0, available)
+    }
+}
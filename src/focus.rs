@@ -0,0 +1,194 @@
+use crate::event::{Event, Mouse, MouseAction};
+use crate::rect::Rect;
+use termion::event::Key;
+
+/// A change of keyboard focus, from one component to another. `None` means nothing had (or
+/// has) focus.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct FocusChange<Id> {
+    pub from: Option<Id>,
+    pub to: Option<Id>,
+}
+
+/// Keeps track of which component has keyboard focus.
+///
+/// Components are identified by an id of your choosing, and are focused in the order they are
+/// added by Tab and Shift-Tab. If a component is given an area with `set_area`, clicking on it
+/// focuses it too.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct FocusManager<Id> {
+    /// The components in tab order, with the area each was last drawn in.
+    entries: Vec<(Id, Option<Rect>)>,
+    /// The index of the focused component in `entries`.
+    focused: Option<usize>,
+    changes: Vec<FocusChange<Id>>,
+}
+
+impl<Id: Clone + Eq> Default for FocusManager<Id> {
+    fn default() -> FocusManager<Id> {
+        FocusManager::new()
+    }
+}
+
+impl<Id: Clone + Eq> FocusManager<Id> {
+    pub fn new() -> FocusManager<Id> {
+        FocusManager {
+            entries: Vec::new(),
+            focused: None,
+            changes: Vec::new(),
+        }
+    }
+
+    /// Add a component to the end of the tab order. Nothing happens if it is already there.
+    ///
+    /// The first component added is focused.
+    pub fn add(&mut self, id: Id) {
+        if self.position(&id).is_none() {
+            self.entries.push((id, None));
+            if self.focused.is_none() {
+                self.set_focused(Some(self.entries.len() - 1));
+            }
+        }
+    }
+
+    /// Remove a component. If it had focus, the next one is focused instead.
+    pub fn remove(&mut self, id: &Id) {
+        let index = match self.position(id) {
+            Some(index) => index,
+            None => return,
+        };
+        match self.focused {
+            Some(focused) if focused == index => {
+                let from = self.entries.remove(index).0;
+                self.focused = if self.entries.is_empty() {
+                    None
+                } else {
+                    Some(index % self.entries.len())
+                };
+                let to = self.focused();
+                self.changes.push(FocusChange {
+                    from: Some(from),
+                    to: to.cloned(),
+                });
+            }
+            Some(focused) if focused > index => {
+                self.entries.remove(index);
+                self.focused = Some(focused - 1);
+            }
+            _ => {
+                self.entries.remove(index);
+            }
+        }
+    }
+
+    /// Set the area a component was drawn in, so it can be focused by clicking on it.
+    pub fn set_area(&mut self, id: &Id, area: Rect) {
+        if let Some(index) = self.position(id) {
+            self.entries[index].1 = Some(area);
+        }
+    }
+
+    /// The component with focus.
+    pub fn focused(&self) -> Option<&Id> {
+        self.focused.map(|index| &self.entries[index].0)
+    }
+
+    pub fn is_focused(&self, id: &Id) -> bool {
+        self.focused() == Some(id)
+    }
+
+    /// Give a component focus. Returns `false` if it hasn't been added.
+    pub fn focus(&mut self, id: &Id) -> bool {
+        match self.position(id) {
+            Some(index) => {
+                self.set_focused(Some(index));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Take focus away from every component.
+    pub fn blur(&mut self) {
+        self.set_focused(None);
+    }
+
+    /// Move focus to the next component, wrapping around to the first.
+    pub fn focus_next(&mut self) {
+        if !self.entries.is_empty() {
+            let next = self
+                .focused
+                .map_or(0, |index| (index + 1) % self.entries.len());
+            self.set_focused(Some(next));
+        }
+    }
+
+    /// Move focus to the previous component, wrapping around to the last.
+    pub fn focus_previous(&mut self) {
+        let len = self.entries.len();
+        if len > 0 {
+            let previous = self
+                .focused
+                .map_or(len - 1, |index| (index + len - 1) % len);
+            self.set_focused(Some(previous));
+        }
+    }
+
+    /// Focus the component whose area contains the given cell. Returns `false` if there isn't
+    /// one.
+    pub fn focus_at(&mut self, row: usize, col: usize) -> bool {
+        let found = self
+            .entries
+            .iter()
+            .position(|(_, area)| area.is_some_and(|area| area.contains(row, col)));
+        if found.is_some() {
+            self.set_focused(found);
+        }
+        found.is_some()
+    }
+
+    /// The changes of focus since this was last called, oldest first.
+    pub fn take_changes(&mut self) -> Vec<FocusChange<Id>> {
+        std::mem::take(&mut self.changes)
+    }
+
+    /// Handle an event, and return the component it should be passed on to, if any.
+    ///
+    /// Presses of Tab and Shift-Tab move the focus, and a mouse press focuses the component under it;
+    /// these aren't passed on. Other key presses are passed on to the focused component. Any
+    /// other events are not for a particular component, so `None` is returned.
+    pub fn dispatch(&mut self, event: &Event) -> Option<Id> {
+        match event {
+            Event::Key(Key::Char('\t')) => self.focus_next(),
+            Event::Key(Key::BackTab) => self.focus_previous(),
+            Event::Key(_) => return self.focused().cloned(),
+            Event::KeyEvent(key) => match key.to_key() {
+                Some(Key::Char('\t')) => self.focus_next(),
+                Some(Key::BackTab) => self.focus_previous(),
+                _ => return self.focused().cloned(),
+            },
+            Event::Mouse(Mouse {
+                action: MouseAction::Press(_),
+                row,
+                col,
+            }) => {
+                self.focus_at(*row, *col);
+            }
+            _ => (),
+        }
+        None
+    }
+
+    fn position(&self, id: &Id) -> Option<usize> {
+        self.entries.iter().position(|(entry, _)| entry == id)
+    }
+
+    fn set_focused(&mut self, focused: Option<usize>) {
+        if focused != self.focused {
+            let from = self.focused().cloned();
+            self.focused = focused;
+            let to = self.focused().cloned();
+            self.changes.push(FocusChange { from, to });
+        }
+    }
+}
@@ -0,0 +1,282 @@
+//! A small bitmap font, for drawing frames as images (see `FrameRecording::write_gif`).
+//!
+//! Printable ASCII comes from a table of 5x7 glyphs. Box drawing, block elements and braille
+//! are drawn from their shapes so that borders and canvases join up across cells, and
+//! anything else is drawn as a hollow box.
+
+/// The width of a cell in pixels.
+pub(crate) const CELL_WIDTH: usize = 6;
+/// The height of a cell in pixels.
+pub(crate) const CELL_HEIGHT: usize = 10;
+
+/// The row of a cell that ASCII glyphs start on.
+const GLYPH_TOP: usize = 1;
+
+/// The glyphs of printable ASCII, from `' '` to `'~'`, as 7 rows of 5 pixels. The highest bit
+/// of each row is the leftmost pixel.
+#[rustfmt::skip]
+const ASCII: [[u8; 7]; 95] = [
+    [0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000], // ' '
+    [0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00000, 0b00100], // '!'
+    [0b01010, 0b01010, 0b01010, 0b00000, 0b00000, 0b00000, 0b00000], // '"'
+    [0b01010, 0b01010, 0b11111, 0b01010, 0b11111, 0b01010, 0b01010], // '#'
+    [0b00100, 0b01111, 0b10100, 0b01110, 0b00101, 0b11110, 0b00100], // '$'
+    [0b11000, 0b11001, 0b00010, 0b00100, 0b01000, 0b10011, 0b00011], // '%'
+    [0b01100, 0b10010, 0b10100, 0b01000, 0b10101, 0b10010, 0b01101], // '&'
+    [0b01100, 0b00100, 0b01000, 0b00000, 0b00000, 0b00000, 0b00000], // "'"
+    [0b00010, 0b00100, 0b01000, 0b01000, 0b01000, 0b00100, 0b00010], // '('
+    [0b01000, 0b00100, 0b00010, 0b00010, 0b00010, 0b00100, 0b01000], // ')'
+    [0b00000, 0b00100, 0b10101, 0b01110, 0b10101, 0b00100, 0b00000], // '*'
+    [0b00000, 0b00100, 0b00100, 0b11111, 0b00100, 0b00100, 0b00000], // '+'
+    [0b00000, 0b00000, 0b00000, 0b00000, 0b01100, 0b00100, 0b01000], // ','
+    [0b00000, 0b00000, 0b00000, 0b11111, 0b00000, 0b00000, 0b00000], // '-'
+    [0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b01100, 0b01100], // '.'
+    [0b00000, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0b00000], // '/'
+    [0b01110, 0b10001, 0b10011, 0b10101, 0b11001, 0b10001, 0b01110], // '0'
+    [0b00100, 0b01100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110], // '1'
+    [0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b01000, 0b11111], // '2'
+    [0b11111, 0b00010, 0b00100, 0b00010, 0b00001, 0b10001, 0b01110], // '3'
+    [0b00010, 0b00110, 0b01010, 0b10010, 0b11111, 0b00010, 0b00010], // '4'
+    [0b11111, 0b10000, 0b11110, 0b00001, 0b00001, 0b10001, 0b01110], // '5'
+    [0b00110, 0b01000, 0b10000, 0b11110, 0b10001, 0b10001, 0b01110], // '6'
+    [0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b01000, 0b01000], // '7'
+    [0b01110, 0b10001, 0b10001, 0b01110, 0b10001, 0b10001, 0b01110], // '8'
+    [0b01110, 0b10001, 0b10001, 0b01111, 0b00001, 0b00010, 0b01100], // '9'
+    [0b00000, 0b01100, 0b01100, 0b00000, 0b01100, 0b01100, 0b00000], // ':'
+    [0b00000, 0b01100, 0b01100, 0b00000, 0b01100, 0b00100, 0b01000], // ';'
+    [0b00010, 0b00100, 0b01000, 0b10000, 0b01000, 0b00100, 0b00010], // '<'
+    [0b00000, 0b00000, 0b11111, 0b00000, 0b11111, 0b00000, 0b00000], // '='
+    [0b01000, 0b00100, 0b00010, 0b00001, 0b00010, 0b00100, 0b01000], // '>'
+    [0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b00000, 0b00100], // '?'
+    [0b01110, 0b10001, 0b00001, 0b01101, 0b10101, 0b10101, 0b01110], // '@'
+    [0b01110, 0b10001, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001], // 'A'
+    [0b11110, 0b10001, 0b10001, 0b11110, 0b10001, 0b10001, 0b11110], // 'B'
+    [0b01110, 0b10001, 0b10000, 0b10000, 0b10000, 0b10001, 0b01110], // 'C'
+    [0b11100, 0b10010, 0b10001, 0b10001, 0b10001, 0b10010, 0b11100], // 'D'
+    [0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b11111], // 'E'
+    [0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b10000], // 'F'
+    [0b01110, 0b10001, 0b10000, 0b10111, 0b10001, 0b10001, 0b01111], // 'G'
+    [0b10001, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001], // 'H'
+    [0b01110, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110], // 'I'
+    [0b00111, 0b00010, 0b00010, 0b00010, 0b00010, 0b10010, 0b01100], // 'J'
+    [0b10001, 0b10010, 0b10100, 0b11000, 0b10100, 0b10010, 0b10001], // 'K'
+    [0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b11111], // 'L'
+    [0b10001, 0b11011, 0b10101, 0b10101, 0b10001, 0b10001, 0b10001], // 'M'
+    [0b10001, 0b10001, 0b11001, 0b10101, 0b10011, 0b10001, 0b10001], // 'N'
+    [0b01110, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110], // 'O'
+    [0b11110, 0b10001, 0b10001, 0b11110, 0b10000, 0b10000, 0b10000], // 'P'
+    [0b01110, 0b10001, 0b10001, 0b10001, 0b10101, 0b10010, 0b01101], // 'Q'
+    [0b11110, 0b10001, 0b10001, 0b11110, 0b10100, 0b10010, 0b10001], // 'R'
+    [0b01111, 0b10000, 0b10000, 0b01110, 0b00001, 0b00001, 0b11110], // 'S'
+    [0b11111, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100], // 'T'
+    [0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110], // 'U'
+    [0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01010, 0b00100], // 'V'
+    [0b10001, 0b10001, 0b10001, 0b10101, 0b10101, 0b10101, 0b01010], // 'W'
+    [0b10001, 0b10001, 0b01010, 0b00100, 0b01010, 0b10001, 0b10001], // 'X'
+    [0b10001, 0b10001, 0b10001, 0b01010, 0b00100, 0b00100, 0b00100], // 'Y'
+    [0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0b11111], // 'Z'
+    [0b01110, 0b01000, 0b01000, 0b01000, 0b01000, 0b01000, 0b01110], // '['
+    [0b00000, 0b10000, 0b01000, 0b00100, 0b00010, 0b00001, 0b00000], // '\\'
+    [0b01110, 0b00010, 0b00010, 0b00010, 0b00010, 0b00010, 0b01110], // ']'
+    [0b00100, 0b01010, 0b10001, 0b00000, 0b00000, 0b00000, 0b00000], // '^'
+    [0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b11111], // '_'
+    [0b01000, 0b00100, 0b00010, 0b00000, 0b00000, 0b00000, 0b00000], // '`'
+    [0b00000, 0b00000, 0b01110, 0b00001, 0b01111, 0b10001, 0b01111], // 'a'
+    [0b10000, 0b10000, 0b10110, 0b11001, 0b10001, 0b10001, 0b11110], // 'b'
+    [0b00000, 0b00000, 0b01110, 0b10000, 0b10000, 0b10001, 0b01110], // 'c'
+    [0b00001, 0b00001, 0b01101, 0b10011, 0b10001, 0b10001, 0b01111], // 'd'
+    [0b00000, 0b00000, 0b01110, 0b10001, 0b11111, 0b10000, 0b01110], // 'e'
+    [0b00110, 0b01001, 0b01000, 0b11100, 0b01000, 0b01000, 0b01000], // 'f'
+    [0b00000, 0b01111, 0b10001, 0b10001, 0b01111, 0b00001, 0b01110], // 'g'
+    [0b10000, 0b10000, 0b10110, 0b11001, 0b10001, 0b10001, 0b10001], // 'h'
+    [0b00100, 0b00000, 0b01100, 0b00100, 0b00100, 0b00100, 0b01110], // 'i'
+    [0b00010, 0b00000, 0b00110, 0b00010, 0b00010, 0b10010, 0b01100], // 'j'
+    [0b10000, 0b10000, 0b10010, 0b10100, 0b11000, 0b10100, 0b10010], // 'k'
+    [0b01100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110], // 'l'
+    [0b00000, 0b00000, 0b11010, 0b10101, 0b10101, 0b10001, 0b10001], // 'm'
+    [0b00000, 0b00000, 0b10110, 0b11001, 0b10001, 0b10001, 0b10001], // 'n'
+    [0b00000, 0b00000, 0b01110, 0b10001, 0b10001, 0b10001, 0b01110], // 'o'
+    [0b00000, 0b00000, 0b11110, 0b10001, 0b11110, 0b10000, 0b10000], // 'p'
+    [0b00000, 0b00000, 0b01101, 0b10011, 0b01111, 0b00001, 0b00001], // 'q'
+    [0b00000, 0b00000, 0b10110, 0b11001, 0b10000, 0b10000, 0b10000], // 'r'
+    [0b00000, 0b00000, 0b01110, 0b10000, 0b01110, 0b00001, 0b11110], // 's'
+    [0b01000, 0b01000, 0b11100, 0b01000, 0b01000, 0b01001, 0b00110], // 't'
+    [0b00000, 0b00000, 0b10001, 0b10001, 0b10001, 0b10011, 0b01101], // 'u'
+    [0b00000, 0b00000, 0b10001, 0b10001, 0b10001, 0b01010, 0b00100], // 'v'
+    [0b00000, 0b00000, 0b10001, 0b10001, 0b10101, 0b10101, 0b01010], // 'w'
+    [0b00000, 0b00000, 0b10001, 0b01010, 0b00100, 0b01010, 0b10001], // 'x'
+    [0b00000, 0b00000, 0b10001, 0b10001, 0b01111, 0b00001, 0b01110], // 'y'
+    [0b00000, 0b00000, 0b11111, 0b00010, 0b00100, 0b01000, 0b11111], // 'z'
+    [0b00010, 0b00100, 0b00100, 0b01000, 0b00100, 0b00100, 0b00010], // '{'
+    [0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100], // '|'
+    [0b01000, 0b00100, 0b00100, 0b00010, 0b00100, 0b00100, 0b01000], // '}'
+    [0b00000, 0b00000, 0b01000, 0b10101, 0b00010, 0b00000, 0b00000], // '~'
+];
+
+/// Call `set(x, y)` for each pixel of a glyph that should be drawn in the foreground color,
+/// where the glyph takes up `width` cells.
+pub(crate) fn draw_glyph(glyph: &str, width: usize, mut set: impl FnMut(usize, usize)) {
+    let ch = match glyph.chars().next() {
+        Some(ch) => ch,
+        None => return,
+    };
+    let (w, h) = (width.max(1) * CELL_WIDTH, CELL_HEIGHT);
+    match ch {
+        ' ' | '\u{a0}' => (),
+        ' '..='~' => {
+            let rows = &ASCII[(ch as u8 - b' ') as usize];
+            for (y, row) in rows.iter().enumerate() {
+                for x in 0..5 {
+                    if row & (0b10000 >> x) != 0 {
+                        set(x, GLYPH_TOP + y);
+                    }
+                }
+            }
+        }
+        '\u{2500}'..='\u{257f}' => {
+            let (up, down, left, right) = box_arms(ch);
+            let (cx, cy) = (w / 2 - 1, h / 2);
+            if left {
+                (0..=cx).for_each(|x| set(x, cy));
+            }
+            if right {
+                (cx..w).for_each(|x| set(x, cy));
+            }
+            if up {
+                (0..=cy).for_each(|y| set(cx, y));
+            }
+            if down {
+                (cy..h).for_each(|y| set(cx, y));
+            }
+        }
+        '\u{2580}'..='\u{259f}' => draw_block(ch, w, h, set),
+        '\u{2800}'..='\u{28ff}' => {
+            // Dots 1 to 8, numbered down the left column and then the right, except that the
+            // bottom row is dots 7 and 8.
+            const DOTS: [(usize, usize); 8] = [
+                (0, 0),
+                (0, 1),
+                (0, 2),
+                (1, 0),
+                (1, 1),
+                (1, 2),
+                (0, 3),
+                (1, 3),
+            ];
+            let bits = ch as u32 - 0x2800;
+            for (i, &(dx, dy)) in DOTS.iter().enumerate() {
+                if bits & (1 << i) != 0 {
+                    set(1 + dx * 3, 1 + dy * 2);
+                    set(2 + dx * 3, 1 + dy * 2);
+                }
+            }
+        }
+        _ => {
+            for x in 1..w - 1 {
+                set(x, GLYPH_TOP);
+                set(x, GLYPH_TOP + 6);
+            }
+            for y in GLYPH_TOP..GLYPH_TOP + 7 {
+                set(1, y);
+                set(w - 2, y);
+            }
+        }
+    }
+}
+
+/// Which of the lines up, down, left and right from the middle of the cell a box drawing
+/// character has. Heavy, double and dashed lines are drawn the same as light ones.
+fn box_arms(ch: char) -> (bool, bool, bool, bool) {
+    match ch {
+        '─' | '━' | '═' | '┄' | '┅' | '┈' | '┉' | '╌' | '╍' => {
+            (false, false, true, true)
+        }
+        '│' | '┃' | '║' | '┆' | '┇' | '┊' | '┋' | '╎' | '╏' => {
+            (true, true, false, false)
+        }
+        '┌' | '┍' | '┎' | '┏' | '╒' | '╓' | '╔' | '╭' => (false, true, false, true),
+        '┐' | '┑' | '┒' | '┓' | '╕' | '╖' | '╗' | '╮' => (false, true, true, false),
+        '└' | '┕' | '┖' | '┗' | '╘' | '╙' | '╚' | '╰' => (true, false, false, true),
+        '┘' | '┙' | '┚' | '┛' | '╛' | '╜' | '╝' | '╯' => (true, false, true, false),
+        '├'..='┣' | '╞' | '╟' | '╠' => (true, true, false, true),
+        '┤'..='┫' | '╡' | '╢' | '╣' => (true, true, true, false),
+        '┬'..='┳' | '╤' | '╥' | '╦' => (false, true, true, true),
+        '┴'..='┻' | '╧' | '╨' | '╩' => (true, false, true, true),
+        '┼'..='╋' | '╪' | '╫' | '╬' => (true, true, true, true),
+        '╴' | '╸' => (false, false, true, false),
+        '╵' | '╹' => (true, false, false, false),
+        '╶' | '╺' => (false, false, false, true),
+        '╷' | '╻' => (false, true, false, false),
+        '╼' | '╾' => (false, false, true, true),
+        '╽' | '╿' => (true, true, false, false),
+        _ => (false, false, false, false),
+    }
+}
+
+/// Draw one of the block elements from U+2580 to U+259F, in a glyph `w` by `h` pixels.
+fn draw_block(ch: char, w: usize, h: usize, mut set: impl FnMut(usize, usize)) {
+    let mut fill = |x0: usize, y0: usize, x1: usize, y1: usize| {
+        for y in y0..y1 {
+            for x in x0..x1 {
+                set(x, y);
+            }
+        }
+    };
+    let (mx, my) = (w / 2, h / 2);
+    match ch {
+        '▀' => fill(0, 0, w, my),
+        // Lower eighths, from '▁' to '█'.
+        '▁'..='█' => {
+            let eighths = ch as usize - '▀' as usize;
+            fill(0, h - h * eighths / 8, w, h);
+        }
+        // Left eighths, from '▉' (seven eighths) to '▏' (one eighth).
+        '▉'..='▏' => {
+            let eighths = '▏' as usize - ch as usize + 1;
+            fill(0, 0, w * eighths / 8, h);
+        }
+        '▐' => fill(mx, 0, w, h),
+        '░' | '▒' | '▓' => {
+            // Shades are drawn with an ordered dither of a quarter, half and three quarters.
+            let level = ch as usize - '░' as usize + 1;
+            for y in 0..h {
+                for x in 0..w {
+                    let threshold = [0, 2, 3, 1][(x % 2) + 2 * (y % 2)];
+                    if threshold < level {
+                        set(x, y);
+                    }
+                }
+            }
+        }
+        '▔' => fill(0, 0, w, h / 8),
+        '▕' => fill(w - w / 8, 0, w, h),
+        // Quadrants, from '▖' to '▟'.
+        _ => {
+            let quadrants: u8 = match ch {
+                '▖' => 0b0010,
+                '▗' => 0b0001,
+                '▘' => 0b1000,
+                '▙' => 0b1011,
+                '▚' => 0b1001,
+                '▛' => 0b1110,
+                '▜' => 0b1101,
+                '▝' => 0b0100,
+                '▞' => 0b0110,
+                '▟' => 0b0111,
+                _ => 0,
+            };
+            if quadrants & 0b1000 != 0 {
+                fill(0, 0, mx, my);
+            }
+            if quadrants & 0b0100 != 0 {
+                fill(mx, 0, w, my);
+            }
+            if quadrants & 0b0010 != 0 {
+                fill(0, my, mx, h);
+            }
+            if quadrants & 0b0001 != 0 {
+                fill(mx, my, w, h);
+            }
+        }
+    }
+}
@@ -0,0 +1,335 @@
+use crate::color::indexed_to_rgb;
+use crate::font::{self, CELL_HEIGHT, CELL_WIDTH};
+use crate::rect::Rect;
+use crate::screen::{Attributes, Color, Frame};
+use crate::ColorSupport;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// The palette entries used for the terminal's default colors.
+const DEFAULT_FG: u8 = 7;
+const DEFAULT_BG: u8 = 0;
+
+/// How long the last frame is shown before the animation starts again.
+const LAST_FRAME_DELAY: Duration = Duration::from_secs(2);
+
+/// Browsers show frames with shorter delays (in hundredths of a second) for much longer.
+const MIN_DELAY: u128 = 2;
+
+/// A sequence of frames with the time each was drawn, which can be saved as an animated GIF,
+/// e.g. to show an app off in its README.
+///
+/// Apps can record the frames they draw with `AppBuilder::record_frames`.
+#[derive(Debug, Clone, Default)]
+pub struct FrameRecording {
+    frames: Vec<(Duration, Frame)>,
+    start: Option<Instant>,
+}
+
+impl FrameRecording {
+    pub fn new() -> FrameRecording {
+        FrameRecording::default()
+    }
+
+    /// Add a frame drawn now. The first frame recorded is at time zero.
+    pub fn record(&mut self, frame: &Frame) {
+        let start = *self.start.get_or_insert_with(Instant::now);
+        if !self.is_repeat(frame) {
+            self.frames.push((start.elapsed(), frame.clone()));
+        }
+    }
+
+    /// Add a frame drawn at `time`. Frames must be added in time order.
+    ///
+    /// Frames that are the same as the one before are skipped, so the earlier one is shown
+    /// for longer.
+    pub fn push(&mut self, time: Duration, frame: Frame) {
+        if !self.is_repeat(&frame) {
+            self.frames.push((time, frame));
+        }
+    }
+
+    pub fn frames(&self) -> &[(Duration, Frame)] {
+        &self.frames
+    }
+
+    pub fn save_gif(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_gif(&mut writer)?;
+        writer.flush()
+    }
+
+    /// Write the recording as an animated GIF that loops forever. Each frame is shown until the
+    /// next one was drawn, and the last one for two seconds.
+    ///
+    /// Cells are drawn 6x10 pixels with a small built-in font, in the nearest colors of xterm's
+    /// 256-color palette. The default colors are drawn as light gray on black. If the frames
+    /// aren't all the same size, they are drawn in the top left corner of an image big enough
+    /// for the largest.
+    ///
+    /// Returns an error if nothing was recorded.
+    pub fn write_gif(&self, writer: &mut impl Write) -> io::Result<()> {
+        if self.frames.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "no frames were recorded",
+            ));
+        }
+        let cols = self.frames.iter().map(|(_, f)| f.columns()).max().unwrap();
+        let rows = self.frames.iter().map(|(_, f)| f.rows()).max().unwrap();
+        let (width, height) = (cols.max(1) * CELL_WIDTH, rows.max(1) * CELL_HEIGHT);
+        if width > usize::from(u16::MAX) || height > usize::from(u16::MAX) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "the frames are too big for a GIF",
+            ));
+        }
+        write_header(writer, width, height)?;
+        let mut prev: Option<Vec<u8>> = None;
+        // Each image is written once we know when the next one starts.
+        let mut pending: Option<(Duration, Rect, Vec<u8>)> = None;
+        for (time, frame) in &self.frames {
+            let pixels = rasterize(frame, width, height);
+            let area = match &prev {
+                Some(prev) => match changed_area(prev, &pixels, width) {
+                    Some(area) => area,
+                    // The frames only differ in ways that aren't drawn, like links.
+                    None => continue,
+                },
+                None => Rect::new(0, 0, height, width),
+            };
+            if let Some((start, area, image)) = pending.take() {
+                write_image(writer, area, &image, time.saturating_sub(start))?;
+            }
+            pending = Some((*time, area, crop(&pixels, width, area)));
+            prev = Some(pixels);
+        }
+        if let Some((_, area, image)) = pending {
+            write_image(writer, area, &image, LAST_FRAME_DELAY)?;
+        }
+        // Trailer.
+        writer.write_all(&[0x3b])
+    }
+
+    fn is_repeat(&self, frame: &Frame) -> bool {
+        match self.frames.last() {
+            Some((_, last)) => {
+                last.rows() == frame.rows()
+                    && last.columns() == frame.columns()
+                    && (0..frame.rows()).all(|row| last.row(row) == frame.row(row))
+            }
+            None => false,
+        }
+    }
+}
+
+/// The GIF header, with xterm's 256-color palette as the global color table, and an
+/// extension to make the animation loop.
+fn write_header(writer: &mut impl Write, width: usize, height: usize) -> io::Result<()> {
+    writer.write_all(b"GIF89a")?;
+    writer.write_all(&(width as u16).to_le_bytes())?;
+    writer.write_all(&(height as u16).to_le_bytes())?;
+    // A global color table of 256 colors, with 8 bits per channel.
+    writer.write_all(&[0xf7, DEFAULT_BG, 0])?;
+    for idx in 0..=255 {
+        let (r, g, b) = indexed_to_rgb(idx);
+        writer.write_all(&[r, g, b])?;
+    }
+    // Repeat forever.
+    writer.write_all(b"\x21\xff\x0bNETSCAPE2.0\x03\x01\x00\x00\x00")
+}
+
+/// One frame of the animation, drawn over the area of the last one that changed.
+fn write_image(
+    writer: &mut impl Write,
+    area: Rect,
+    pixels: &[u8],
+    delay: Duration,
+) -> io::Result<()> {
+    let delay = (delay.as_millis() / 10).clamp(MIN_DELAY, u128::from(u16::MAX)) as u16;
+    // Graphic control extension: leave the image in place under the next one.
+    writer.write_all(&[0x21, 0xf9, 4, 0x04])?;
+    writer.write_all(&delay.to_le_bytes())?;
+    writer.write_all(&[0, 0])?;
+    // Image descriptor, using the global color table.
+    writer.write_all(&[0x2c])?;
+    for n in &[area.col, area.row, area.width, area.height] {
+        writer.write_all(&(*n as u16).to_le_bytes())?;
+    }
+    writer.write_all(&[0])?;
+    write_lzw(writer, pixels)
+}
+
+/// Compress pixels with the LZW coding GIF uses, with 8-bit codes to start with.
+fn write_lzw(writer: &mut impl Write, pixels: &[u8]) -> io::Result<()> {
+    const CLEAR: u16 = 256;
+    const END: u16 = 257;
+    const MAX_CODE_SIZE: u32 = 12;
+    let mut codes = Codes::default();
+    let mut table: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut next_code = END + 1;
+    let mut code_size = 9;
+    codes.push(CLEAR, code_size);
+    let mut prefix: Option<u16> = None;
+    for &pixel in pixels {
+        let current = match prefix {
+            Some(current) => current,
+            None => {
+                prefix = Some(u16::from(pixel));
+                continue;
+            }
+        };
+        if let Some(&code) = table.get(&(current, pixel)) {
+            prefix = Some(code);
+            continue;
+        }
+        codes.push(current, code_size);
+        // The decoder adds each code to its table a code later than we do, so it only needs
+        // more bits once the code before this one doesn't fit.
+        if u32::from(next_code) >= 1 << code_size && code_size < MAX_CODE_SIZE {
+            code_size += 1;
+        }
+        if next_code < 1 << MAX_CODE_SIZE {
+            table.insert((current, pixel), next_code);
+            next_code += 1;
+        } else {
+            codes.push(CLEAR, code_size);
+            table.clear();
+            next_code = END + 1;
+            code_size = 9;
+        }
+        prefix = Some(u16::from(pixel));
+    }
+    if let Some(current) = prefix {
+        codes.push(current, code_size);
+    }
+    codes.push(END, code_size);
+    codes.finish();
+
+    writer.write_all(&[8])?;
+    for block in codes.bytes.chunks(255) {
+        writer.write_all(&[block.len() as u8])?;
+        writer.write_all(block)?;
+    }
+    writer.write_all(&[0])
+}
+
+/// Variable length codes, packed least significant bit first.
+#[derive(Default)]
+struct Codes {
+    bytes: Vec<u8>,
+    bits: u32,
+    len: u32,
+}
+
+impl Codes {
+    fn push(&mut self, code: u16, size: u32) {
+        self.bits |= u32::from(code) << self.len;
+        self.len += size;
+        while self.len >= 8 {
+            self.bytes.push(self.bits as u8);
+            self.bits >>= 8;
+            self.len -= 8;
+        }
+    }
+
+    fn finish(&mut self) {
+        if self.len > 0 {
+            self.bytes.push(self.bits as u8);
+            self.bits = 0;
+            self.len = 0;
+        }
+    }
+}
+
+/// Draw a frame as palette indices, in the top left of an image of the given size.
+fn rasterize(frame: &Frame, width: usize, height: usize) -> Vec<u8> {
+    let mut pixels = vec![DEFAULT_BG; width * height];
+    for (row, col, ch) in frame.iter() {
+        if ch.is_continuation() {
+            continue;
+        }
+        let cells = ch.width().clamp(1, frame.columns() - col);
+        let mut fg = palette_index(ch.color_fg, DEFAULT_FG);
+        let mut bg = palette_index(ch.color_bg, DEFAULT_BG);
+        if ch.attrs.contains(Attributes::REVERSE) {
+            std::mem::swap(&mut fg, &mut bg);
+        }
+        if ch.attrs.contains(Attributes::DIM) {
+            fg = dim(fg, bg);
+        }
+        let (left, top, w) = (col * CELL_WIDTH, row * CELL_HEIGHT, cells * CELL_WIDTH);
+        for y in top..top + CELL_HEIGHT {
+            pixels[y * width + left..y * width + left + w].fill(bg);
+        }
+        let bold = ch.attrs.contains(Attributes::BOLD);
+        let mut set = |x: usize, y: usize| {
+            if x < w && y < CELL_HEIGHT {
+                let idx = (top + y) * width + left + x;
+                pixels[idx] = fg;
+                // Bold text is drawn thicker, by smearing it a pixel to the right.
+                if bold && x + 1 < w {
+                    pixels[idx + 1] = fg;
+                }
+            }
+        };
+        font::draw_glyph(ch.glyph.as_str(), cells, &mut set);
+        if ch.attrs.contains(Attributes::UNDERLINE) {
+            (0..w).for_each(|x| set(x, CELL_HEIGHT - 1));
+        }
+        if ch.attrs.contains(Attributes::STRIKETHROUGH) {
+            (0..w).for_each(|x| set(x, CELL_HEIGHT / 2));
+        }
+    }
+    pixels
+}
+
+/// The entry in xterm's 256-color palette closest to a color.
+fn palette_index(color: Color, default: u8) -> u8 {
+    match color.downgrade(ColorSupport::Indexed256) {
+        Color::Default => default,
+        Color::Indexed(idx) => idx,
+        named => (0..16)
+            .find(|&idx| named.rgb() == Some(indexed_to_rgb(idx)))
+            .unwrap_or(default),
+    }
+}
+
+/// The color halfway between the foreground and background, for dim text.
+fn dim(fg: u8, bg: u8) -> u8 {
+    let ((r1, g1, b1), (r2, g2, b2)) = (indexed_to_rgb(fg), indexed_to_rgb(bg));
+    let mix = |a: u8, b: u8| ((u16::from(a) + u16::from(b)) / 2) as u8;
+    palette_index(Color::Rgb(mix(r1, r2), mix(g1, g2), mix(b1, b2)), fg)
+}
+
+/// The smallest area containing all the pixels that differ between two images.
+fn changed_area(prev: &[u8], next: &[u8], width: usize) -> Option<Rect> {
+    let (mut top, mut left, mut bottom, mut right) = (usize::MAX, usize::MAX, 0, 0);
+    for (y, (prev, next)) in prev.chunks(width).zip(next.chunks(width)).enumerate() {
+        let first = match (0..width).find(|&x| prev[x] != next[x]) {
+            Some(first) => first,
+            None => continue,
+        };
+        let last = (0..width).rev().find(|&x| prev[x] != next[x]).unwrap();
+        top = top.min(y);
+        bottom = y + 1;
+        left = left.min(first);
+        right = right.max(last + 1);
+    }
+    if bottom == 0 {
+        return None;
+    }
+    Some(Rect::new(top, left, bottom - top, right - left))
+}
+
+/// The pixels in an area of an image.
+fn crop(pixels: &[u8], width: usize, area: Rect) -> Vec<u8> {
+    let mut cropped = Vec::with_capacity(area.width * area.height);
+    for y in area.row..area.bottom() {
+        cropped.extend_from_slice(&pixels[y * width + area.col..y * width + area.right()]);
+    }
+    cropped
+}
@@ -0,0 +1,125 @@
+use std::fmt;
+use unicode_width::UnicodeWidthStr;
+
+/// The most bytes of UTF-8 a glyph can hold.
+const GLYPH_CAPACITY: usize = 31;
+
+/// The contents of a cell: a single grapheme cluster, such as `e`, `é` (as `e` plus a combining
+/// accent), or a multi-codepoint emoji.
+///
+/// The cluster is stored inline, so glyphs are `Copy`. Clusters longer than 31
+/// bytes can't be stored.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct Glyph {
+    len: u8,
+    bytes: [u8; GLYPH_CAPACITY],
+}
+
+impl Glyph {
+    /// The empty glyph, used for transparent cells (see `Char::TRANSPARENT`).
+    pub(crate) const EMPTY: Glyph = Glyph {
+        len: 0,
+        bytes: [0; GLYPH_CAPACITY],
+    };
+
+    /// A glyph holding the given string, or `None` if it is empty or too long.
+    ///
+    /// The string should be a single grapheme cluster, but this isn't checked.
+    pub fn new(s: &str) -> Option<Glyph> {
+        if s.is_empty() || s.len() > GLYPH_CAPACITY {
+            return None;
+        }
+        let mut bytes = [0; GLYPH_CAPACITY];
+        bytes[..s.len()].copy_from_slice(s.as_bytes());
+        Some(Glyph {
+            len: s.len() as u8,
+            bytes,
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        // We only ever store valid UTF-8.
+        std::str::from_utf8(&self.bytes[..usize::from(self.len)]).unwrap()
+    }
+
+    /// The number of terminal columns the glyph takes up (0, 1 or 2).
+    ///
+    /// Control characters are counted as 1.
+    pub fn width(&self) -> usize {
+        if self.is_control() {
+            return 1;
+        }
+        self.as_str().width().min(2)
+    }
+
+    /// The glyph is a control character (or `"\r\n"`).
+    pub(crate) fn is_control(&self) -> bool {
+        self.as_str().chars().next().is_some_and(char::is_control)
+    }
+
+    /// The glyph is the nul character, which is used to mark the second cell of a wide glyph.
+    pub(crate) fn is_nul(&self) -> bool {
+        self.len == 1 && self.bytes[0] == 0
+    }
+}
+
+impl From<char> for Glyph {
+    fn from(c: char) -> Glyph {
+        let mut bytes = [0; GLYPH_CAPACITY];
+        let len = c.encode_utf8(&mut bytes).len();
+        Glyph {
+            len: len as u8,
+            bytes,
+        }
+    }
+}
+
+/// Strings that can't be stored (see `Glyph::new`) become `U+FFFD REPLACEMENT CHARACTER`.
+impl From<&str> for Glyph {
+    fn from(s: &str) -> Glyph {
+        Glyph::new(s).unwrap_or_else(|| Glyph::from(char::REPLACEMENT_CHARACTER))
+    }
+}
+
+impl Default for Glyph {
+    fn default() -> Glyph {
+        Glyph::from(' ')
+    }
+}
+
+impl PartialEq<char> for Glyph {
+    fn eq(&self, other: &char) -> bool {
+        let mut buf = [0; 4];
+        self.as_str() == other.encode_utf8(&mut buf)
+    }
+}
+
+impl fmt::Debug for Glyph {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Display for Glyph {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Glyph {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Glyph {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Glyph, D::Error> {
+        let s = <std::borrow::Cow<'de, str>>::deserialize(deserializer)?;
+        if s.is_empty() {
+            return Ok(Glyph::EMPTY);
+        }
+        Glyph::new(&s).ok_or_else(|| serde::de::Error::custom("invalid glyph"))
+    }
+}
@@ -0,0 +1,59 @@
+use crate::rect::Rect;
+use crate::screen::{Char, Color, Frame};
+
+/// The direction a gradient runs in.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+pub enum GradientDirection {
+    /// From the left edge to the right edge.
+    #[default]
+    Horizontal,
+    /// From the top edge to the bottom edge.
+    Vertical,
+}
+
+impl Frame {
+    /// Fill the background of a region with a gradient between two colors.
+    ///
+    /// The glyphs and foreground colors already in the region are kept, so this can be used
+    /// before or after drawing text. The colors are interpolated in RGB (the terminal's default
+    /// background is taken to be black), and are downgraded when rendering to terminals without
+    /// true color. Any part of the region outside the frame is ignored.
+    pub fn fill_gradient(
+        &mut self,
+        region: Rect,
+        from: Color,
+        to: Color,
+        direction: GradientDirection,
+    ) {
+        let from = from.rgb().or_else(|| Color::Black.rgb()).unwrap();
+        let to = to.rgb().or_else(|| Color::Black.rgb()).unwrap();
+        let steps = match direction {
+            GradientDirection::Horizontal => region.width,
+            GradientDirection::Vertical => region.height,
+        };
+        let clipped = region.intersection(self.area());
+        for row in clipped.row..clipped.bottom() {
+            for col in clipped.col..clipped.right() {
+                let ch = self.get(row, col);
+                // The wide glyph to the left takes care of its continuation cell.
+                if ch.is_continuation() {
+                    continue;
+                }
+                let step = match direction {
+                    GradientDirection::Horizontal => col - region.col,
+                    GradientDirection::Vertical => row - region.row,
+                };
+                let color_bg = interpolate(from, to, step, steps);
+                self.set_clipped(row, col, Char { color_bg, ..ch });
+            }
+        }
+    }
+}
+
+/// The color `step` of the way from `from` to `to` in `steps` steps, where the first step is
+/// `from` and the last is `to`.
+fn interpolate(from: (u8, u8, u8), to: (u8, u8, u8), step: usize, steps: usize) -> Color {
+    let (step, last) = (step as i64, steps.saturating_sub(1).max(1) as i64);
+    let mix = |a: u8, b: u8| (i64::from(a) + (i64::from(b) - i64::from(a)) * step / last) as u8;
+    Color::Rgb(mix(from.0, to.0), mix(from.1, to.1), mix(from.2, to.2))
+}
@@ -0,0 +1,446 @@
+//! Images drawn with sixel graphics or the kitty graphics protocol, enabled with the `images`
+//! feature.
+//!
+//! Images are drawn over a region of cells after the rest of the frame. While an image stays
+//! in the same place the cells under it are left alone, so the diff doesn't draw over it, and
+//! when it moves or goes away it is deleted and the cells it covered are drawn again.
+use crate::backend::Backend;
+use crate::link::Link;
+use crate::rect::Rect;
+use crate::screen::{cursor_after, Attributes, Screen, Style};
+use crate::Draw;
+use std::collections::HashSet;
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+
+/// The levels of each channel in the palette used by `SixelImage::encode`.
+const LEVELS: u32 = 6;
+
+/// Alpha values below this are left transparent.
+const OPAQUE: u8 = 128;
+
+/// The most base64 data a kitty graphics escape may carry.
+const KITTY_CHUNK: usize = 4096;
+
+/// An image encoded as sixel graphics, ready to be drawn with `Draw::image`.
+///
+/// Only some terminals can show sixel graphics (e.g. xterm with `-ti vt340`, foot, WezTerm and
+/// mlterm). Others ignore them.
+///
+/// Images are cheap to clone.
+#[derive(Clone, Eq, PartialEq, Hash)]
+pub struct SixelImage {
+    data: Arc<[u8]>,
+}
+
+impl SixelImage {
+    /// An image that is already encoded, as the whole escape from the `ESC P` to the string
+    /// terminator.
+    pub fn from_sixel(data: impl Into<Vec<u8>>) -> SixelImage {
+        SixelImage {
+            data: data.into().into(),
+        }
+    }
+
+    /// Encode an image from its pixels, as rows of RGBA bytes.
+    ///
+    /// The colors are reduced to a palette of 216. Pixels that are mostly transparent aren't
+    /// drawn, so the cells under them show through.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there aren't `width * height * 4` bytes of pixels.
+    pub fn encode(width: usize, height: usize, rgba: &[u8]) -> SixelImage {
+        assert_eq!(rgba.len(), width * height * 4, "wrong number of pixels");
+        let color = |row: usize, col: usize| {
+            let px = &rgba[(row * width + col) * 4..][..4];
+            if px[3] < OPAQUE {
+                return None;
+            }
+            let level = |c: u8| (u32::from(c) * (LEVELS - 1) + 127) / 255;
+            Some((level(px[0]) * LEVELS + level(px[1])) * LEVELS + level(px[2]))
+        };
+        let mut out = Vec::new();
+        // Transparent background, and square pixels.
+        out.extend_from_slice(b"\x1bP0;1;0q");
+        out.extend_from_slice(format!("\"1;1;{};{}", width, height).as_bytes());
+        let mut used = [false; (LEVELS * LEVELS * LEVELS) as usize];
+        for row in 0..height {
+            for col in 0..width {
+                if let Some(idx) = color(row, col) {
+                    used[idx as usize] = true;
+                }
+            }
+        }
+        for (idx, _) in used.iter().enumerate().filter(|(_, &used)| used) {
+            let idx = idx as u32;
+            let percent = |level: u32| level * 100 / (LEVELS - 1);
+            out.extend_from_slice(
+                format!(
+                    "#{};2;{};{};{}",
+                    idx,
+                    percent(idx / (LEVELS * LEVELS)),
+                    percent(idx / LEVELS % LEVELS),
+                    percent(idx % LEVELS)
+                )
+                .as_bytes(),
+            );
+        }
+        // Each band of six rows is drawn one color at a time, going back to the start of the
+        // band for each.
+        for band in (0..height).step_by(6) {
+            let rows = band..(band + 6).min(height);
+            let mut colors: Vec<u32> = rows
+                .clone()
+                .flat_map(|row| (0..width).filter_map(move |col| color(row, col)))
+                .collect();
+            colors.sort_unstable();
+            colors.dedup();
+            for (i, &idx) in colors.iter().enumerate() {
+                if i > 0 {
+                    out.push(b'$');
+                }
+                out.extend_from_slice(format!("#{}", idx).as_bytes());
+                let sixels = (0..width).map(|col| {
+                    let bits = rows
+                        .clone()
+                        .enumerate()
+                        .filter(|&(_, row)| color(row, col) == Some(idx))
+                        .fold(0, |bits, (bit, _)| bits | 1 << bit);
+                    b'?' + bits
+                });
+                write_runs(sixels, &mut out);
+            }
+            out.push(b'-');
+        }
+        out.extend_from_slice(b"\x1b\\");
+        SixelImage { data: out.into() }
+    }
+
+    /// The encoded image.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+/// Write sixels, using the repeat introducer for runs of the same one.
+fn write_runs(sixels: impl Iterator<Item = u8>, out: &mut Vec<u8>) {
+    let mut run: Option<(u8, usize)> = None;
+    let flush = |run: Option<(u8, usize)>, out: &mut Vec<u8>| match run {
+        Some((sixel, len)) if len > 3 => {
+            out.extend_from_slice(format!("!{}{}", len, sixel as char).as_bytes())
+        }
+        Some((sixel, len)) => out.extend(std::iter::repeat_n(sixel, len)),
+        None => {}
+    };
+    for sixel in sixels {
+        match &mut run {
+            Some((current, len)) if *current == sixel => *len += 1,
+            _ => {
+                flush(run, out);
+                run = Some((sixel, 1));
+            }
+        }
+    }
+    flush(run, out);
+}
+
+impl std::fmt::Debug for SixelImage {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("SixelImage")
+            .field("len", &self.data.len())
+            .finish()
+    }
+}
+
+/// A PNG image, to be drawn with the kitty graphics protocol by `Draw::kitty_image`.
+///
+/// The image is sent to the terminal the first time it is drawn, and after that only its
+/// placement is. Clones share the image, so keep the same one around rather than making a new
+/// one for each frame.
+#[derive(Clone, Eq, PartialEq, Hash)]
+pub struct KittyImage {
+    id: u32,
+    png: Arc<[u8]>,
+}
+
+impl KittyImage {
+    /// An image from the contents of a PNG file.
+    pub fn from_png(png: impl Into<Vec<u8>>) -> KittyImage {
+        static NEXT_ID: AtomicU32 = AtomicU32::new(1);
+        KittyImage {
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            png: png.into().into(),
+        }
+    }
+
+    /// The PNG data.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.png
+    }
+
+    /// Write the escapes to send the image to the terminal, without showing it.
+    fn write_upload(&self, writer: &mut impl Write) -> io::Result<()> {
+        let data = base64(&self.png);
+        let mut chunks = data.chunks(KITTY_CHUNK).peekable();
+        // Even an empty image needs one escape.
+        let mut first = true;
+        while first || chunks.peek().is_some() {
+            let chunk = chunks.next().unwrap_or_default();
+            let more = u8::from(chunks.peek().is_some());
+            if first {
+                write!(writer, "\x1b_Ga=t,f=100,i={},q=2,m={};", self.id, more)?;
+                first = false;
+            } else {
+                write!(writer, "\x1b_Gm={};", more)?;
+            }
+            writer.write_all(chunk)?;
+            write!(writer, "\x1b\\")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Debug for KittyImage {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("KittyImage")
+            .field("id", &self.id)
+            .field("len", &self.png.len())
+            .finish()
+    }
+}
+
+/// Encode bytes as base64, with padding.
+fn base64(bytes: &[u8]) -> Vec<u8> {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = Vec::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0, |n, (i, &b)| n | u32::from(b) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize]);
+            } else {
+                out.push(b'=');
+            }
+        }
+    }
+    out
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+enum Image {
+    Sixel(SixelImage),
+    Kitty(KittyImage),
+}
+
+/// An image drawn over a region of the terminal.
+#[derive(Debug, Clone, Eq, PartialEq)]
+struct Placement {
+    region: Rect,
+    image: Image,
+}
+
+impl Placement {
+    /// Identifies a kitty image's placement, so it can be deleted. An image can only be placed
+    /// once in a region, and regions fit in `u16`s.
+    fn kitty_id(&self) -> u32 {
+        (self.region.row as u32) << 16 | self.region.col as u32
+    }
+}
+
+/// The images in the next frame, and those the terminal is showing.
+#[derive(Debug, Default)]
+pub(crate) struct Images {
+    next: Vec<Placement>,
+    shown: Vec<Placement>,
+    /// The kitty images the terminal has been sent.
+    uploaded: HashSet<u32>,
+    /// Whether the terminal can show sixel graphics.
+    pub(crate) sixel: bool,
+    /// Whether the terminal supports the kitty graphics protocol.
+    pub(crate) kitty: bool,
+}
+
+impl Images {
+    pub(crate) fn clear(&mut self) {
+        self.next.clear();
+    }
+}
+
+impl<'a, B: Backend> Draw<'a, B> {
+    /// Draw a sixel image over a region of the frame, once the rest of the frame has been
+    /// drawn.
+    ///
+    /// The image is drawn at its own size from the top left of the region, and the cells of
+    /// the region are left alone while it is shown. So that nothing is left behind when the
+    /// image is moved or removed, the region should cover the whole image. Images are drawn
+    /// in the order they are added.
+    ///
+    /// Returns whether the image will be drawn: nothing is drawn if the terminal can't show
+    /// sixel graphics (see `Capabilities::sixel`) or the region isn't entirely in the frame,
+    /// so something else can be drawn in its place.
+    pub fn image(&mut self, region: Rect, image: &SixelImage) -> bool {
+        self.screen.images.sixel && self.place(region, Image::Sixel(image.clone()))
+    }
+
+    /// Draw a PNG image over a region of the frame with the kitty graphics protocol, scaled
+    /// to fill the region.
+    ///
+    /// Otherwise this works like `Draw::image`, returning whether the image will be drawn: if
+    /// the terminal doesn't support the protocol (see `Capabilities::kitty_graphics`),
+    /// something else should be drawn in its place.
+    pub fn kitty_image(&mut self, region: Rect, image: &KittyImage) -> bool {
+        self.screen.images.kitty && self.place(region, Image::Kitty(image.clone()))
+    }
+
+    fn place(&mut self, region: Rect, image: Image) -> bool {
+        let next = &self.screen.next;
+        let region = match &self.fixed {
+            Some(fixed) => {
+                let (rows, cols) = (next.rows(), next.columns());
+                let top_left = fixed.to_terminal((region.row, region.col), rows, cols);
+                let bottom_right = (
+                    region.bottom().checked_sub(1),
+                    region.right().checked_sub(1),
+                );
+                match (top_left, bottom_right) {
+                    (Some((row, col)), (Some(bottom), Some(right)))
+                        if fixed.to_terminal((bottom, right), rows, cols).is_some() =>
+                    {
+                        Rect::new(row, col, region.height, region.width)
+                    }
+                    _ => return false,
+                }
+            }
+            None if region.intersection(next.area()) == region => region,
+            None => return false,
+        };
+        if region.height == 0 || region.width == 0 {
+            return false;
+        }
+        self.screen.images.next.push(Placement { region, image });
+        true
+    }
+}
+
+impl Screen {
+    /// Whether the images to draw are different from those shown.
+    pub(crate) fn images_changed(&self) -> bool {
+        self.images.next != self.images.shown
+    }
+
+    /// Whether any images are shown, or are about to be.
+    pub(crate) fn has_images(&self) -> bool {
+        !self.images.next.is_empty() || !self.images.shown.is_empty()
+    }
+
+    /// Whether a cell is under an image that is already shown and is staying, so it mustn't
+    /// be drawn.
+    pub(crate) fn hidden(&self, row: usize, col: usize) -> bool {
+        self.images.next.iter().any(|placement| {
+            placement.region.contains(row, col) && self.images.shown.contains(placement)
+        })
+    }
+
+    /// Delete images that have gone and draw the cells under them, then draw the images that
+    /// are new. `cleared` says whether the screen has been cleared, removing the sixel images.
+    pub(crate) fn draw_images(&mut self, output: &mut Vec<u8>, cleared: bool) -> io::Result<()> {
+        for placement in &self.images.shown {
+            if self.images.next.contains(placement) {
+                continue;
+            }
+            if let Image::Kitty(image) = &placement.image {
+                write!(
+                    output,
+                    "\x1b_Ga=d,d=i,i={},p={},q=2\x1b\\",
+                    image.id,
+                    placement.kitty_id()
+                )?;
+            }
+            if !cleared {
+                self.redraw_region(placement.region, output)?;
+            }
+        }
+        if cleared {
+            self.images.shown.clear();
+        }
+        for placement in &self.images.next {
+            if self.images.shown.contains(placement) {
+                continue;
+            }
+            let region = placement.region;
+            match &placement.image {
+                Image::Sixel(image) => {
+                    write!(output, "{}", self.goto(region.row, region.col))?;
+                    output.extend_from_slice(image.as_bytes());
+                }
+                Image::Kitty(image) => {
+                    if self.images.uploaded.insert(image.id) {
+                        image.write_upload(output)?;
+                    }
+                    // Placing an image again with the same ids replaces the old placement. C=1
+                    // leaves the cursor where it is.
+                    write!(
+                        output,
+                        "{}\x1b_Ga=p,i={},p={},c={},r={},C=1,q=2\x1b\\",
+                        self.goto(region.row, region.col),
+                        image.id,
+                        placement.kitty_id(),
+                        region.width,
+                        region.height
+                    )?;
+                }
+            }
+        }
+        self.images.shown.clone_from(&self.images.next);
+        Ok(())
+    }
+
+    /// Draw the cells in a region of the next frame, whatever the terminal is showing.
+    fn redraw_region(&self, region: Rect, writer: &mut impl Write) -> io::Result<()> {
+        let region = region.intersection(self.next.area());
+        write!(writer, "{}", termion::style::Reset)?;
+        let mut prev = Style::default();
+        let mut cursor = None;
+        for row in region.row..region.bottom() {
+            // Start from the glyph covering the left edge, if it is wide.
+            let mut start = region.col;
+            while start > 0 && self.next.get(row, start).is_continuation() {
+                start -= 1;
+            }
+            for col in start..region.right() {
+                let current = &self.next[(row, col)];
+                if current.is_continuation() || self.hidden(row, col) {
+                    continue;
+                }
+                if cursor != Some((row, col)) {
+                    write!(writer, "{}", self.goto(row, col))?;
+                }
+                if prev.color_fg != current.color_fg {
+                    self.write_fg(current.color_fg, writer)?;
+                }
+                if prev.color_bg != current.color_bg {
+                    self.write_bg(current.color_bg, writer)?;
+                }
+                let attrs: Attributes = current.attrs & self.attributes;
+                if prev.attrs != attrs {
+                    attrs.write_change(prev.attrs, writer)?;
+                }
+                if prev.link != current.link {
+                    Link::write_change(prev.link.as_ref(), current.link.as_ref(), writer)?;
+                }
+                prev = Style {
+                    attrs,
+                    ..current.style()
+                };
+                write!(writer, "{}", current.glyph)?;
+                cursor = cursor_after(row, col, current);
+            }
+        }
+        Link::write_change(prev.link.as_ref(), None, writer)
+    }
+}
@@ -0,0 +1,203 @@
+use crate::rect::Rect;
+use crate::screen::{Attributes, Color, Frame, Style};
+use termion::event::Key;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// A single line of editable text, with a cursor.
+///
+/// The text scrolls horizontally to keep the cursor in view when it is wider than the region
+/// it's drawn in.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct TextInput {
+    value: String,
+    /// The byte index of the cursor. This is always on a grapheme boundary.
+    cursor: usize,
+    /// The number of columns scrolled off the left edge.
+    left: usize,
+    pub style: Style,
+    /// The style of the cell under the cursor.
+    pub cursor_style: Style,
+    /// Text shown (in `placeholder_style`) when the input is empty.
+    pub placeholder: Option<String>,
+    pub placeholder_style: Style,
+}
+
+impl Default for TextInput {
+    fn default() -> TextInput {
+        TextInput::new()
+    }
+}
+
+impl TextInput {
+    pub fn new() -> TextInput {
+        TextInput {
+            value: String::new(),
+            cursor: 0,
+            left: 0,
+            style: Style::default(),
+            cursor_style: Style::new(Color::Default, Color::Default, Attributes::REVERSE),
+            placeholder: None,
+            placeholder_style: Style::new(Color::LightBlack, Color::Default, Attributes::empty()),
+        }
+    }
+
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    /// Replace the text, moving the cursor to the end.
+    pub fn set_value(&mut self, value: impl Into<String>) {
+        self.value = value.into();
+        self.cursor = self.value.len();
+    }
+
+    /// The byte index of the cursor in the text.
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// Insert a character at the cursor.
+    pub fn insert(&mut self, ch: char) {
+        self.value.insert(self.cursor, ch);
+        self.cursor += ch.len_utf8();
+        // A combining character joins the grapheme before it.
+        self.cursor = next_boundary(&self.value, previous_boundary(&self.value, self.cursor));
+    }
+
+    /// Delete the grapheme before the cursor, like Backspace.
+    pub fn delete_backward(&mut self) {
+        let start = previous_boundary(&self.value, self.cursor);
+        self.value.replace_range(start..self.cursor, "");
+        self.cursor = start;
+    }
+
+    /// Delete the grapheme after the cursor, like Delete.
+    pub fn delete_forward(&mut self) {
+        let end = next_boundary(&self.value, self.cursor);
+        self.value.replace_range(self.cursor..end, "");
+    }
+
+    pub fn move_left(&mut self) {
+        self.cursor = previous_boundary(&self.value, self.cursor);
+    }
+
+    pub fn move_right(&mut self) {
+        self.cursor = next_boundary(&self.value, self.cursor);
+    }
+
+    pub fn move_home(&mut self) {
+        self.cursor = 0;
+    }
+
+    pub fn move_end(&mut self) {
+        self.cursor = self.value.len();
+    }
+
+    /// Remove all the text.
+    pub fn clear(&mut self) {
+        self.value.clear();
+        self.cursor = 0;
+        self.left = 0;
+    }
+
+    /// Edit the text in response to a key press.
+    ///
+    /// Handles printable characters, Backspace, Delete, the left and right arrows, Home and End
+    /// (also `Ctrl-A` and `Ctrl-E`). Returns `false` if the key wasn't used, e.g. for Enter.
+    pub fn handle_key(&mut self, key: Key) -> bool {
+        match key {
+            Key::Char(ch) if !ch.is_control() => self.insert(ch),
+            Key::Backspace => self.delete_backward(),
+            Key::Delete => self.delete_forward(),
+            Key::Left => self.move_left(),
+            Key::Right => self.move_right(),
+            Key::Home | Key::Ctrl('a') => self.move_home(),
+            Key::End | Key::Ctrl('e') => self.move_end(),
+            _ => return false,
+        }
+        true
+    }
+
+    /// The width of the glyph under the cursor, or 1 at the end of the text.
+    fn current_width(&self) -> usize {
+        let end = next_boundary(&self.value, self.cursor);
+        self.value[self.cursor..end].width().max(1)
+    }
+
+    /// Draw the input on the first row of a region, with the cursor shown in `cursor_style`.
+    pub fn render_into(&mut self, frame: &mut Frame, region: Rect) {
+        let region = region.intersection(frame.area());
+        if region.is_empty() {
+            return;
+        }
+        frame.fill_rect(
+            region.row,
+            region.col,
+            1,
+            region.width,
+            self.style.char(' '),
+        );
+        if self.value.is_empty() {
+            self.left = 0;
+            if let Some(placeholder) = &self.placeholder {
+                let chars = placeholder
+                    .graphemes(true)
+                    .map(|glyph| self.placeholder_style.char(glyph));
+                frame.write_run(region.row, region.col, region.right(), chars);
+            }
+        } else {
+            // Keep the cursor, and the glyph under it, in view.
+            let cursor_col = self.value[..self.cursor].width();
+            let cursor_end = cursor_col + self.current_width();
+            if cursor_col < self.left {
+                self.left = cursor_col;
+            } else if cursor_end > self.left + region.width {
+                self.left = cursor_end.saturating_sub(region.width).min(cursor_col);
+            }
+            let mut pos = 0;
+            let chars = self.value.graphemes(true).filter_map(|glyph| {
+                let start = pos;
+                pos += glyph.width();
+                if pos <= self.left {
+                    None
+                } else if start < self.left {
+                    // A wide glyph cut in half by the left edge.
+                    Some(self.style.char(' '))
+                } else {
+                    Some(self.style.char(glyph))
+                }
+            });
+            frame.write_run(region.row, region.col, region.right(), chars);
+        }
+        let col = region.col + self.value[..self.cursor].width() - self.left;
+        let mut cell = frame.get(region.row, col);
+        if cell.is_continuation() {
+            return;
+        }
+        if self.cursor_style.color_fg != Color::Default {
+            cell.color_fg = self.cursor_style.color_fg;
+        }
+        if self.cursor_style.color_bg != Color::Default {
+            cell.color_bg = self.cursor_style.color_bg;
+        }
+        cell.attrs.insert(self.cursor_style.attrs);
+        frame.set_clipped(region.row, col, cell);
+    }
+}
+
+/// The start of the grapheme before `index`.
+pub(crate) fn previous_boundary(text: &str, index: usize) -> usize {
+    text[..index]
+        .grapheme_indices(true)
+        .next_back()
+        .map_or(0, |(index, _)| index)
+}
+
+/// The end of the grapheme after `index`.
+pub(crate) fn next_boundary(text: &str, index: usize) -> usize {
+    text[index..]
+        .graphemes(true)
+        .next()
+        .map_or(index, |glyph| index + glyph.len())
+}
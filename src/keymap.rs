@@ -0,0 +1,264 @@
+use std::fmt;
+use termion::event::Key;
+
+/// Maps keys, and sequences of keys, to actions.
+///
+/// Bindings can be written as strings like `"ctrl-s"`, `"alt-x"`, `"f5"`, `"shift-tab"` or
+/// `"g g"` (a sequence, separated by spaces). With the `serde` feature, a `KeyMap` is
+/// (de)serialized as a map from these strings to actions, so it can be loaded from a config
+/// file.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct KeyMap<A> {
+    bindings: Vec<(Vec<Key>, A)>,
+}
+
+/// The result of looking up some keys in a `KeyMap`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum KeyMatch<'a, A> {
+    /// The keys are bound to this action.
+    Action(&'a A),
+    /// The keys are the start of one or more longer sequences.
+    Prefix,
+    /// Nothing is bound to the keys.
+    None,
+}
+
+/// An error from binding keys that would clash with an existing binding, because they are the
+/// same keys or one is the start of the other.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct KeyConflict {
+    pub keys: Vec<Key>,
+    pub existing: Vec<Key>,
+}
+
+impl fmt::Display for KeyConflict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "\"{}\" conflicts with the existing binding \"{}\"",
+            format_keys(&self.keys),
+            format_keys(&self.existing)
+        )
+    }
+}
+
+impl std::error::Error for KeyConflict {}
+
+/// An error from parsing a key binding string.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct ParseKeyError {
+    /// The part of the string that isn't a valid key.
+    pub input: String,
+}
+
+impl fmt::Display for ParseKeyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\"{}\" is not a valid key", self.input)
+    }
+}
+
+impl std::error::Error for ParseKeyError {}
+
+impl<A> Default for KeyMap<A> {
+    fn default() -> KeyMap<A> {
+        KeyMap::new()
+    }
+}
+
+impl<A> KeyMap<A> {
+    pub fn new() -> KeyMap<A> {
+        KeyMap {
+            bindings: Vec::new(),
+        }
+    }
+
+    /// Bind a sequence of keys to an action.
+    ///
+    /// Fails if the keys are already bound, or if they start with (or are the start of) a
+    /// sequence that is already bound, since one of the two could never be used.
+    pub fn bind(&mut self, keys: &[Key], action: A) -> Result<(), KeyConflict> {
+        let conflict = self.bindings.iter().find(|(existing, _)| {
+            let len = existing.len().min(keys.len());
+            existing[..len] == keys[..len]
+        });
+        if let Some((existing, _)) = conflict {
+            return Err(KeyConflict {
+                keys: keys.to_vec(),
+                existing: existing.clone(),
+            });
+        }
+        if !keys.is_empty() {
+            self.bindings.push((keys.to_vec(), action));
+        }
+        Ok(())
+    }
+
+    /// Remove the binding for a sequence of keys, returning its action.
+    pub fn unbind(&mut self, keys: &[Key]) -> Option<A> {
+        let index = self.bindings.iter().position(|(bound, _)| bound == keys)?;
+        Some(self.bindings.remove(index).1)
+    }
+
+    /// The action bound to a single key.
+    pub fn get(&self, key: Key) -> Option<&A> {
+        match self.lookup(&[key]) {
+            KeyMatch::Action(action) => Some(action),
+            _ => None,
+        }
+    }
+
+    /// Look up a sequence of keys, e.g. the keys pressed so far.
+    pub fn lookup(&self, keys: &[Key]) -> KeyMatch<'_, A> {
+        let mut result = KeyMatch::None;
+        for (bound, action) in &self.bindings {
+            if bound == keys {
+                return KeyMatch::Action(action);
+            } else if bound.starts_with(keys) && !keys.is_empty() {
+                result = KeyMatch::Prefix;
+            }
+        }
+        result
+    }
+
+    /// Every binding, in the order they were added.
+    pub fn iter(&self) -> impl Iterator<Item = (&[Key], &A)> {
+        self.bindings
+            .iter()
+            .map(|(keys, action)| (keys.as_slice(), action))
+    }
+}
+
+/// Parse a key binding string like `"ctrl-x ctrl-c"` into a sequence of keys.
+///
+/// Keys are separated by spaces. A key is a single character, a name (`enter`, `tab`, `space`,
+/// `esc`, `backspace`, `delete`, `insert`, `left`, `right`, `up`, `down`, `home`, `end`,
+/// `pageup`, `pagedown`, `shift-tab`, or `f1` to `f12`), or `ctrl-` or `alt-` followed by a
+/// character. Names and modifiers are case insensitive, and `C-` and `M-` can be used for
+/// `ctrl-` and `alt-`.
+pub fn parse_keys(s: &str) -> Result<Vec<Key>, ParseKeyError> {
+    s.split_whitespace().map(parse_key).collect()
+}
+
+/// Write a sequence of keys in the format read by `parse_keys`.
+pub fn format_keys(keys: &[Key]) -> String {
+    keys.iter()
+        .map(|&key| format_key(key))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn parse_key(s: &str) -> Result<Key, ParseKeyError> {
+    let error = || ParseKeyError {
+        input: s.to_string(),
+    };
+    let single = |s: &str| {
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (Some(ch), None) => Some(ch),
+            _ => None,
+        }
+    };
+    if let Some(ch) = single(s) {
+        return Ok(Key::Char(ch));
+    }
+    let lower = s.to_lowercase();
+    for (prefix, modifier) in [
+        ("ctrl-", Key::Ctrl as fn(char) -> Key),
+        ("c-", Key::Ctrl),
+        ("alt-", Key::Alt),
+        ("m-", Key::Alt),
+    ] {
+        if lower.starts_with(prefix) && lower != "shift-tab" {
+            let rest = &s[prefix.len()..];
+            let ch = match parse_key(rest)? {
+                Key::Char(ch) => ch,
+                _ => return Err(error()),
+            };
+            // Terminals can't tell Ctrl-A from Ctrl-a.
+            return Ok(modifier(if prefix.starts_with('c') {
+                ch.to_ascii_lowercase()
+            } else {
+                ch
+            }));
+        }
+    }
+    let key = match lower.as_str() {
+        "enter" | "return" => Key::Char('\n'),
+        "tab" => Key::Char('\t'),
+        "space" => Key::Char(' '),
+        "esc" | "escape" => Key::Esc,
+        "backspace" => Key::Backspace,
+        "delete" | "del" => Key::Delete,
+        "insert" | "ins" => Key::Insert,
+        "left" => Key::Left,
+        "right" => Key::Right,
+        "up" => Key::Up,
+        "down" => Key::Down,
+        "home" => Key::Home,
+        "end" => Key::End,
+        "pageup" => Key::PageUp,
+        "pagedown" => Key::PageDown,
+        "shift-tab" | "backtab" => Key::BackTab,
+        "null" => Key::Null,
+        _ => match lower.strip_prefix('f').and_then(|n| n.parse().ok()) {
+            Some(n @ 1..=12) => Key::F(n),
+            _ => return Err(error()),
+        },
+    };
+    Ok(key)
+}
+
+fn format_key(key: Key) -> String {
+    let name = |ch: char| match ch {
+        '\n' => "enter".to_string(),
+        '\t' => "tab".to_string(),
+        ' ' => "space".to_string(),
+        ch => ch.to_string(),
+    };
+    match key {
+        Key::Char(ch) => name(ch),
+        Key::Ctrl(ch) => format!("ctrl-{}", name(ch)),
+        Key::Alt(ch) => format!("alt-{}", name(ch)),
+        Key::F(n) => format!("f{}", n),
+        Key::Esc => "esc".to_string(),
+        Key::Backspace => "backspace".to_string(),
+        Key::Delete => "delete".to_string(),
+        Key::Insert => "insert".to_string(),
+        Key::Left => "left".to_string(),
+        Key::Right => "right".to_string(),
+        Key::Up => "up".to_string(),
+        Key::Down => "down".to_string(),
+        Key::Home => "home".to_string(),
+        Key::End => "end".to_string(),
+        Key::PageUp => "pageup".to_string(),
+        Key::PageDown => "pagedown".to_string(),
+        Key::BackTab => "shift-tab".to_string(),
+        _ => "null".to_string(),
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<A: serde::Serialize> serde::Serialize for KeyMap<A> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeMap;
+        let mut map = serializer.serialize_map(Some(self.bindings.len()))?;
+        for (keys, action) in &self.bindings {
+            map.serialize_entry(&format_keys(keys), action)?;
+        }
+        map.end()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, A: serde::Deserialize<'de>> serde::Deserialize<'de> for KeyMap<A> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<KeyMap<A>, D::Error> {
+        use serde::de::Error;
+        let bindings = std::collections::BTreeMap::<String, A>::deserialize(deserializer)?;
+        let mut map = KeyMap::new();
+        for (keys, action) in bindings {
+            let keys = parse_keys(&keys).map_err(D::Error::custom)?;
+            map.bind(&keys, action).map_err(D::Error::custom)?;
+        }
+        Ok(map)
+    }
+}
@@ -0,0 +1,277 @@
+use std::ops::{BitOr, BitOrAssign};
+use termion::event::Key;
+
+/// Turn on the kitty keyboard protocol, with keys disambiguated, event types (release and
+/// repeat) reported, alternate (shifted) keys reported, and all keys sent as escape codes.
+pub(crate) const KITTY_ENABLE: &str = "\x1b[>15u";
+/// Go back to whatever keyboard mode was in use before `KITTY_ENABLE`.
+pub(crate) const KITTY_DISABLE: &str = "\x1b[<u";
+
+/// A key event with full detail, as sent by terminals supporting the kitty keyboard protocol
+/// (see `AppBuilder::kitty_keyboard`).
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct KeyEvent {
+    pub code: KeyCode,
+    pub modifiers: Modifiers,
+    pub kind: KeyEventKind,
+}
+
+/// A key on the keyboard.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum KeyCode {
+    /// A key that types a character. If Shift is held, this is the shifted character (e.g.
+    /// `'A'` rather than `'a'`).
+    Char(char),
+    Enter,
+    Tab,
+    Backspace,
+    Esc,
+    Left,
+    Right,
+    Up,
+    Down,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    Insert,
+    Delete,
+    /// A function key, from F1 to F35.
+    F(u8),
+    /// Any other key, like the modifier keys themselves or media keys, by its code in the
+    /// kitty protocol.
+    Other(u32),
+}
+
+/// Whether a key was pressed, held down, or released.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+pub enum KeyEventKind {
+    #[default]
+    Press,
+    Repeat,
+    Release,
+}
+
+/// The modifier keys held during a key event.
+///
+/// Modifiers can be combined with `|`, e.g. `Modifiers::CTRL | Modifiers::SHIFT`.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct Modifiers {
+    bits: u8,
+}
+
+impl Modifiers {
+    pub const SHIFT: Modifiers = Modifiers { bits: 1 };
+    pub const ALT: Modifiers = Modifiers { bits: 1 << 1 };
+    pub const CTRL: Modifiers = Modifiers { bits: 1 << 2 };
+    pub const SUPER: Modifiers = Modifiers { bits: 1 << 3 };
+    pub const HYPER: Modifiers = Modifiers { bits: 1 << 4 };
+    pub const META: Modifiers = Modifiers { bits: 1 << 5 };
+    pub const CAPS_LOCK: Modifiers = Modifiers { bits: 1 << 6 };
+    pub const NUM_LOCK: Modifiers = Modifiers { bits: 1 << 7 };
+
+    /// No modifiers held.
+    pub const fn empty() -> Modifiers {
+        Modifiers { bits: 0 }
+    }
+
+    /// The raw bit representation of the modifiers, as used by the kitty protocol.
+    pub fn bits(self) -> u8 {
+        self.bits
+    }
+
+    pub fn from_bits(bits: u8) -> Modifiers {
+        Modifiers { bits }
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.bits == 0
+    }
+
+    /// Whether all the modifiers in `other` are also held in `self`.
+    pub fn contains(self, other: Modifiers) -> bool {
+        self.bits & other.bits == other.bits
+    }
+
+    pub fn insert(&mut self, other: Modifiers) {
+        self.bits |= other.bits;
+    }
+
+    pub fn remove(&mut self, other: Modifiers) {
+        self.bits &= !other.bits;
+    }
+}
+
+impl BitOr for Modifiers {
+    type Output = Modifiers;
+    fn bitor(self, other: Modifiers) -> Modifiers {
+        Modifiers {
+            bits: self.bits | other.bits,
+        }
+    }
+}
+
+impl BitOrAssign for Modifiers {
+    fn bitor_assign(&mut self, other: Modifiers) {
+        self.bits |= other.bits;
+    }
+}
+
+impl KeyEvent {
+    pub fn new(code: KeyCode, modifiers: Modifiers) -> KeyEvent {
+        KeyEvent {
+            code,
+            modifiers,
+            kind: KeyEventKind::Press,
+        }
+    }
+
+    /// The closest `Key`, as it would be received without the kitty protocol. Releases, and
+    /// keys that can't be represented, give `None`.
+    pub fn to_key(&self) -> Option<Key> {
+        if self.kind == KeyEventKind::Release {
+            return None;
+        }
+        // Lock keys don't change what a key means here.
+        let mut modifiers = self.modifiers;
+        modifiers.remove(Modifiers::CAPS_LOCK | Modifiers::NUM_LOCK);
+        let char_key = |ch: char| {
+            if modifiers.contains(Modifiers::CTRL) {
+                Some(Key::Ctrl(ch.to_ascii_lowercase()))
+            } else if modifiers.contains(Modifiers::ALT) {
+                Some(Key::Alt(ch))
+            } else {
+                Some(Key::Char(ch))
+            }
+        };
+        match self.code {
+            KeyCode::Char(ch) => char_key(ch),
+            KeyCode::Enter => char_key('\n'),
+            KeyCode::Tab if modifiers.contains(Modifiers::SHIFT) => Some(Key::BackTab),
+            KeyCode::Tab => char_key('\t'),
+            KeyCode::Backspace => Some(Key::Backspace),
+            KeyCode::Esc => Some(Key::Esc),
+            KeyCode::Left => Some(Key::Left),
+            KeyCode::Right => Some(Key::Right),
+            KeyCode::Up => Some(Key::Up),
+            KeyCode::Down => Some(Key::Down),
+            KeyCode::Home => Some(Key::Home),
+            KeyCode::End => Some(Key::End),
+            KeyCode::PageUp => Some(Key::PageUp),
+            KeyCode::PageDown => Some(Key::PageDown),
+            KeyCode::Insert => Some(Key::Insert),
+            KeyCode::Delete => Some(Key::Delete),
+            KeyCode::F(n @ 1..=12) => Some(Key::F(n)),
+            KeyCode::F(_) | KeyCode::Other(_) => None,
+        }
+    }
+}
+
+impl From<Key> for KeyEvent {
+    /// A key press as it would be received with the kitty protocol.
+    fn from(key: Key) -> KeyEvent {
+        let (code, modifiers) = match key {
+            Key::Char('\n') => (KeyCode::Enter, Modifiers::empty()),
+            Key::Char('\t') => (KeyCode::Tab, Modifiers::empty()),
+            Key::Char(ch) => (KeyCode::Char(ch), Modifiers::empty()),
+            Key::Ctrl(ch) => (KeyCode::Char(ch), Modifiers::CTRL),
+            Key::Alt(ch) => (KeyCode::Char(ch), Modifiers::ALT),
+            Key::BackTab => (KeyCode::Tab, Modifiers::SHIFT),
+            Key::Backspace => (KeyCode::Backspace, Modifiers::empty()),
+            Key::Esc => (KeyCode::Esc, Modifiers::empty()),
+            Key::Left => (KeyCode::Left, Modifiers::empty()),
+            Key::Right => (KeyCode::Right, Modifiers::empty()),
+            Key::Up => (KeyCode::Up, Modifiers::empty()),
+            Key::Down => (KeyCode::Down, Modifiers::empty()),
+            Key::Home => (KeyCode::Home, Modifiers::empty()),
+            Key::End => (KeyCode::End, Modifiers::empty()),
+            Key::PageUp => (KeyCode::PageUp, Modifiers::empty()),
+            Key::PageDown => (KeyCode::PageDown, Modifiers::empty()),
+            Key::Insert => (KeyCode::Insert, Modifiers::empty()),
+            Key::Delete => (KeyCode::Delete, Modifiers::empty()),
+            Key::F(n) => (KeyCode::F(n), Modifiers::empty()),
+            Key::Null | Key::__IsNotComplete => (KeyCode::Char('\0'), Modifiers::empty()),
+        };
+        KeyEvent::new(code, modifiers)
+    }
+}
+
+/// The length of the kitty key sequence at the start of `bytes`, if there is one.
+///
+/// These are `CSI ... u` sequences, and legacy sequences for special keys (like `CSI 1;5A` for
+/// Ctrl-Up) that have modifiers or an event type, neither of which termion understands.
+pub(crate) fn sequence_len(bytes: &[u8]) -> Option<usize> {
+    let params = bytes.strip_prefix(b"\x1b[")?;
+    let len = params
+        .iter()
+        .take_while(|b| matches!(b, b'0'..=b'9' | b';' | b':'))
+        .count();
+    if len == 0 || !params[0].is_ascii_digit() {
+        return None;
+    }
+    match params.get(len)? {
+        b'u' => Some(len + 3),
+        b'A'..=b'D' | b'E' | b'F' | b'H' | b'P'..=b'S' | b'~' if params[..len].contains(&b';') => {
+            Some(len + 3)
+        }
+        _ => None,
+    }
+}
+
+/// Parse a sequence found by `sequence_len`.
+pub(crate) fn parse(sequence: &[u8]) -> Option<KeyEvent> {
+    let (&last, params) = sequence.strip_prefix(b"\x1b[")?.split_last()?;
+    let params = std::str::from_utf8(params).ok()?;
+    let mut fields = params.split(';');
+    let mut keys = fields.next()?.split(':');
+    let number: u32 = keys.next()?.parse().ok()?;
+    let shifted: Option<u32> = keys.next().and_then(|key| key.parse().ok());
+    let mut state = fields.next().unwrap_or("1").split(':');
+    let modifiers = state.next()?.parse::<u16>().ok()?.saturating_sub(1);
+    let modifiers = Modifiers::from_bits(modifiers as u8);
+    let kind = match state.next().unwrap_or("1") {
+        "1" => KeyEventKind::Press,
+        "2" => KeyEventKind::Repeat,
+        "3" => KeyEventKind::Release,
+        _ => return None,
+    };
+    let code = match (last, number) {
+        (b'u', 9) => KeyCode::Tab,
+        (b'u', 13) => KeyCode::Enter,
+        (b'u', 27) => KeyCode::Esc,
+        (b'u', 127) => KeyCode::Backspace,
+        (b'u', 57376..=57398) => KeyCode::F((number - 57376 + 13) as u8),
+        (b'u', 57344..=63743) => KeyCode::Other(number),
+        (b'u', _) => {
+            let number = match shifted {
+                Some(shifted) if modifiers.contains(Modifiers::SHIFT) => shifted,
+                _ => number,
+            };
+            KeyCode::Char(char::from_u32(number)?)
+        }
+        (b'~', 2) => KeyCode::Insert,
+        (b'~', 3) => KeyCode::Delete,
+        (b'~', 5) => KeyCode::PageUp,
+        (b'~', 6) => KeyCode::PageDown,
+        (b'~', 1) | (b'~', 7) => KeyCode::Home,
+        (b'~', 4) | (b'~', 8) => KeyCode::End,
+        (b'~', 11..=15) => KeyCode::F((number - 10) as u8),
+        (b'~', 17..=21) => KeyCode::F((number - 11) as u8),
+        (b'~', 23..=24) => KeyCode::F((number - 12) as u8),
+        (b'A', _) => KeyCode::Up,
+        (b'B', _) => KeyCode::Down,
+        (b'C', _) => KeyCode::Right,
+        (b'D', _) => KeyCode::Left,
+        // The middle key of the keypad.
+        (b'E', _) => KeyCode::Other(57427),
+        (b'F', _) => KeyCode::End,
+        (b'H', _) => KeyCode::Home,
+        (b'P'..=b'S', _) => KeyCode::F(last - b'P' + 1),
+        _ => return None,
+    };
+    Some(KeyEvent {
+        code,
+        modifiers,
+        kind,
+    })
+}
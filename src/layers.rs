@@ -0,0 +1,150 @@
+use crate::glyph::Glyph;
+use crate::rect::Rect;
+use crate::screen::{Char, Color, Frame};
+
+/// A stack of frames that are composited into a single frame, for things like popups and HUDs
+/// that are drawn over the rest of the UI without destroying it.
+///
+/// Layers start out filled with `Char::TRANSPARENT`, so only the cells that are drawn on a
+/// layer cover the layers below it. Layer 0 is at the bottom.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct Layers {
+    layers: Vec<Frame>,
+}
+
+impl Layers {
+    /// Create `count` transparent layers of the given size.
+    pub fn new(count: usize, rows: usize, cols: usize) -> Layers {
+        Layers {
+            layers: (0..count).map(|_| transparent(rows, cols)).collect(),
+        }
+    }
+
+    /// The number of layers.
+    pub fn len(&self) -> usize {
+        self.layers.len()
+    }
+
+    /// Whether there are no layers.
+    pub fn is_empty(&self) -> bool {
+        self.layers.is_empty()
+    }
+
+    /// The layer at position `z` (0 is the bottom). Will panic if there is no such layer.
+    pub fn layer(&self, z: usize) -> &Frame {
+        &self.layers[z]
+    }
+
+    /// The layer at position `z` (0 is the bottom), for drawing on. Will panic if there is no
+    /// such layer.
+    pub fn layer_mut(&mut self, z: usize) -> &mut Frame {
+        &mut self.layers[z]
+    }
+
+    /// Make every layer transparent again, changing its size to the one given.
+    ///
+    /// Like the frame of an `App`, layers are usually redrawn from scratch each time.
+    pub fn clear(&mut self, rows: usize, cols: usize) {
+        for layer in &mut self.layers {
+            if layer.rows() == rows && layer.columns() == cols {
+                layer.fill_rect(0, 0, rows, cols, Char::TRANSPARENT);
+            } else {
+                *layer = transparent(rows, cols);
+            }
+        }
+    }
+}
+
+impl Frame {
+    /// Draw the layers over this frame, from the bottom up, skipping transparent cells.
+    ///
+    /// Any part of a layer that lies outside the frame is ignored.
+    pub fn composite(&mut self, layers: &Layers) {
+        for layer in &layers.layers {
+            self.blit(layer, 0, 0);
+        }
+    }
+
+    /// Copy the whole of another frame into this one, with its top-left corner at the given
+    /// position.
+    ///
+    /// Transparent cells in the source are skipped, so whatever is already there shows through.
+    /// Colors with an alpha channel (`Color::Rgba`) are blended with what's already there, and a
+    /// transparent cell with an `Rgba` background tints the cell below it. Any part of the source that lies outside this frame is ignored.
+    pub fn blit(&mut self, source: &Frame, dst_row: usize, dst_col: usize) {
+        self.blit_rect(source, source.area(), dst_row, dst_col);
+    }
+
+    /// Copy part of another frame into this one, with the top-left corner of the part at the
+    /// given position.
+    ///
+    /// Transparent cells in the source are skipped, and any half of a wide glyph cut off by the
+    /// edge of `src` is copied as a space. Any part that lies outside this frame is ignored.
+    pub fn blit_rect(&mut self, source: &Frame, src: Rect, dst_row: usize, dst_col: usize) {
+        let src = src.intersection(source.area());
+        for row in src.row..src.bottom() {
+            for col in src.col..src.right() {
+                let ch = source.get(row, col);
+                let (row, col) = (dst_row + (row - src.row), dst_col + (col - src.col));
+                if ch.is_transparent() {
+                    self.tint(row, col, ch.color_bg);
+                    continue;
+                }
+                let cut_off = if ch.is_continuation() {
+                    // Otherwise it was filled in along with the wide glyph to its left.
+                    col == src.col
+                } else {
+                    ch.width() == 2 && col + 1 == src.right()
+                };
+                let ch = if cut_off {
+                    Char {
+                        glyph: Glyph::from(' '),
+                        ..ch
+                    }
+                } else if ch.is_continuation() {
+                    continue;
+                } else {
+                    ch
+                };
+                let ch = match self.try_get(row, col) {
+                    Some(below) => blend(ch, below),
+                    None => continue,
+                };
+                self.set_clipped(row, col, ch);
+            }
+        }
+    }
+
+    /// Blend a translucent color over a cell, if it is in bounds.
+    fn tint(&mut self, row: usize, col: usize, color: Color) {
+        if let (Color::Rgba(..), Some(below)) = (color, self.try_get(row, col)) {
+            // The wide glyph to the left takes care of its continuation cell.
+            if !below.is_continuation() {
+                let ch = Char {
+                    color_fg: color.blend_over(below.color_fg, Color::White),
+                    color_bg: color.blend_over(below.color_bg, Color::Black),
+                    ..below
+                };
+                self.set_clipped(row, col, ch);
+            }
+        }
+    }
+}
+
+/// Blend the colors of a cell over the cell below it. The glyph is drawn over the background, so
+/// the foreground is blended with that.
+fn blend(ch: Char, below: Char) -> Char {
+    let color_bg = ch.color_bg.blend_over(below.color_bg, Color::Black);
+    Char {
+        color_fg: ch.color_fg.blend_over(color_bg, Color::White),
+        color_bg,
+        ..ch
+    }
+}
+
+/// A frame full of transparent cells.
+fn transparent(rows: usize, cols: usize) -> Frame {
+    let mut frame = Frame::new(rows, cols);
+    frame.fill_rect(0, 0, rows, cols, Char::TRANSPARENT);
+    frame
+}
@@ -0,0 +1,403 @@
+use crate::rect::Rect;
+use std::ops::Range;
+
+/// How big something should be along one axis.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Constraint {
+    /// Exactly this many cells.
+    Fixed(usize),
+    /// This percentage of the space available (not counting gaps).
+    Percentage(u16),
+    /// At least this many cells. Any space left over after everything else is shared between
+    /// the `Min` constraints.
+    Min(usize),
+}
+
+/// Work out sizes from constraints, given the space available.
+pub(crate) fn resolve(constraints: &[Constraint], available: usize) -> Vec<usize> {
+    let mut sizes: Vec<usize> = constraints
+        .iter()
+        .map(|constraint| match *constraint {
+            Constraint::Fixed(size) | Constraint::Min(size) => size,
+            Constraint::Percentage(percent) => available * usize::from(percent.min(100)) / 100,
+        })
+        .collect();
+    grow(constraints, &mut sizes, available);
+    sizes
+}
+
+/// Share any space left over between the `Min` constraints.
+fn grow(constraints: &[Constraint], sizes: &mut [usize], available: usize) {
+    let mins = constraints
+        .iter()
+        .filter(|c| matches!(c, Constraint::Min(_)))
+        .count();
+    let left_over = available.saturating_sub(sizes.iter().sum());
+    if let Some(share) = left_over.checked_div(mins) {
+        let growable = constraints
+            .iter()
+            .zip(sizes)
+            .filter(|(c, _)| matches!(c, Constraint::Min(_)));
+        for (i, (_, size)) in growable.enumerate() {
+            // Give any remainder to the first ones.
+            *size += share + usize::from(i < left_over % mins);
+        }
+    }
+}
+
+/// Space to leave around the inside edge of a region.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+pub struct Padding {
+    pub top: usize,
+    pub right: usize,
+    pub bottom: usize,
+    pub left: usize,
+}
+
+impl Padding {
+    pub fn new(top: usize, right: usize, bottom: usize, left: usize) -> Padding {
+        Padding {
+            top,
+            right,
+            bottom,
+            left,
+        }
+    }
+
+    /// The same padding on every side.
+    pub fn uniform(size: usize) -> Padding {
+        Padding::new(size, size, size, size)
+    }
+}
+
+impl Rect {
+    /// The part of the rectangle inside some padding. If the padding is too big, the result is
+    /// empty.
+    pub fn inner(&self, padding: Padding) -> Rect {
+        let height = self.height.saturating_sub(padding.top + padding.bottom);
+        let width = self.width.saturating_sub(padding.left + padding.right);
+        Rect::new(
+            (self.row + padding.top).min(self.bottom()),
+            (self.col + padding.left).min(self.right()),
+            height,
+            width,
+        )
+    }
+}
+
+/// The axis a `Flex` lays its items out along.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+pub enum Direction {
+    /// Left to right.
+    #[default]
+    Horizontal,
+    /// Top to bottom.
+    Vertical,
+}
+
+/// Where items go along the main axis of a `Flex`, when there is space left over.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+pub enum Justify {
+    #[default]
+    Start,
+    Center,
+    End,
+    /// The space is shared out between the items.
+    SpaceBetween,
+    /// The space is shared out around the items, with half as much at each end.
+    SpaceAround,
+}
+
+/// Where items go across a line of a `Flex`, if they don't fill it.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+pub enum CrossAlign {
+    #[default]
+    Start,
+    Center,
+    End,
+}
+
+/// An item in a `Flex` layout.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct FlexItem {
+    /// The size along the main axis.
+    pub size: Constraint,
+    /// The size across the main axis. If `None`, the item fills its line.
+    pub cross: Option<usize>,
+}
+
+impl FlexItem {
+    pub fn new(size: Constraint) -> FlexItem {
+        FlexItem { size, cross: None }
+    }
+}
+
+impl From<Constraint> for FlexItem {
+    fn from(size: Constraint) -> FlexItem {
+        FlexItem::new(size)
+    }
+}
+
+/// A flexbox-like layout, which places items in a row or column.
+///
+/// `split` returns a rectangle for each item, in order. Items that don't fit are given empty
+/// rectangles.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Hash)]
+pub struct Flex {
+    pub items: Vec<FlexItem>,
+    pub direction: Direction,
+    /// Whether to start a new line when items don't fit, rather than cutting them off.
+    pub wrap: bool,
+    /// The space between items, and between lines when wrapping.
+    pub gap: usize,
+    pub padding: Padding,
+    pub justify: Justify,
+    pub align: CrossAlign,
+}
+
+impl Flex {
+    pub fn new(direction: Direction, items: Vec<FlexItem>) -> Flex {
+        Flex {
+            items,
+            direction,
+            ..Flex::default()
+        }
+    }
+
+    pub fn split(&self, area: Rect) -> Vec<Rect> {
+        if self.items.is_empty() {
+            return Vec::new();
+        }
+        let inner = area.inner(self.padding);
+        let (main_len, cross_len) = match self.direction {
+            Direction::Horizontal => (inner.width, inner.height),
+            Direction::Vertical => (inner.height, inner.width),
+        };
+        let constraints: Vec<Constraint> = self.items.iter().map(|item| item.size).collect();
+        let gaps = self.gap * self.items.len().saturating_sub(1);
+        let mut sizes = resolve(&constraints, main_len.saturating_sub(gaps));
+
+        // Break the items into lines.
+        let mut lines: Vec<Range<usize>> = Vec::new();
+        lines.push(0..0);
+        if self.wrap {
+            let mut used = 0;
+            for (i, &size) in sizes.iter().enumerate() {
+                let line = lines.last_mut().unwrap();
+                if line.start != line.end && used + self.gap + size > main_len {
+                    lines.push(i..i + 1);
+                    used = size;
+                } else {
+                    used += if line.start == line.end {
+                        size
+                    } else {
+                        self.gap + size
+                    };
+                    line.end = i + 1;
+                }
+            }
+        } else {
+            lines[0].end = self.items.len();
+        }
+
+        // Lines where every item has a cross size are as big as their biggest item. The rest
+        // share what's left.
+        let fixed_cross = |line: &Range<usize>| {
+            self.items[line.clone()]
+                .iter()
+                .map(|item| item.cross)
+                .try_fold(0, |max, cross| cross.map(|cross| max.max(cross)))
+        };
+        let line_gaps = self.gap * (lines.len() - 1);
+        let fixed: usize = lines.iter().filter_map(fixed_cross).sum();
+        let stretchy = lines.iter().filter(|l| fixed_cross(l).is_none()).count();
+        let stretch = cross_len
+            .saturating_sub(fixed + line_gaps)
+            .checked_div(stretchy)
+            .unwrap_or(0);
+
+        let mut rects = vec![Rect::new(inner.row, inner.col, 0, 0); self.items.len()];
+        let mut cross_pos = 0;
+        for line in lines {
+            let line_cross = if self.wrap {
+                fixed_cross(&line).unwrap_or(stretch)
+            } else {
+                cross_len
+            };
+            let count = line.len();
+            let line_gaps = self.gap * count.saturating_sub(1);
+            if self.wrap {
+                grow(
+                    &constraints[line.clone()],
+                    &mut sizes[line.clone()],
+                    main_len.saturating_sub(line_gaps),
+                );
+            }
+            let used: usize = sizes[line.clone()].iter().sum::<usize>() + line_gaps;
+            let free = main_len.saturating_sub(used);
+            let (mut main_pos, spacing) = match self.justify {
+                Justify::Start => (0, 0),
+                Justify::Center => (free / 2, 0),
+                Justify::End => (free, 0),
+                Justify::SpaceBetween if count > 1 => (0, free / (count - 1)),
+                Justify::SpaceBetween => (0, 0),
+                Justify::SpaceAround => (free / count / 2, free / count),
+            };
+            for i in line {
+                let item = self.items[i];
+                let cross = item.cross.unwrap_or(line_cross).min(line_cross);
+                let offset = match self.align {
+                    CrossAlign::Start => 0,
+                    CrossAlign::Center => (line_cross - cross) / 2,
+                    CrossAlign::End => line_cross - cross,
+                };
+                let (row, col, height, width) = match self.direction {
+                    Direction::Horizontal => (cross_pos + offset, main_pos, cross, sizes[i]),
+                    Direction::Vertical => (main_pos, cross_pos + offset, sizes[i], cross),
+                };
+                rects[i] =
+                    Rect::new(inner.row + row, inner.col + col, height, width).intersection(inner);
+                main_pos += sizes[i] + self.gap + spacing;
+            }
+            cross_pos += line_cross + self.gap;
+        }
+        rects
+    }
+}
+
+/// A grid layout, with rows and columns sized by constraints.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Hash)]
+pub struct Grid {
+    pub rows: Vec<Constraint>,
+    pub columns: Vec<Constraint>,
+    /// The space between rows.
+    pub row_gap: usize,
+    /// The space between columns.
+    pub column_gap: usize,
+    pub padding: Padding,
+}
+
+impl Grid {
+    pub fn new(rows: Vec<Constraint>, columns: Vec<Constraint>) -> Grid {
+        Grid {
+            rows,
+            columns,
+            ..Grid::default()
+        }
+    }
+
+    /// The rectangles of every cell in the grid, one `Vec` per row.
+    pub fn split(&self, area: Rect) -> Vec<Vec<Rect>> {
+        (0..self.rows.len())
+            .map(|row| {
+                (0..self.columns.len())
+                    .map(|col| self.span(area, row, col, 1, 1))
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// The rectangle covering `rows` rows and `cols` columns, starting at the given cell. This
+    /// includes the gaps in between.
+    pub fn span(&self, area: Rect, row: usize, col: usize, rows: usize, cols: usize) -> Rect {
+        let inner = area.inner(self.padding);
+        let (top, height) = track(&self.rows, self.row_gap, inner.height, row, rows);
+        let (left, width) = track(&self.columns, self.column_gap, inner.width, col, cols);
+        Rect::new(inner.row + top, inner.col + left, height, width).intersection(inner)
+    }
+}
+
+/// The start and size of a run of tracks (rows or columns) in a grid.
+fn track(
+    constraints: &[Constraint],
+    gap: usize,
+    len: usize,
+    start: usize,
+    count: usize,
+) -> (usize, usize) {
+    let gaps = gap * constraints.len().saturating_sub(1);
+    let sizes = resolve(constraints, len.saturating_sub(gaps));
+    let start = start.min(sizes.len());
+    let end = (start + count).min(sizes.len());
+    let pos = sizes[..start].iter().map(|size| size + gap).sum();
+    let size = sizes[start..end].iter().sum::<usize>() + gap * (end - start).saturating_sub(1);
+    (pos, size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Constraint::{Fixed, Min, Percentage};
+
+    #[test]
+    fn left_over_space_goes_to_min_constraints() {
+        assert_eq!(resolve(&[Fixed(3), Min(1), Min(1)], 10), [3, 4, 3]);
+        assert_eq!(resolve(&[Percentage(50), Percentage(200)], 10), [5, 10]);
+        assert_eq!(resolve(&[Fixed(8), Min(4)], 10), [8, 4]);
+        assert_eq!(resolve(&[Fixed(2), Percentage(10)], 10), [2, 1]);
+    }
+
+    #[test]
+    fn padding_is_clamped() {
+        let area = Rect::new(1, 2, 5, 10);
+        assert_eq!(area.inner(Padding::new(1, 2, 3, 4)), Rect::new(2, 6, 1, 4));
+        assert_eq!(area.inner(Padding::uniform(6)), Rect::new(6, 8, 0, 0));
+    }
+
+    #[test]
+    fn flex_places_items_along_a_line() {
+        let mut flex = Flex::new(Direction::Horizontal, vec![Fixed(2).into(), Min(1).into()]);
+        flex.gap = 1;
+        let area = Rect::new(0, 0, 3, 10);
+        assert_eq!(
+            flex.split(area),
+            [Rect::new(0, 0, 3, 2), Rect::new(0, 3, 3, 7)]
+        );
+        flex.items = vec![Fixed(2).into(), Fixed(2).into()];
+        flex.justify = Justify::SpaceBetween;
+        assert_eq!(
+            flex.split(area),
+            [Rect::new(0, 0, 3, 2), Rect::new(0, 8, 3, 2)]
+        );
+        flex.justify = Justify::End;
+        flex.align = CrossAlign::Center;
+        flex.items[0].cross = Some(1);
+        assert_eq!(
+            flex.split(area),
+            [Rect::new(1, 5, 1, 2), Rect::new(0, 8, 3, 2)]
+        );
+    }
+
+    #[test]
+    fn flex_wraps_items_onto_new_lines() {
+        let items = vec![Fixed(4).into(), Fixed(4).into(), Min(2).into()];
+        let mut flex = Flex::new(Direction::Vertical, items);
+        flex.wrap = true;
+        flex.padding = Padding::uniform(1);
+        let rects = flex.split(Rect::new(0, 0, 8, 10));
+        assert_eq!(
+            rects,
+            [
+                Rect::new(1, 1, 4, 4),
+                Rect::new(1, 5, 4, 4),
+                Rect::new(5, 5, 2, 4),
+            ]
+        );
+    }
+
+    #[test]
+    fn grid_cells_and_spans() {
+        let mut grid = Grid::new(vec![Fixed(1), Min(0)], vec![Percentage(50), Min(0)]);
+        grid.column_gap = 2;
+        let area = Rect::new(0, 0, 5, 12);
+        assert_eq!(
+            grid.split(area),
+            [
+                [Rect::new(0, 0, 1, 5), Rect::new(0, 7, 1, 5)],
+                [Rect::new(1, 0, 4, 5), Rect::new(1, 7, 4, 5)],
+            ]
+        );
+        assert_eq!(grid.span(area, 0, 0, 2, 2), Rect::new(0, 0, 5, 12));
+        assert_eq!(grid.span(area, 1, 1, 5, 5), Rect::new(1, 7, 4, 5));
+    }
+}
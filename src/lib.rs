@@ -1,107 +1,1238 @@
-pub use crate::screen::{Char, Color, Frame};
+pub use crate::ansi::{parse_ansi, AnsiParser};
+pub use crate::backend::{Backend, TermionBackend, TestBackend, WriterBackend};
+pub use crate::bar::{StatusBar, TitleBar};
+pub use crate::block::Block;
+pub use crate::border::{BorderStyle, BoxOptions};
+pub use crate::broadcast::{Broadcast, ClientId};
+pub use crate::camera::{Camera, World};
+pub use crate::canvas::{BrailleCanvas, HalfBlockCanvas};
+pub use crate::chart::{LinePlot, Sparkline};
+pub use crate::chord::{Chord, ChordMatcher};
+pub use crate::color::ColorSupport;
+pub use crate::cursor::CursorShape;
+pub use crate::editor::Editor;
+pub use crate::error::Error;
+pub use crate::event::{Event, Mouse, MouseAction};
+use crate::fixed::FixedSize;
+pub use crate::focus::{FocusChange, FocusManager};
+pub use crate::gif::FrameRecording;
+pub use crate::glyph::Glyph;
+pub use crate::gradient::GradientDirection;
+#[cfg(feature = "images")]
+pub use crate::images::{KittyImage, SixelImage};
+pub use crate::input::TextInput;
+pub use crate::keymap::{format_keys, parse_keys, KeyConflict, KeyMap, KeyMatch, ParseKeyError};
+pub use crate::kitty::{KeyCode, KeyEvent, KeyEventKind, Modifiers};
+pub use crate::layers::Layers;
+pub use crate::layout::{
+    Constraint, CrossAlign, Direction, Flex, FlexItem, Grid, Justify, Padding,
+};
+pub use crate::link::Link;
+pub use crate::list::{List, ListState};
+pub use crate::logger::{LogLevel, LogRecord, LogView, TermbufferLogger};
+pub use crate::modes::{InputModes, Mode, Resolved};
+#[cfg(feature = "net")]
+pub use crate::net::{TelnetBackend, TelnetServer, TelnetStream};
+pub use crate::popup::{Anchor, Backdrop, Popup};
+pub use crate::progress::ProgressBar;
+use crate::record::{Output, Recorder};
+pub use crate::rect::Rect;
+pub use crate::region::Region;
+pub use crate::replay::{EventLog, RecordingBackend, ReplayBackend};
+pub use crate::screen::{
+    Attributes, CellChange, Char, Color, ControlPolicy, Frame, OutOfBounds, Style,
+};
+pub use crate::signal::JobControl;
+pub use crate::sim::SimBackend;
+pub use crate::span::{Line, Span, Text};
+pub use crate::spinner::{Spinner, SpinnerStyle};
+use crate::stats::DebugOverlay;
+pub use crate::stats::RenderStats;
+#[cfg(feature = "stream")]
+pub use crate::stream::{EventStream, NextEvent};
+pub use crate::table::Table;
+pub use crate::terminfo::Capabilities;
+pub use crate::text::{Align, TextOptions, VerticalAlign};
+pub use crate::viewer::TextView;
 use std::{
+    collections::VecDeque,
+    fs::OpenOptions,
     io::{self, Write},
     ops::{Deref, DerefMut},
+    path::PathBuf,
+    time::{Duration, Instant},
 };
-pub use termion::event::{Event, Key, MouseButton, MouseEvent};
-use termion::{
-    async_stdin, clear, cursor,
-    input::{Events, TermRead},
-    raw::IntoRawMode,
-    raw::RawTerminal,
-    terminal_size, AsyncReader,
-};
+use termion::clear;
+pub use termion::event::{Key, MouseButton};
 
+mod ansi;
+mod backend;
+mod bar;
+mod block;
+mod border;
+mod broadcast;
+mod camera;
+mod canvas;
+mod chart;
+mod chord;
+mod color;
+mod cursor;
+mod editor;
+mod error;
+mod event;
+mod export;
+mod fixed;
+mod focus;
+mod font;
+mod gif;
+mod glyph;
+mod gradient;
+#[cfg(feature = "images")]
+mod images;
+mod input;
+mod keymap;
+mod kitty;
+mod layers;
+mod layout;
+mod link;
+mod list;
+mod logger;
+mod modes;
+#[cfg(feature = "net")]
+mod net;
+mod osc;
+mod popup;
+mod progress;
+mod record;
+mod rect;
+mod region;
+mod replay;
 mod screen;
+mod signal;
+mod sim;
+mod span;
+mod spinner;
+mod stats;
+#[cfg(feature = "stream")]
+mod stream;
+mod sync;
+mod table;
+mod terminfo;
+pub mod testing;
+mod text;
+mod viewer;
+
+/// How often to check the terminal size while waiting for input.
+const RESIZE_POLL_INTERVAL: Duration = Duration::from_millis(100);
 
-pub struct App {
-    output: RawTerminal<io::Stdout>,
-    input: Events<AsyncReader>,
+/// Turn on mouse button and drag reporting, using the extended coordinate formats.
+const MOUSE_ENABLE: &str = "\x1b[?1000h\x1b[?1002h\x1b[?1015h\x1b[?1006h";
+const MOUSE_DISABLE: &str = "\x1b[?1006l\x1b[?1015l\x1b[?1002l\x1b[?1000l";
+
+/// Turn on reporting of the terminal window gaining and losing focus.
+const FOCUS_ENABLE: &str = "\x1b[?1004h";
+const FOCUS_DISABLE: &str = "\x1b[?1004l";
+
+pub struct App<B: Backend = TermionBackend> {
+    backend: B,
     screen: screen::Screen,
+    alternate_screen: bool,
+    capabilities: Capabilities,
+    /// The number of rows to draw in at the bottom of the terminal, if not the whole screen.
+    inline: Option<usize>,
+    /// The frame drawn in the middle of the terminal, if it has a fixed size.
+    fixed: Option<FixedSize>,
+    mouse: bool,
+    focus_events: bool,
+    kitty_keyboard: bool,
+    clipboard: bool,
+    cursor_shape: CursorShape,
+    debug_overlay: Option<DebugOverlay>,
+    /// Events to send before any others, e.g. a clipboard read locally.
+    queued: VecDeque<Event>,
+    /// The terminal size (cols, rows) last reported through `events`.
+    size: (usize, usize),
+    ticker: Option<Ticker>,
+    /// When the next frame is due, if there is a target frame rate.
+    frame_pacer: Option<Ticker>,
+    /// When the last frame was started with `draw`.
+    last_draw: Option<Instant>,
+    /// Whether an `Event::Suspend` has been sent and we should suspend on the next poll.
+    suspend_pending: bool,
+    recorder: Option<Recorder>,
+    frame_recording: Option<FrameRecording>,
+}
+
+/// Keeps track of when the next `Event::Tick` (or frame) is due.
+struct Ticker {
+    rate: Duration,
+    next: Instant,
+}
+
+impl Ticker {
+    /// Move on to the next tick, once the current one is due.
+    fn advance(&mut self, now: Instant) {
+        self.next += self.rate;
+        if self.next < now {
+            // We've fallen behind, so skip the missed ticks rather than sending a burst.
+            self.next = now + self.rate;
+        }
+    }
 }
 
 impl App {
     pub fn builder() -> AppBuilder {
         AppBuilder::default()
     }
+}
 
-    pub fn draw<'a>(&'a mut self) -> Draw<'a> {
-        let (cols, rows) = terminal_size().unwrap();
-        let (cols, rows) = (cols as usize, rows as usize);
+impl<B: Backend> App<B> {
+    /// Start drawing a new frame.
+    ///
+    /// If the size of the terminal can't be read, the frame is the size it was last known to
+    /// be, and the error is returned by `Draw::commit`.
+    pub fn draw<'a>(&'a mut self) -> Draw<'a, B> {
+        let (size, error) = match self.backend.size() {
+            Ok(size) => (size, None),
+            Err(e) => (self.size, Some(e)),
+        };
+        let (cols, mut rows) = size;
+        if let Some(height) = self.inline {
+            let height = height.min(rows);
+            self.screen.set_origin(rows - height);
+            rows = height;
+        }
         self.screen.prepare_next_frame(rows, cols);
+        if let Some(fixed) = &mut self.fixed {
+            let (rows, cols) = (fixed.frame.rows(), fixed.frame.columns());
+            fixed.frame.reset(rows, cols);
+        }
+        let now = self.backend.now();
+        let delta_time = self
+            .last_draw
+            .replace(now)
+            .map_or(Duration::ZERO, |last| now.saturating_duration_since(last));
         Draw {
-            output: &mut self.output,
+            output: Output {
+                writer: &mut self.backend,
+                recorder: self.recorder.as_mut(),
+            },
             screen: &mut self.screen,
+            fixed: self.fixed.as_mut(),
+            debug_overlay: self.debug_overlay.as_mut(),
+            frame_recording: self.frame_recording.as_mut(),
+            delta_time,
+            error,
+            committed: false,
+        }
+    }
+
+    /// The most recently drawn frame.
+    pub fn frame(&self) -> &Frame {
+        &self.screen.next
+    }
+
+    /// Whether frames are printed as plain text, rather than drawn on the terminal (see
+    /// `AppBuilder::plain` and `AppBuilder::plain_fallback`).
+    pub fn is_plain(&self) -> bool {
+        self.screen.plain
+    }
+
+    /// What the terminal can do, as detected when the app was built (see
+    /// `AppBuilder::capabilities`).
+    pub fn capabilities(&self) -> Capabilities {
+        self.capabilities
+    }
+
+    /// Measurements of rendering the most recently drawn frame.
+    pub fn stats(&self) -> RenderStats {
+        self.screen.stats
+    }
+
+    /// Whether the debug overlay is shown (see `App::set_debug_overlay`).
+    pub fn debug_overlay(&self) -> bool {
+        self.debug_overlay.is_some()
+    }
+
+    /// Show or hide an overlay in the top right corner of each frame with the frame rate, and
+    /// the time taken, cells changed and bytes written by the last render (see `RenderStats`).
+    ///
+    /// The overlay is drawn over the frame just before it is rendered, so it is also in
+    /// `App::frame`.
+    pub fn set_debug_overlay(&mut self, show: bool) {
+        if show != self.debug_overlay() {
+            self.debug_overlay = if show {
+                Some(DebugOverlay::default())
+            } else {
+                None
+            };
+        }
+    }
+
+    /// The frames drawn so far, if they are being recorded (see `AppBuilder::record_frames`).
+    pub fn frame_recording(&self) -> Option<&FrameRecording> {
+        self.frame_recording.as_ref()
+    }
+
+    /// Stop recording frames, returning those drawn so far, e.g. to save with
+    /// `FrameRecording::save_gif`.
+    pub fn take_frame_recording(&mut self) -> Option<FrameRecording> {
+        self.frame_recording.take()
+    }
+
+    /// Which cell of the frame is at a position on the terminal (e.g. of an `Event::Mouse`),
+    /// or `None` if the frame isn't drawn there.
+    ///
+    /// The two only differ when using `AppBuilder::inline` or `AppBuilder::fixed_size`.
+    pub fn frame_position(&self, row: usize, col: usize) -> Option<(usize, usize)> {
+        let next = &self.screen.next;
+        let row = row.checked_sub(self.screen.origin)?;
+        match &self.fixed {
+            Some(fixed) => fixed.to_frame((row, col), next.rows(), next.columns()),
+            None if next.area().contains(row, col) => Some((row, col)),
+            None => None,
+        }
+    }
+
+    /// A copy of the most recently drawn frame, e.g. for saving with `Frame::save`.
+    pub fn screenshot(&self) -> Frame {
+        self.screen.next.clone()
+    }
+
+    pub fn backend(&self) -> &B {
+        &self.backend
+    }
+
+    pub fn backend_mut(&mut self) -> &mut B {
+        &mut self.backend
+    }
+
+    /// All events received since the last call to `events`.
+    ///
+    /// If the terminal has been resized since the last call, an `Event::Resize` is yielded
+    /// first, followed by an `Event::Tick` if one is due.
+    pub fn events<'a>(&'a mut self) -> impl Iterator<Item = Result<Event, Error>> + 'a {
+        let job_control = self.check_job_control();
+        let resize = self.check_resize();
+        let tick = self.check_tick().map(Ok);
+        let queued = self.queued.drain(..).map(Ok);
+        let backend = &mut self.backend;
+        job_control
+            .into_iter()
+            .chain(queued)
+            .chain(resize)
+            .chain(tick)
+            .chain(std::iter::from_fn(move || backend.next_event()))
+            .map(|evt| evt.map_err(Error::from))
+    }
+
+    /// Wait until the next frame is due, at the rate set with `AppBuilder::target_fps`, so a
+    /// loop that draws a frame and then calls this runs at that rate.
+    ///
+    /// Returns straight away if there is no target frame rate, or if drawing took longer
+    /// than a frame. Input that arrives in the meantime is kept for the next call to `events`.
+    pub fn tick(&mut self) {
+        let now = self.backend.now();
+        if let Some(pacer) = &mut self.frame_pacer {
+            if now < pacer.next {
+                self.backend.sleep(pacer.next - now);
+            }
+            let now = self.backend.now();
+            pacer.advance(now);
+        }
+    }
+
+    /// Wait for up to `timeout` for the next event.
+    ///
+    /// Returns `Ok(None)` if no event arrived in time.
+    pub fn poll_event(&mut self, timeout: Duration) -> Result<Option<Event>, Error> {
+        self.wait_until(Some(self.backend.now() + timeout))
+    }
+
+    /// Wait until the next event arrives.
+    pub fn wait_event(&mut self) -> Result<Event, Error> {
+        loop {
+            if let Some(evt) = self.wait_until(None)? {
+                return Ok(evt);
+            }
+        }
+    }
+
+    fn wait_until(&mut self, deadline: Option<Instant>) -> Result<Option<Event>, Error> {
+        loop {
+            if let Some(evt) = self.check_job_control() {
+                return Ok(Some(evt?));
+            }
+            if let Some(evt) = self.queued.pop_front() {
+                return Ok(Some(evt));
+            }
+            if let Some(resize) = self.check_resize() {
+                return Ok(Some(resize?));
+            }
+            if let Some(tick) = self.check_tick() {
+                return Ok(Some(tick));
+            }
+            let now = self.backend.now();
+            if deadline.is_some_and(|deadline| now >= deadline) {
+                return Ok(None);
+            }
+            // Wake up periodically so resizes are noticed, and in time for the next tick.
+            let mut wait = Some(RESIZE_POLL_INTERVAL).filter(|_| self.backend.can_resize());
+            if let Some(deadline) = deadline {
+                wait = Some(wait.map_or(deadline - now, |wait| wait.min(deadline - now)));
+            }
+            if let Some(ticker) = &self.ticker {
+                let until_tick = ticker.next.saturating_duration_since(now);
+                wait = Some(wait.map_or(until_tick, |wait| wait.min(until_tick)));
+            }
+            match self.backend.wait_event(wait) {
+                Some(evt) => return Ok(Some(evt?)),
+                // Nothing could wake us up again.
+                None if wait.is_none() => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        "no more events will arrive",
+                    )
+                    .into())
+                }
+                None => (),
+            }
+        }
+    }
+
+    /// Copy text to the system clipboard, using the OSC 52 escape.
+    ///
+    /// This works over SSH, since the terminal does the copying, but not every terminal
+    /// supports it (some ask the user first). Does nothing if disabled with
+    /// `AppBuilder::clipboard`.
+    pub fn set_clipboard(&mut self, text: &str) -> io::Result<()> {
+        if !self.clipboard {
+            return Ok(());
+        }
+        let mut output = self.output();
+        output.write_all(osc::set_clipboard(text).as_bytes())?;
+        output.flush()
+    }
+
+    /// Ask for the contents of the clipboard. They arrive later as an `Event::Clipboard`.
+    ///
+    /// This uses the OSC 52 escape, which many terminals don't allow reading with (they ignore
+    /// it, so no event arrives). With the `local-clipboard` feature, the clipboard is read
+    /// with local tools instead when possible. Does nothing if disabled with
+    /// `AppBuilder::clipboard`.
+    pub fn request_clipboard(&mut self) -> io::Result<()> {
+        if !self.clipboard {
+            return Ok(());
+        }
+        #[cfg(feature = "local-clipboard")]
+        {
+            if let Some(text) = osc::read_local_clipboard() {
+                self.queued.push_back(Event::Clipboard(text));
+                return Ok(());
+            }
+        }
+        let mut output = self.output();
+        output.write_all(osc::request_clipboard().as_bytes())?;
+        output.flush()
+    }
+
+    /// Choose whether the cursor is shown (it is hidden by default).
+    ///
+    /// The cursor is only shown in frames that place it with `Draw::set_cursor`.
+    pub fn show_cursor(&mut self, show: bool) -> io::Result<()> {
+        self.screen.cursor_visible = show;
+        let mut output = Output {
+            writer: &mut self.backend,
+            recorder: self.recorder.as_mut(),
+        };
+        self.screen.update_cursor(&mut output)?;
+        output.flush()
+    }
+
+    /// Change the shape of the cursor. It is put back to the terminal's default when the app
+    /// exits.
+    pub fn set_cursor_shape(&mut self, shape: CursorShape) -> io::Result<()> {
+        self.cursor_shape = shape;
+        if self.screen.plain {
+            return Ok(());
+        }
+        let mut output = self.output();
+        output.write_all(shape.sequence().as_bytes())?;
+        output.flush()
+    }
+
+    /// Ring the terminal's bell, which may beep, flash the screen or mark the window as
+    /// needing attention, depending on the terminal.
+    pub fn bell(&mut self) -> io::Result<()> {
+        if self.screen.plain {
+            return Ok(());
+        }
+        let mut output = self.output();
+        output.write_all(b"\x07")?;
+        output.flush()
+    }
+
+    /// Show a desktop notification, e.g. when a long-running task finishes.
+    ///
+    /// This uses the OSC 777 or OSC 9 escapes, which terminals that don't support them ignore.
+    pub fn notify(&mut self, title: &str, body: &str) -> io::Result<()> {
+        if self.screen.plain {
+            return Ok(());
+        }
+        let mut output = self.output();
+        output.write_all(osc::notify(title, body).as_bytes())?;
+        output.flush()
+    }
+
+    /// Suspend the process, restoring the terminal to normal while it is stopped.
+    ///
+    /// This returns once the process has been continued, and the next frame will be fully
+    /// redrawn. Apps may want to call this when they receive `Ctrl-Z`, since in raw mode the
+    /// terminal doesn't send `SIGTSTP` itself.
+    pub fn suspend(&mut self) -> io::Result<()> {
+        self.leave_terminal()?;
+        self.backend.stop()?;
+        self.enter_terminal()
+    }
+
+    /// Temporarily leave the terminal to run `f`, e.g. to spawn an editor.
+    ///
+    /// While `f` runs, the terminal is out of raw mode, the cursor is visible, and no input is
+    /// read by the app. Afterwards the terminal is set up again, and the next frame will be
+    /// fully redrawn.
+    pub fn suspend_raw<T>(&mut self, f: impl FnOnce() -> T) -> io::Result<T> {
+        self.leave_terminal()?;
+        let out = f();
+        self.enter_terminal()?;
+        Ok(out)
+    }
+
+    /// Handle any pending suspend or continue, returning the event to report.
+    fn check_job_control(&mut self) -> Option<io::Result<Event>> {
+        if self.suspend_pending {
+            self.suspend_pending = false;
+            return Some(self.suspend().map(|()| Event::Resume));
+        }
+        match self.backend.take_job_control()? {
+            JobControl::Suspend => {
+                self.suspend_pending = true;
+                Some(Ok(Event::Suspend))
+            }
+            // We were stopped by something else, so the screen may have been messed with.
+            JobControl::Continue => Some(self.enter_terminal().map(|()| Event::Resume)),
+        }
+    }
+
+    /// Set up the terminal for drawing, and make sure the next frame is fully drawn.
+    fn enter_terminal(&mut self) -> io::Result<()> {
+        self.backend.enable_raw_mode()?;
+        self.screen.invalidate();
+        if self.screen.plain {
+            return Ok(());
+        }
+        let (alternate_screen, mouse) = (self.alternate_screen, self.mouse);
+        let (focus_events, kitty_keyboard) = (self.focus_events, self.kitty_keyboard);
+        let cursor_shape = self.cursor_shape;
+        let inline = self.inline;
+        let hide_cursor = self.capabilities.hide_cursor;
+        let (_, rows) = self.backend.size()?;
+        let mut output = self.output();
+        if alternate_screen {
+            write!(output, "{}", termion::screen::ToAlternateScreen)?;
+        }
+        match inline {
+            // Scroll everything up to make room at the bottom.
+            Some(height) => write!(
+                output,
+                "{}{}",
+                termion::cursor::Goto(1, rows as u16),
+                "\n".repeat(height.min(rows))
+            )?,
+            None => write!(output, "{}", clear::All)?,
+        }
+        if hide_cursor {
+            write!(output, "{}", termion::cursor::Hide)?;
+        }
+        if cursor_shape != CursorShape::Default {
+            write!(output, "{}", cursor_shape.sequence())?;
+        }
+        if mouse {
+            write!(output, "{}", MOUSE_ENABLE)?;
+        }
+        if focus_events {
+            write!(output, "{}", FOCUS_ENABLE)?;
+        }
+        if kitty_keyboard {
+            write!(output, "{}", kitty::KITTY_ENABLE)?;
+        }
+        output.flush()
+    }
+
+    /// Restore the terminal to how it was before we started.
+    fn leave_terminal(&mut self) -> io::Result<()> {
+        use termion::{color, style};
+        if self.screen.plain {
+            return self.backend.disable_raw_mode();
+        }
+        let (alternate_screen, mouse) = (self.alternate_screen, self.mouse);
+        let (focus_events, kitty_keyboard) = (self.focus_events, self.kitty_keyboard);
+        let cursor_shape = self.cursor_shape;
+        let inline = self.inline.is_some();
+        let bottom = self.screen.origin + self.screen.next.rows();
+        let mut output = self.output();
+        write!(
+            output,
+            "{}{}{}",
+            color::Fg(color::Reset),
+            color::Bg(color::Reset),
+            style::Reset,
+        )?;
+        if inline {
+            // Leave the last frame where it is, and carry on below it.
+            write!(output, "{}\r\n", termion::cursor::Goto(1, bottom as u16))?;
+        } else {
+            write!(output, "{}{}", clear::All, termion::cursor::Goto(1, 1))?;
+        }
+        write!(output, "{}", termion::cursor::Show)?;
+        if cursor_shape != CursorShape::Default {
+            write!(output, "{}", CursorShape::Default.sequence())?;
+        }
+        if mouse {
+            write!(output, "{}", MOUSE_DISABLE)?;
+        }
+        if focus_events {
+            write!(output, "{}", FOCUS_DISABLE)?;
+        }
+        if kitty_keyboard {
+            write!(output, "{}", kitty::KITTY_DISABLE)?;
+        }
+        if alternate_screen {
+            write!(output, "{}", termion::screen::ToMainScreen)?;
+        }
+        output.flush()?;
+        self.backend.disable_raw_mode()
+    }
+
+    /// Where to write terminal output.
+    fn output(&mut self) -> Output<'_, B> {
+        Output {
+            writer: &mut self.backend,
+            recorder: self.recorder.as_mut(),
         }
     }
 
-    pub fn events<'a>(&'a mut self) -> &'a mut (impl Iterator<Item = io::Result<Event>> + 'a) {
-        &mut self.input
+    /// An `Event::Tick` if one is due.
+    fn check_tick(&mut self) -> Option<Event> {
+        let now = self.backend.now();
+        let ticker = self.ticker.as_mut()?;
+        if now < ticker.next {
+            return None;
+        }
+        ticker.advance(now);
+        Some(Event::Tick)
+    }
+
+    /// An `Event::Resize` if the terminal has changed size since we last checked.
+    fn check_resize(&mut self) -> Option<io::Result<Event>> {
+        match self.backend.size() {
+            Ok(size) => {
+                if size != self.size {
+                    self.size = size;
+                    Some(Ok(Event::Resize(size.0, size.1)))
+                } else {
+                    None
+                }
+            }
+            Err(e) => Some(Err(e)),
+        }
     }
 }
 
-impl Drop for App {
+impl<B: Backend> Drop for App<B> {
     fn drop(&mut self) {
-        use termion::color;
         // The best we can do here is to ignore errors.
-        let _ = write!(
-            self.output,
-            "{}{}{}{}{}",
-            color::Fg(color::Reset),
-            color::Bg(color::Reset),
-            clear::All,
-            cursor::Goto(1, 1),
-            cursor::Show
-        );
+        let _ = self.leave_terminal();
     }
 }
 
 #[derive(Debug, Clone)]
-pub struct AppBuilder {}
+pub struct AppBuilder {
+    alternate_screen: bool,
+    inline: Option<usize>,
+    fixed_size: Option<(usize, usize)>,
+    letterbox: Char,
+    capabilities: Option<Capabilities>,
+    color_support: Option<ColorSupport>,
+    monochrome: bool,
+    mouse: bool,
+    focus_events: bool,
+    kitty_keyboard: bool,
+    clipboard: bool,
+    tick_rate: Option<Duration>,
+    target_fps: Option<u32>,
+    debug_overlay: bool,
+    record_to: Option<PathBuf>,
+    record_frames: bool,
+    control_policy: ControlPolicy,
+    clip_writes: bool,
+    synchronized_output: Option<bool>,
+    scroll_detection: bool,
+    redraw_threshold: u8,
+    min_size: Option<(usize, usize)>,
+    plain: bool,
+    plain_fallback: bool,
+    dev_tty: bool,
+    require_cursor_addressing: bool,
+}
 
 impl AppBuilder {
-    pub fn build(self) -> io::Result<App> {
-        let mut output = io::stdout().into_raw_mode()?;
-        write!(output, "{}{}", clear::All, cursor::Hide)?;
-        let input = async_stdin().events();
-        let (cols, rows) = terminal_size()?;
-        let (cols, rows) = (cols as usize, rows as usize);
-        output.flush()?;
-        Ok(App {
-            input,
-            output,
-            screen: screen::Screen::new(cols, rows),
-        })
+    /// Whether to draw on the terminal's alternate screen (default `true`).
+    ///
+    /// When enabled, the original contents of the terminal are restored when the `App` is
+    /// dropped.
+    pub fn alternate_screen(mut self, alternate_screen: bool) -> Self {
+        self.alternate_screen = alternate_screen;
+        self
+    }
+
+    /// Draw in the bottom `height` rows of the terminal, rather than the whole screen, like
+    /// `fzf` or a progress display. Frames are `height` rows tall (or the height of the
+    /// terminal if less).
+    ///
+    /// Room is made by scrolling the terminal up, and the alternate screen isn't used. When the
+    /// app exits, the last frame is left in place with the terminal's cursor below it. Use
+    /// `App::frame_position` to find where mouse events are in the frame.
+    pub fn inline(mut self, height: usize) -> Self {
+        self.inline = Some(height);
+        self
+    }
+
+    /// Always draw frames of the given size, in the middle of the terminal, so positions in the
+    /// frame don't change when the terminal is resized.
+    ///
+    /// The rest of the terminal is filled with the `letterbox` cell. If the terminal is too
+    /// small, the middle of the frame is shown. Use `App::frame_position` to find where mouse
+    /// events are in the frame.
+    pub fn fixed_size(mut self, rows: usize, cols: usize) -> Self {
+        self.fixed_size = Some((rows, cols));
+        self
+    }
+
+    /// The cell to fill the terminal with around a frame of fixed size (default a blank cell).
+    pub fn letterbox(mut self, letterbox: Char) -> Self {
+        self.letterbox = letterbox;
+        self
+    }
+
+    /// Override the capabilities of the terminal, which are otherwise looked up in the terminfo
+    /// database (see `Capabilities::detect`).
+    pub fn capabilities(mut self, capabilities: Capabilities) -> Self {
+        self.capabilities = Some(capabilities);
+        self
+    }
+
+    /// Override the detected color support of the terminal.
+    ///
+    /// Colors the terminal can't display are replaced with the closest color it can.
+    pub fn color_support(mut self, color_support: ColorSupport) -> Self {
+        self.color_support = Some(color_support);
+        self
+    }
+
+    /// Don't output any colors (default `false`).
+    ///
+    /// This is also enabled automatically if the `NO_COLOR` environment variable is set.
+    pub fn monochrome(mut self, monochrome: bool) -> Self {
+        self.monochrome = monochrome;
+        self
+    }
+
+    /// Whether to capture mouse events (default `false`).
+    ///
+    /// When enabled, mouse clicks, drags and wheel scrolls are received as `Event::Mouse`, and
+    /// the terminal will no longer handle them itself (e.g. for selecting text).
+    pub fn mouse(mut self, mouse: bool) -> Self {
+        self.mouse = mouse;
+        self
+    }
+
+    /// Whether to report the terminal window gaining and losing focus (default `false`).
+    ///
+    /// When enabled, `Event::FocusGained` and `Event::FocusLost` are received, e.g. to pause
+    /// animations while the window is in the background. Not all terminals support this.
+    pub fn focus_events(mut self, focus_events: bool) -> Self {
+        self.focus_events = focus_events;
+        self
+    }
+
+    /// Whether to use the kitty keyboard protocol, if the terminal supports it (default
+    /// `false`).
+    ///
+    /// When enabled, keys are received as `Event::KeyEvent`, which includes key releases and
+    /// repeats, and tells apart keys that normally look the same (like Ctrl-I and Tab, or
+    /// Ctrl-Shift-A and Ctrl-A). Terminals that don't support the protocol ignore it, and keys
+    /// arrive as `Event::Key` as usual.
+    pub fn kitty_keyboard(mut self, kitty_keyboard: bool) -> Self {
+        self.kitty_keyboard = kitty_keyboard;
+        self
+    }
+
+    /// Whether `App::set_clipboard` and `App::request_clipboard` use the clipboard (default
+    /// `true`).
+    ///
+    /// Turn this off for terminals that misbehave when sent the OSC 52 escape.
+    pub fn clipboard(mut self, clipboard: bool) -> Self {
+        self.clipboard = clipboard;
+        self
+    }
+
+    /// Send an `Event::Tick` at the given rate, along with the input events.
+    ///
+    /// Ticks are useful for driving animation: draw a new frame on every tick.
+    pub fn tick_rate(mut self, tick_rate: Duration) -> Self {
+        self.tick_rate = Some(tick_rate);
+        self
+    }
+
+    /// Limit drawing to the given number of frames per second, by calling `App::tick` after
+    /// drawing each frame.
+    ///
+    /// This suits apps that draw in a loop, e.g. games, rather than waiting for events. For
+    /// apps that only draw when something happens, use `tick_rate` instead.
+    pub fn target_fps(mut self, fps: u32) -> Self {
+        self.target_fps = Some(fps);
+        self
+    }
+
+    /// Show the frame rate and render stats in the top right corner (default `false`). This can
+    /// be changed later with `App::set_debug_overlay`.
+    pub fn debug_overlay(mut self, debug_overlay: bool) -> Self {
+        self.debug_overlay = debug_overlay;
+        self
+    }
+
+    /// Record everything drawn to the terminal to an asciinema (v2) cast file at the given
+    /// path, so the session can be replayed with `asciinema play`.
+    pub fn record_to(mut self, path: impl Into<PathBuf>) -> Self {
+        self.record_to = Some(path.into());
+        self
+    }
+
+    /// Keep a copy of every frame drawn, along with when it was drawn, so the session can be
+    /// saved as an animated GIF (default `false`). See `App::take_frame_recording`.
+    pub fn record_frames(mut self, record_frames: bool) -> Self {
+        self.record_frames = record_frames;
+        self
+    }
+
+    /// How control characters written to the frame are handled (default
+    /// `ControlPolicy::Replace`).
+    ///
+    /// This can also be changed while drawing with `Frame::set_control_policy`.
+    pub fn control_policy(mut self, control_policy: ControlPolicy) -> Self {
+        self.control_policy = control_policy;
+        self
+    }
+
+    /// Whether to ignore writes outside the frame, rather than panicking (default `false`).
+    ///
+    /// This can also be changed while drawing with `Frame::set_clip_writes`.
+    pub fn clip_writes(mut self, clip_writes: bool) -> Self {
+        self.clip_writes = clip_writes;
+        self
+    }
+
+    /// Whether to draw each frame as a synchronized update (DEC mode 2026), so the terminal
+    /// never shows a partly drawn frame.
+    ///
+    /// By default this is enabled if the terminal looks like one that supports it.
+    pub fn synchronized_output(mut self, synchronized_output: bool) -> Self {
+        self.synchronized_output = Some(synchronized_output);
+        self
+    }
+
+    /// Whether to look for content that has moved up or down since the last frame, and scroll
+    /// the terminal instead of redrawing it (default `false`).
+    ///
+    /// This can save a lot of output for things like logs and pagers, at the cost of comparing
+    /// rows when rendering.
+    pub fn scroll_detection(mut self, scroll_detection: bool) -> Self {
+        self.scroll_detection = scroll_detection;
+        self
+    }
+
+    /// Redraw the whole screen, rather than only the cells that have changed, when at least
+    /// this percentage of them have changed (default 50).
+    ///
+    /// Redrawing avoids moving the cursor to each change, which is slower when most of the
+    /// screen is changing. Values over 100 turn this off.
+    pub fn redraw_threshold(mut self, percent: u8) -> Self {
+        self.redraw_threshold = percent;
+        self
+    }
+
+    /// Refuse to build the app, with `Error::TerminalTooSmall`, if the terminal has fewer than
+    /// the given number of rows and columns.
+    pub fn min_size(mut self, rows: usize, cols: usize) -> Self {
+        self.min_size = Some((rows, cols));
+        self
+    }
+
+    /// Print each frame that differs from the last as plain text, followed by a blank line,
+    /// rather than drawing it on the terminal (default `false`).
+    ///
+    /// No escapes are written at all, so this suits output to a file or a log. The cursor,
+    /// clipboard, bell and notifications do nothing.
+    pub fn plain(mut self, plain: bool) -> Self {
+        self.plain = plain;
+        self
+    }
+
+    /// If stdin or stdout isn't a terminal, have `build` print frames as plain text (see
+    /// `plain`) instead of failing with `Error::NotATty` (default `false`).
+    ///
+    /// No input is read in this case. Check `App::is_plain` to tell whether it happened, e.g.
+    /// to exit after drawing the first frame.
+    pub fn plain_fallback(mut self, plain_fallback: bool) -> Self {
+        self.plain_fallback = plain_fallback;
+        self
+    }
+
+    /// Draw to and read input from the controlling terminal, `/dev/tty`, rather than stdout
+    /// and stdin (default `false`).
+    ///
+    /// This lets the app run while stdout is piped to another program, e.g. for a picker that
+    /// prints what was picked once it exits, as fzf does.
+    pub fn dev_tty(mut self, dev_tty: bool) -> Self {
+        self.dev_tty = dev_tty;
+        self
+    }
+
+    /// Have `build` fail with `Error::UnsupportedTerminal` if the terminal doesn't say it can
+    /// move the cursor, e.g. if `TERM` isn't set or is `dumb` (default `false`).
+    ///
+    /// Otherwise such a terminal is assumed to understand the usual xterm escapes (see
+    /// `Capabilities::xterm`), keeping the detected colors, since `TERM` is often just missing
+    /// (e.g. under some CI runners or `sudo`) rather than naming a terminal that really can't.
+    pub fn require_cursor_addressing(mut self, require_cursor_addressing: bool) -> Self {
+        self.require_cursor_addressing = require_cursor_addressing;
+        self
+    }
+
+    /// Build an app that draws to the terminal on standard output, and reads events from
+    /// standard input, or uses `/dev/tty` for both if `dev_tty` is set.
+    ///
+    /// Fails with `Error::NotATty` if either isn't a terminal (unless `plain_fallback` is
+    /// set), and with `Error::UnsupportedTerminal` if the terminal can't move the cursor and
+    /// `require_cursor_addressing` is set.
+    pub fn build(mut self) -> Result<App, Error> {
+        let is_tty = |fd| unsafe { libc::isatty(fd) } == 1;
+        let has_tty = if self.dev_tty {
+            // This fails if there is no controlling terminal.
+            OpenOptions::new()
+                .read(true)
+                .write(true)
+                .open("/dev/tty")
+                .is_ok()
+        } else {
+            is_tty(libc::STDIN_FILENO) && is_tty(libc::STDOUT_FILENO)
+        };
+        if !has_tty {
+            if !self.plain_fallback {
+                return Err(Error::NotATty);
+            }
+            self.plain = true;
+        }
+        if self.plain {
+            return self.build_with_backend(TermionBackend::plain());
+        }
+        let capabilities = *self.capabilities.get_or_insert_with(Capabilities::detect);
+        if !capabilities.cursor_addressing {
+            if self.require_cursor_addressing {
+                let term = std::env::var("TERM").unwrap_or_default();
+                return Err(Error::UnsupportedTerminal(term));
+            }
+            self.capabilities = Some(Capabilities::xterm(capabilities.colors));
+        }
+        let backend = if self.dev_tty {
+            TermionBackend::dev_tty()?
+        } else {
+            TermionBackend::new()?
+        };
+        self.build_with_backend(backend)
+    }
+
+    /// Build an app over an in-memory `TestBackend` of the given size, for testing.
+    ///
+    /// Unless overridden, the terminal is assumed to understand xterm escapes and 24-bit
+    /// color, without synchronized output, so the output doesn't depend on the environment
+    /// (e.g. `TERM`, `COLORTERM` or `NO_COLOR`).
+    pub fn test_backend(mut self, cols: usize, rows: usize) -> App<TestBackend> {
+        self.capabilities
+            .get_or_insert_with(|| Capabilities::xterm(ColorSupport::TrueColor));
+        self.synchronized_output.get_or_insert(false);
+        self.build_with_backend(TestBackend::new(cols, rows))
+            .expect("the test backend cannot fail")
+    }
+
+    /// Build an app that draws to and reads events from the given backend.
+    ///
+    /// Fails with `Error::TerminalTooSmall` if the terminal is smaller than the size set with
+    /// `min_size`.
+    pub fn build_with_backend<B: Backend>(self, backend: B) -> Result<App<B>, Error> {
+        let (cols, rows) = backend.size()?;
+        if let Some((min_rows, min_cols)) = self.min_size {
+            if rows < min_rows || cols < min_cols {
+                return Err(Error::TerminalTooSmall {
+                    cols,
+                    rows,
+                    min_cols,
+                    min_rows,
+                });
+            }
+        }
+        let now = backend.now();
+        let mut capabilities = self.capabilities.unwrap_or_else(Capabilities::detect);
+        if self.monochrome {
+            capabilities.colors = ColorSupport::NoColor;
+        } else if let Some(color_support) = self.color_support {
+            capabilities.colors = color_support;
+        }
+        let mut screen = screen::Screen::new(rows, cols, capabilities.colors);
+        screen.attributes = capabilities.attributes;
+        #[cfg(feature = "images")]
+        {
+            screen.images.sixel = capabilities.sixel;
+            screen.images.kitty = capabilities.kitty_graphics;
+        }
+        screen.set_control_policy(self.control_policy);
+        screen.set_clip_writes(self.clip_writes);
+        screen.synchronized_output = self.synchronized_output.unwrap_or_else(sync::detect);
+        screen.scroll_detection = self.scroll_detection;
+        screen.redraw_threshold = self.redraw_threshold;
+        screen.inline = self.inline.is_some();
+        screen.plain = self.plain;
+        let mut app = App {
+            backend,
+            screen,
+            alternate_screen: self.alternate_screen
+                && self.inline.is_none()
+                && capabilities.alternate_screen,
+            capabilities,
+            inline: self.inline,
+            fixed: self.fixed_size.map(|(rows, cols)| {
+                let mut frame = Frame::new(rows, cols);
+                frame.set_control_policy(self.control_policy);
+                frame.set_clip_writes(self.clip_writes);
+                FixedSize {
+                    frame,
+                    letterbox: self.letterbox.clone(),
+                }
+            }),
+            mouse: self.mouse,
+            focus_events: self.focus_events,
+            kitty_keyboard: self.kitty_keyboard,
+            clipboard: self.clipboard && !self.plain,
+            cursor_shape: CursorShape::Default,
+            debug_overlay: if self.debug_overlay {
+                Some(DebugOverlay::default())
+            } else {
+                None
+            },
+            queued: VecDeque::new(),
+            size: (cols, rows),
+            ticker: self.tick_rate.map(|rate| Ticker {
+                rate,
+                next: now + rate,
+            }),
+            frame_pacer: self.target_fps.filter(|&fps| fps > 0).map(|fps| {
+                let rate = Duration::from_secs(1) / fps;
+                Ticker {
+                    rate,
+                    next: now + rate,
+                }
+            }),
+            last_draw: None,
+            suspend_pending: false,
+            recorder: match &self.record_to {
+                Some(path) => Some(Recorder::create(path, cols, rows)?),
+                None => None,
+            },
+            frame_recording: if self.record_frames {
+                Some(FrameRecording::new())
+            } else {
+                None
+            },
+        };
+        app.enter_terminal()?;
+        Ok(app)
     }
 }
 
 impl Default for AppBuilder {
     fn default() -> AppBuilder {
-        AppBuilder {}
+        AppBuilder {
+            alternate_screen: true,
+            inline: None,
+            fixed_size: None,
+            letterbox: Char::default(),
+            capabilities: None,
+            color_support: None,
+            monochrome: false,
+            mouse: false,
+            focus_events: false,
+            kitty_keyboard: false,
+            clipboard: true,
+            tick_rate: None,
+            target_fps: None,
+            debug_overlay: false,
+            record_to: None,
+            record_frames: false,
+            control_policy: ControlPolicy::default(),
+            clip_writes: false,
+            synchronized_output: None,
+            scroll_detection: false,
+            redraw_threshold: screen::DEFAULT_REDRAW_THRESHOLD,
+            min_size: None,
+            plain: false,
+            plain_fallback: false,
+            dev_tty: false,
+            require_cursor_addressing: false,
+        }
     }
 }
 
-pub struct Draw<'a> {
+pub struct Draw<'a, B: Backend = TermionBackend> {
     screen: &'a mut screen::Screen,
-    output: &'a mut RawTerminal<io::Stdout>,
+    output: Output<'a, B>,
+    fixed: Option<&'a mut FixedSize>,
+    debug_overlay: Option<&'a mut DebugOverlay>,
+    frame_recording: Option<&'a mut FrameRecording>,
+    delta_time: Duration,
+    /// An error reading the size of the terminal, to return from `commit`.
+    error: Option<io::Error>,
+    committed: bool,
+}
+
+impl<'a, B: Backend> Draw<'a, B> {
+    /// Render the frame to the terminal, returning any I/O error, including one from reading
+    /// the size of the terminal in `App::draw`.
+    ///
+    /// If the draw is dropped without calling `commit`, the frame is still rendered but errors
+    /// are ignored.
+    pub fn commit(mut self) -> Result<(), Error> {
+        self.committed = true;
+        self.render()?;
+        match self.error.take() {
+            Some(e) => Err(Error::Io(e)),
+            None => Ok(()),
+        }
+    }
+
+    /// The time since the previous frame was started, or zero for the first frame.
+    ///
+    /// Use this to move animations on by the time that has passed, so they run at the same
+    /// speed whatever the frame rate.
+    pub fn delta_time(&self) -> Duration {
+        self.delta_time
+    }
+
+    /// Show the cursor at the given position once the frame is drawn, if the cursor is
+    /// visible (see `App::show_cursor`). The cursor is hidden in frames that don't set it.
+    pub fn set_cursor(&mut self, row: usize, col: usize) {
+        self.screen.cursor = Some((row, col));
+    }
+
+    fn render(&mut self) -> io::Result<()> {
+        if let Some(fixed) = &self.fixed {
+            let next = &mut self.screen.next;
+            fixed.draw(next);
+            self.screen.cursor = self
+                .screen
+                .cursor
+                .and_then(|cursor| fixed.to_terminal(cursor, next.rows(), next.columns()));
+        }
+        if let Some(overlay) = &mut self.debug_overlay {
+            overlay.draw(&mut self.screen.next, self.screen.stats);
+        }
+        if let Some(recording) = &mut self.frame_recording {
+            recording.record(&self.screen.next);
+        }
+        self.screen.render(&mut self.output)?;
+        self.output.flush()
+    }
 }
 
-impl<'a> Deref for Draw<'a> {
+impl<'a, B: Backend> Deref for Draw<'a, B> {
     type Target = Frame;
     fn deref(&self) -> &Frame {
-        &self.screen.next
+        match &self.fixed {
+            Some(fixed) => &fixed.frame,
+            None => &self.screen.next,
+        }
     }
 }
 
-impl<'a> DerefMut for Draw<'a> {
+impl<'a, B: Backend> DerefMut for Draw<'a, B> {
     fn deref_mut(&mut self) -> &mut Frame {
-        &mut self.screen.next
+        match &mut self.fixed {
+            Some(fixed) => &mut fixed.frame,
+            None => &mut self.screen.next,
+        }
     }
 }
 
-impl<'a> Drop for Draw<'a> {
+impl<'a, B: Backend> Drop for Draw<'a, B> {
     fn drop(&mut self) {
-        self.screen.render(&mut self.output.lock()).unwrap();
-        self.output.flush().unwrap();
+        if !self.committed {
+            // The best we can do here is to ignore errors.
+            let _ = self.render();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backend_ignores_the_environment() {
+        let app = App::builder().test_backend(10, 5);
+        assert_eq!(app.capabilities().colors, ColorSupport::TrueColor);
+        assert!(!app.screen.synchronized_output);
+    }
+
+    #[test]
+    fn polling_an_empty_test_backend_times_out() {
+        let mut app = App::builder().test_backend(10, 5);
+        let start = Instant::now();
+        assert!(app.poll_event(Duration::from_millis(20)).unwrap().is_none());
+        assert!(start.elapsed() >= Duration::from_millis(20));
+    }
+
+    #[test]
+    fn waiting_on_an_empty_test_backend_fails() {
+        let mut app = App::builder().test_backend(10, 5);
+        app.backend_mut().push_event(Event::Key(Key::Char('q')));
+        assert_eq!(app.wait_event().unwrap(), Event::Key(Key::Char('q')));
+        match app.wait_event() {
+            Err(Error::Io(e)) => assert_eq!(e.kind(), io::ErrorKind::UnexpectedEof),
+            other => panic!("expected an error, got {:?}", other),
+        }
+        assert!(app.poll_event(Duration::ZERO).unwrap().is_none());
+    }
+
+    #[test]
+    fn waiting_after_the_simulated_script_runs_out_fails() {
+        let mut sim = SimBackend::new(10, 5);
+        sim.push_event_after(Duration::from_millis(500), Event::Key(Key::Char('q')));
+        let mut app = App::builder().build_with_backend(sim).unwrap();
+        assert_eq!(app.wait_event().unwrap(), Event::Key(Key::Char('q')));
+        match app.wait_event() {
+            Err(Error::Io(e)) => assert_eq!(e.kind(), io::ErrorKind::UnexpectedEof),
+            other => panic!("expected an error, got {:?}", other),
+        }
+        assert!(app.poll_event(Duration::from_secs(1)).unwrap().is_none());
+        assert_eq!(app.backend().elapsed(), Duration::from_millis(1500));
     }
 }
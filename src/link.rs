@@ -0,0 +1,104 @@
+use std::collections::hash_map::DefaultHasher;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::io::{self, Write};
+use std::sync::Arc;
+
+/// A hyperlink target, drawn with OSC 8 so terminals that support it make the text clickable.
+///
+/// Set it on a `Style` (or a `Char`) to link the cells drawn with it. Terminals that don't
+/// support hyperlinks show the text as normal.
+///
+/// The URL is shared, so links are cheap to clone. Links are compared and hashed by their URL.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct Link {
+    url: Arc<str>,
+}
+
+impl Link {
+    /// A link to the given URL, e.g. `https://example.com` or `file:///etc/hosts`.
+    ///
+    /// Characters other than printable ASCII aren't allowed in the escape, so they are
+    /// percent-encoded.
+    pub fn new(url: &str) -> Link {
+        Link {
+            url: encode(url).into(),
+        }
+    }
+
+    /// The URL the link points to.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// An id for the link in OSC 8 escapes, the same for every link to the same URL.
+    fn id(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.url.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Write the OSC 8 escapes needed to go from the `prev` link to `next`.
+    pub(crate) fn write_change(
+        prev: Option<&Link>,
+        next: Option<&Link>,
+        writer: &mut impl Write,
+    ) -> io::Result<()> {
+        match next {
+            // The id lets the terminal treat a link split over several rows as one.
+            Some(link) => write!(writer, "\x1b]8;id={:x};{}\x1b\\", link.id(), link.url),
+            None if prev.is_some() => write!(writer, "\x1b]8;;\x1b\\"),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Percent-encode any bytes that aren't printable ASCII.
+fn encode(url: &str) -> String {
+    let mut out = String::with_capacity(url.len());
+    for &byte in url.as_bytes() {
+        if (0x20..0x7f).contains(&byte) {
+            out.push(char::from(byte));
+        } else {
+            out.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    out
+}
+
+impl fmt::Debug for Link {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&*self.url, f)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Link {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.url)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Link {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Link, D::Error> {
+        let s = <std::borrow::Cow<'de, str>>::deserialize(deserializer)?;
+        Ok(Link::new(&s))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn links_compare_by_url() {
+        let (a, b) = (
+            Link::new("https://b.example"),
+            Link::new("https://a.example"),
+        );
+        assert_eq!(a, Link::new("https://b.example"));
+        assert!(b < a);
+        assert_eq!(a.id(), Link::new("https://b.example").id());
+    }
+}
@@ -0,0 +1,161 @@
+use crate::rect::Rect;
+use crate::screen::{Frame, Style};
+use crate::span::Line;
+use crate::table::apply_style;
+use termion::event::Key;
+use unicode_width::UnicodeWidthStr;
+
+/// The selection and scroll position of a `List`. This is kept between frames.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+pub struct ListState {
+    selected: Option<usize>,
+    offset: usize,
+}
+
+impl ListState {
+    pub fn new() -> ListState {
+        ListState::default()
+    }
+
+    /// The index of the selected item.
+    pub fn selected(&self) -> Option<usize> {
+        self.selected
+    }
+
+    pub fn select(&mut self, index: Option<usize>) {
+        self.selected = index;
+    }
+
+    /// The index of the first visible item.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Move the selection down one item, stopping at the last one.
+    pub fn select_next(&mut self, len: usize) {
+        self.selected = match self.selected {
+            _ if len == 0 => None,
+            Some(index) => Some((index + 1).min(len - 1)),
+            None => Some(0),
+        };
+    }
+
+    /// Move the selection up one item, stopping at the first one.
+    pub fn select_previous(&mut self, len: usize) {
+        self.selected = match self.selected {
+            _ if len == 0 => None,
+            Some(index) => Some(index.saturating_sub(1).min(len - 1)),
+            None => Some(len - 1),
+        };
+    }
+
+    pub fn select_first(&mut self, len: usize) {
+        self.selected = if len == 0 { None } else { Some(0) };
+    }
+
+    pub fn select_last(&mut self, len: usize) {
+        self.selected = len.checked_sub(1);
+    }
+
+    /// Move the selection in response to a key press, for a list with `len` items.
+    ///
+    /// Handles the arrow keys, Home, End, Page Up and Page Down (which move by `page` items).
+    /// Returns `false` if the key wasn't used.
+    pub fn handle_key(&mut self, key: Key, len: usize, page: usize) -> bool {
+        match key {
+            Key::Down => self.select_next(len),
+            Key::Up => self.select_previous(len),
+            Key::Home => self.select_first(len),
+            Key::End => self.select_last(len),
+            Key::PageDown => {
+                for _ in 0..page.max(1) {
+                    self.select_next(len);
+                }
+            }
+            Key::PageUp => {
+                for _ in 0..page.max(1) {
+                    self.select_previous(len);
+                }
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    /// Scroll so that the selected item is visible in a list `height` rows tall.
+    fn scroll_to_selected(&mut self, len: usize, height: usize) {
+        self.offset = self.offset.min(len.saturating_sub(height));
+        if let Some(selected) = self.selected {
+            if selected < self.offset {
+                self.offset = selected;
+            } else if height > 0 && selected >= self.offset + height {
+                self.offset = selected + 1 - height;
+            }
+        }
+    }
+}
+
+/// A list of items, one per row, where one item can be selected.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Hash)]
+pub struct List<'a> {
+    pub items: Vec<Line<'a>>,
+    /// Applied to the whole list, underneath the styles of the items.
+    pub style: Style,
+    /// Applied to the selected item, underneath the styles of its spans.
+    pub highlight_style: Style,
+    /// Drawn before the selected item. Other items are indented by the same amount.
+    pub highlight_symbol: Option<&'a str>,
+}
+
+impl<'a> List<'a> {
+    pub fn new(items: Vec<Line<'a>>) -> List<'a> {
+        List {
+            items,
+            ..List::default()
+        }
+    }
+
+    /// Draw the visible items into a region of a frame, scrolling if needed so the selected
+    /// item can be seen.
+    pub fn render_into(&self, frame: &mut Frame, region: Rect, state: &mut ListState) {
+        let region = region.intersection(frame.area());
+        if let Some(selected) = state.selected {
+            if selected >= self.items.len() {
+                state.selected = self.items.len().checked_sub(1);
+            }
+        }
+        state.scroll_to_selected(self.items.len(), region.height);
+        frame.fill_rect(
+            region.row,
+            region.col,
+            region.height,
+            region.width,
+            self.style.char(' '),
+        );
+        let symbol = self.highlight_symbol.unwrap_or("");
+        let indent = region.col + symbol.width();
+        let items = self.items.iter().enumerate().skip(state.offset);
+        for (row, (index, item)) in (region.row..region.bottom()).zip(items) {
+            let selected = state.selected == Some(index);
+            if selected {
+                frame.fill_rect(
+                    row,
+                    region.col,
+                    1,
+                    region.width,
+                    self.highlight_style.char(' '),
+                );
+                frame.write_line(row, region.col, region.right(), &symbol.into());
+            }
+            if indent < region.right() {
+                frame.write_line(row, indent, region.right(), item);
+            }
+            let style = if selected {
+                &self.highlight_style
+            } else {
+                &self.style
+            };
+            apply_style(frame, row, region, style);
+        }
+    }
+}
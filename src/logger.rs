@@ -0,0 +1,277 @@
+use crate::rect::Rect;
+use crate::screen::{Attributes, Color, Frame, Style};
+use crate::span::{Line, Span};
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+/// How important a log message is, most important first, as with the `log` crate's levels.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum LogLevel {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl LogLevel {
+    /// The color a `LogView` shows the level in.
+    pub fn color(self) -> Color {
+        match self {
+            LogLevel::Error => Color::LightRed,
+            LogLevel::Warn => Color::LightYellow,
+            LogLevel::Info => Color::LightGreen,
+            LogLevel::Debug => Color::LightBlue,
+            LogLevel::Trace => Color::LightBlack,
+        }
+    }
+}
+
+impl fmt::Display for LogLevel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad(match self {
+            LogLevel::Error => "ERROR",
+            LogLevel::Warn => "WARN",
+            LogLevel::Info => "INFO",
+            LogLevel::Debug => "DEBUG",
+            LogLevel::Trace => "TRACE",
+        })
+    }
+}
+
+/// A message kept by a `TermbufferLogger`.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct LogRecord {
+    pub level: LogLevel,
+    /// Where the message came from, usually a module path.
+    pub target: String,
+    pub message: String,
+}
+
+/// Keeps the most recent log messages in memory, so they can be shown in a `LogView` rather
+/// than printed, which would mess up the screen in raw mode.
+///
+/// Clones share the same messages, so one can be handed to the logging framework while
+/// another is used for drawing.
+///
+/// This crate doesn't depend on the `log` crate, so there is no `log::Log` implementation here.
+/// To use it as the logger for `log`, wrap it in a type of your own that forwards each record:
+///
+/// ```ignore
+/// struct Logger(TermbufferLogger);
+///
+/// impl log::Log for Logger {
+///     fn enabled(&self, metadata: &log::Metadata) -> bool {
+///         self.0.enabled(level(metadata.level()))
+///     }
+///
+///     fn log(&self, record: &log::Record) {
+///         self.0.log(level(record.level()), record.target(), record.args());
+///     }
+///
+///     fn flush(&self) {}
+/// }
+///
+/// fn level(level: log::Level) -> LogLevel {
+///     match level {
+///         log::Level::Error => LogLevel::Error,
+///         log::Level::Warn => LogLevel::Warn,
+///         log::Level::Info => LogLevel::Info,
+///         log::Level::Debug => LogLevel::Debug,
+///         log::Level::Trace => LogLevel::Trace,
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct TermbufferLogger {
+    buffer: Arc<Mutex<Buffer>>,
+}
+
+#[derive(Debug)]
+struct Buffer {
+    records: VecDeque<LogRecord>,
+    capacity: usize,
+    max_level: LogLevel,
+}
+
+impl TermbufferLogger {
+    /// Create a logger keeping up to `capacity` messages. Once it is full, the oldest messages
+    /// are dropped to make room.
+    pub fn new(capacity: usize) -> TermbufferLogger {
+        TermbufferLogger {
+            buffer: Arc::new(Mutex::new(Buffer {
+                records: VecDeque::with_capacity(capacity),
+                capacity,
+                max_level: LogLevel::Trace,
+            })),
+        }
+    }
+
+    /// Only keep messages at this level or more important (default `LogLevel::Trace`, to
+    /// keep everything).
+    pub fn set_max_level(&self, level: LogLevel) {
+        self.lock().max_level = level;
+    }
+
+    pub fn max_level(&self) -> LogLevel {
+        self.lock().max_level
+    }
+
+    /// Whether messages at the given level are kept.
+    pub fn enabled(&self, level: LogLevel) -> bool {
+        level <= self.max_level()
+    }
+
+    /// Add a message, dropping the oldest if the logger is full.
+    pub fn log(&self, level: LogLevel, target: &str, message: impl fmt::Display) {
+        let mut buffer = self.lock();
+        if level > buffer.max_level || buffer.capacity == 0 {
+            return;
+        }
+        if buffer.records.len() == buffer.capacity {
+            buffer.records.pop_front();
+        }
+        buffer.records.push_back(LogRecord {
+            level,
+            target: target.into(),
+            message: message.to_string(),
+        });
+    }
+
+    /// A copy of the messages kept, oldest first.
+    pub fn records(&self) -> Vec<LogRecord> {
+        self.lock().records.iter().cloned().collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.lock().records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lock().records.is_empty()
+    }
+
+    pub fn clear(&self) {
+        self.lock().records.clear();
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Buffer> {
+        // A panic while logging can't leave the buffer in a bad state, so carry on.
+        self.buffer.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Draws the messages kept by a `TermbufferLogger`, newest at the bottom, with the level of
+/// each one in color.
+///
+/// Messages with several lines take up a row for each.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct LogView {
+    /// Only show messages at this level or more important.
+    pub max_level: LogLevel,
+    /// Whether to show where each message came from.
+    pub show_target: bool,
+    /// The number of rows scrolled back from the newest message.
+    pub scroll: usize,
+}
+
+impl Default for LogView {
+    fn default() -> LogView {
+        LogView::new()
+    }
+}
+
+impl LogView {
+    pub fn new() -> LogView {
+        LogView {
+            max_level: LogLevel::Trace,
+            show_target: true,
+            scroll: 0,
+        }
+    }
+
+    /// Draw as many of the messages as fit in a region, ending `scroll` rows back from the
+    /// newest.
+    pub fn render_into(&self, logger: &TermbufferLogger, frame: &mut Frame, region: Rect) {
+        let region = region.intersection(frame.area());
+        if region.is_empty() {
+            return;
+        }
+        let target_style = Style::new(Color::Default, Color::Default, Attributes::DIM);
+        let mut lines = Vec::new();
+        for record in logger.records() {
+            if record.level > self.max_level {
+                continue;
+            }
+            let level = Style::new(record.level.color(), Color::Default, Attributes::BOLD);
+            let mut prefix = vec![Span::new(format!("{:<5} ", record.level), level)];
+            if self.show_target && !record.target.is_empty() {
+                prefix.push(Span::new(
+                    format!("{} ", record.target),
+                    target_style.clone(),
+                ));
+            }
+            let indent = Line::new(prefix.clone()).width();
+            let mut texts: Vec<&str> = record.message.lines().collect();
+            if texts.is_empty() {
+                texts.push("");
+            }
+            for (i, text) in texts.into_iter().enumerate() {
+                let mut spans = if i == 0 {
+                    prefix.clone()
+                } else {
+                    vec![Span::raw(" ".repeat(indent))]
+                };
+                spans.push(Span::raw(text.to_string()));
+                lines.push(Line::new(spans));
+            }
+        }
+        let end = lines.len().saturating_sub(self.scroll);
+        let start = end.saturating_sub(region.height);
+        for (row, line) in (region.row..).zip(&lines[start..end]) {
+            frame.write_line(row, region.col, region.right(), line);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_oldest_messages_are_dropped() {
+        let logger = TermbufferLogger::new(2);
+        for i in 0..3 {
+            logger.log(LogLevel::Info, "app", i);
+        }
+        let messages: Vec<_> = logger.records().into_iter().map(|r| r.message).collect();
+        assert_eq!(messages, ["1", "2"]);
+    }
+
+    #[test]
+    fn messages_below_the_max_level_are_ignored() {
+        let logger = TermbufferLogger::new(10);
+        logger.set_max_level(LogLevel::Warn);
+        logger.log(LogLevel::Debug, "app", "hidden");
+        logger.log(LogLevel::Error, "app", "shown");
+        assert_eq!(logger.len(), 1);
+        assert!(!logger.enabled(LogLevel::Info));
+    }
+
+    #[test]
+    fn the_view_shows_the_newest_messages() {
+        let logger = TermbufferLogger::new(10);
+        logger.log(LogLevel::Info, "app", "first");
+        logger.log(LogLevel::Warn, "", "second\nthird");
+        let mut frame = Frame::new(2, 20);
+        let area = frame.area();
+        LogView::new().render_into(&logger, &mut frame, area);
+        let mut text = Vec::new();
+        frame.write_text(&mut text).unwrap();
+        assert_eq!(
+            String::from_utf8(text).unwrap(),
+            "WARN  second\n      third\n"
+        );
+    }
+}
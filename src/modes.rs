@@ -0,0 +1,143 @@
+use crate::keymap::{KeyMap, KeyMatch};
+use termion::event::Key;
+
+/// An input mode, in the style of vim.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Hash)]
+pub enum Mode {
+    #[default]
+    Normal,
+    /// Keys are mostly typed as text, and count prefixes aren't used.
+    Insert,
+    /// Keys are mostly typed as a command line, and count prefixes aren't used.
+    Command,
+    /// A mode of your own, e.g. `Custom("visual".into())`.
+    Custom(String),
+}
+
+impl Mode {
+    /// Whether digits typed in this mode are a count prefix, rather than keys.
+    fn counts(&self) -> bool {
+        !matches!(self, Mode::Insert | Mode::Command)
+    }
+}
+
+/// The result of feeding a key to `InputModes`.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum Resolved<A> {
+    /// The keys so far are bound to an action. `count` is the number typed before them, if any.
+    Action { action: A, count: Option<usize> },
+    /// More keys are needed: a count is being typed, or the keys so far start a longer
+    /// sequence.
+    Pending,
+    /// The keys aren't bound to anything in the current mode. In insert mode, these are usually
+    /// typed as text.
+    Unbound(Vec<Key>),
+}
+
+/// Keeps track of the current input mode and turns keys into actions, using a separate
+/// `KeyMap` for each mode.
+///
+/// Outside insert and command mode, a number typed before a binding (like `3` in `3j`) is
+/// returned as its count. A `0` on its own is an ordinary key.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct InputModes<A> {
+    mode: Mode,
+    keymaps: Vec<(Mode, KeyMap<A>)>,
+    /// The keys typed so far in a sequence, not counting the count.
+    pending: Vec<Key>,
+    count: Option<usize>,
+}
+
+impl<A: Clone> Default for InputModes<A> {
+    fn default() -> InputModes<A> {
+        InputModes::new()
+    }
+}
+
+impl<A: Clone> InputModes<A> {
+    /// Start in normal mode, with no bindings.
+    pub fn new() -> InputModes<A> {
+        InputModes {
+            mode: Mode::Normal,
+            keymaps: Vec::new(),
+            pending: Vec::new(),
+            count: None,
+        }
+    }
+
+    pub fn mode(&self) -> &Mode {
+        &self.mode
+    }
+
+    /// Switch to another mode, throwing away any keys typed so far.
+    pub fn set_mode(&mut self, mode: Mode) {
+        self.mode = mode;
+        self.reset();
+    }
+
+    /// The bindings for a mode, to add to.
+    pub fn keymap_mut(&mut self, mode: Mode) -> &mut KeyMap<A> {
+        let index = match self.keymaps.iter().position(|(m, _)| *m == mode) {
+            Some(index) => index,
+            None => {
+                self.keymaps.push((mode, KeyMap::new()));
+                self.keymaps.len() - 1
+            }
+        };
+        &mut self.keymaps[index].1
+    }
+
+    /// Replace the bindings for a mode.
+    pub fn set_keymap(&mut self, mode: Mode, keymap: KeyMap<A>) {
+        *self.keymap_mut(mode) = keymap;
+    }
+
+    /// The keys typed so far in an unfinished sequence, e.g. to show them in a status bar.
+    pub fn pending(&self) -> &[Key] {
+        &self.pending
+    }
+
+    /// The count typed so far, if any.
+    pub fn count(&self) -> Option<usize> {
+        self.count
+    }
+
+    /// Throw away any keys typed so far.
+    pub fn reset(&mut self) {
+        self.pending.clear();
+        self.count = None;
+    }
+
+    /// Handle a key press in the current mode.
+    pub fn handle_key(&mut self, key: Key) -> Resolved<A> {
+        if let (Key::Char(ch @ '0'..='9'), true) = (key, self.pending.is_empty()) {
+            if self.mode.counts() && (ch != '0' || self.count.is_some()) {
+                let digit = ch as usize - '0' as usize;
+                let count = self.count.unwrap_or(0);
+                self.count = Some(count.saturating_mul(10).saturating_add(digit));
+                return Resolved::Pending;
+            }
+        }
+        self.pending.push(key);
+        let keymap = self.keymaps.iter().find(|(mode, _)| *mode == self.mode);
+        let found = match keymap {
+            Some((_, keymap)) => keymap.lookup(&self.pending),
+            None => KeyMatch::None,
+        };
+        match found {
+            KeyMatch::Action(action) => {
+                let resolved = Resolved::Action {
+                    action: action.clone(),
+                    count: self.count,
+                };
+                self.reset();
+                resolved
+            }
+            KeyMatch::Prefix => Resolved::Pending,
+            KeyMatch::None => {
+                self.count = None;
+                Resolved::Unbound(std::mem::take(&mut self.pending))
+            }
+        }
+    }
+}
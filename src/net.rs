@@ -0,0 +1,384 @@
+//! Serving apps to remote terminals over telnet, enabled with the `net` feature.
+//!
+//! Each connection gets its own `App`, so its screen is diffed and drawn independently of the
+//! others.
+use crate::backend::WriterBackend;
+use crate::{App, AppBuilder, Capabilities, ColorSupport, Error};
+use std::io::{self, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Interpret as command: the byte that starts every telnet command.
+const IAC: u8 = 255;
+const DONT: u8 = 254;
+const DO: u8 = 253;
+const WONT: u8 = 252;
+const WILL: u8 = 251;
+/// Start of subnegotiation.
+const SB: u8 = 250;
+/// End of subnegotiation.
+const SE: u8 = 240;
+
+const OPT_ECHO: u8 = 1;
+const OPT_SUPPRESS_GO_AHEAD: u8 = 3;
+/// Negotiate about window size (RFC 1073).
+const OPT_NAWS: u8 = 31;
+
+/// The size assumed for clients that don't report theirs.
+const DEFAULT_SIZE: (usize, usize) = (80, 24);
+
+/// How long to wait for a client to report its size before drawing the first frame.
+const NAWS_TIMEOUT: Duration = Duration::from_millis(500);
+/// The most bytes of a subnegotiation kept. The only one we understand, NAWS, needs 5, so
+/// longer ones are cut short and then ignored, and a client can't make us buffer without limit.
+const MAX_SUBNEGOTIATION: usize = 64;
+/// How long to wait before accepting connections again after running out of file descriptors or
+/// memory, so that `serve` doesn't spin until some are freed.
+const ACCEPT_BACKOFF: Duration = Duration::from_millis(100);
+
+/// A backend drawing to a telnet client.
+pub type TelnetBackend = WriterBackend<TelnetStream>;
+
+/// A TCP stream speaking the telnet protocol.
+///
+/// Writing to it escapes any bytes that would be taken as telnet commands.
+#[derive(Debug)]
+pub struct TelnetStream {
+    stream: TcpStream,
+}
+
+impl TelnetStream {
+    pub fn get_ref(&self) -> &TcpStream {
+        &self.stream
+    }
+}
+
+impl Write for TelnetStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match buf.iter().position(|&b| b == IAC) {
+            None => self.stream.write(buf),
+            Some(0) => {
+                self.stream.write_all(&[IAC, IAC])?;
+                Ok(1)
+            }
+            Some(pos) => self.stream.write(&buf[..pos]),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.stream.flush()
+    }
+}
+
+impl AsRawFd for TelnetStream {
+    fn as_raw_fd(&self) -> RawFd {
+        self.stream.as_raw_fd()
+    }
+}
+
+/// The size of the client's window, once it has told us.
+type WindowSize = Arc<(Mutex<Option<(usize, usize)>>, Condvar)>;
+
+impl WriterBackend<TelnetStream> {
+    /// Create a backend over a telnet connection.
+    ///
+    /// This asks the client to send keys as they are pressed without echoing them, and to
+    /// report its window size, waiting briefly for the size to arrive. Clients that don't
+    /// report a size are assumed to be 80x24.
+    pub fn telnet(mut stream: TcpStream) -> io::Result<TelnetBackend> {
+        stream.set_nodelay(true)?;
+        stream.write_all(&[
+            IAC,
+            WILL,
+            OPT_ECHO,
+            IAC,
+            WILL,
+            OPT_SUPPRESS_GO_AHEAD,
+            IAC,
+            DO,
+            OPT_NAWS,
+        ])?;
+        let window: WindowSize = Default::default();
+        let mut decoder = Decoder::new(window.clone());
+        let size_window = window.clone();
+        let backend = WriterBackend::filtered(
+            TelnetStream { stream },
+            move || Ok(size_window.0.lock().unwrap().unwrap_or(DEFAULT_SIZE)),
+            Box::new(move |input, out| decoder.decode(input, out)),
+        )?;
+        let (size, reported) = &*window;
+        let deadline = Instant::now() + NAWS_TIMEOUT;
+        let mut size = size.lock().unwrap();
+        while size.is_none() {
+            let now = Instant::now();
+            if now >= deadline {
+                break;
+            }
+            size = reported.wait_timeout(size, deadline - now).unwrap().0;
+        }
+        Ok(backend)
+    }
+}
+
+/// The state of the telnet protocol between reads.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum State {
+    Data,
+    Command,
+    /// The option of a WILL, WONT, DO or DONT.
+    Option,
+    Subnegotiation,
+    SubnegotiationCommand,
+}
+
+/// Strips telnet commands out of the input, keeping track of the window size.
+struct Decoder {
+    state: State,
+    subnegotiation: Vec<u8>,
+    /// Whether the last byte was a carriage return, which telnet follows with a NUL or line
+    /// feed.
+    carriage_return: bool,
+    window: WindowSize,
+}
+
+impl Decoder {
+    fn new(window: WindowSize) -> Decoder {
+        Decoder {
+            state: State::Data,
+            subnegotiation: Vec::new(),
+            carriage_return: false,
+            window,
+        }
+    }
+
+    fn decode(&mut self, input: &[u8], out: &mut Vec<u8>) {
+        for &byte in input {
+            self.state = match (self.state, byte) {
+                (State::Data, IAC) => State::Command,
+                (State::Data, 0) | (State::Data, b'\n') if self.carriage_return => {
+                    // Enter arrives as "\r\0" or "\r\n", but a terminal sends just "\r".
+                    self.carriage_return = false;
+                    State::Data
+                }
+                (State::Data, _) => {
+                    self.carriage_return = byte == b'\r';
+                    out.push(byte);
+                    State::Data
+                }
+                (State::Command, IAC) => {
+                    out.push(IAC);
+                    State::Data
+                }
+                (State::Command, SB) => {
+                    self.subnegotiation.clear();
+                    State::Subnegotiation
+                }
+                (State::Command, WILL) | (State::Command, WONT) => State::Option,
+                (State::Command, DO) | (State::Command, DONT) => State::Option,
+                (State::Command, _) | (State::Option, _) => State::Data,
+                (State::Subnegotiation, IAC) => State::SubnegotiationCommand,
+                (State::Subnegotiation, _) => {
+                    self.push_subnegotiation(byte);
+                    State::Subnegotiation
+                }
+                (State::SubnegotiationCommand, IAC) => {
+                    self.push_subnegotiation(IAC);
+                    State::Subnegotiation
+                }
+                (State::SubnegotiationCommand, SE) => {
+                    self.end_subnegotiation();
+                    State::Data
+                }
+                (State::SubnegotiationCommand, _) => State::Data,
+            };
+        }
+    }
+
+    fn push_subnegotiation(&mut self, byte: u8) {
+        if self.subnegotiation.len() < MAX_SUBNEGOTIATION {
+            self.subnegotiation.push(byte);
+        }
+    }
+
+    fn end_subnegotiation(&mut self) {
+        if let [OPT_NAWS, w0, w1, h0, h1] = self.subnegotiation[..] {
+            let cols = usize::from(u16::from_be_bytes([w0, w1]));
+            let rows = usize::from(u16::from_be_bytes([h0, h1]));
+            // Some clients send zeros when they don't know.
+            if cols > 0 && rows > 0 {
+                let (size, reported) = &*self.window;
+                *size.lock().unwrap() = Some((cols, rows));
+                reported.notify_all();
+            }
+        }
+    }
+}
+
+/// Accepts telnet connections, giving each its own `App`.
+///
+/// Unless set on the builder, clients are assumed to understand xterm escapes and 256 colors.
+pub struct TelnetServer {
+    listener: TcpListener,
+    builder: AppBuilder,
+}
+
+impl TelnetServer {
+    /// Listen for connections on the given address, e.g. `"0.0.0.0:2323"`.
+    pub fn bind(addr: impl ToSocketAddrs) -> io::Result<TelnetServer> {
+        Ok(TelnetServer {
+            listener: TcpListener::bind(addr)?,
+            builder: AppBuilder::default(),
+        })
+    }
+
+    /// The builder used to build each connection's app.
+    pub fn builder(mut self, builder: AppBuilder) -> TelnetServer {
+        self.builder = builder;
+        self
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Wait for the next connection, and build an app for it.
+    pub fn accept(&self) -> Result<(App<TelnetBackend>, SocketAddr), Error> {
+        let (stream, addr) = self.listener.accept()?;
+        Ok((connect(self.builder.clone(), stream)?, addr))
+    }
+
+    /// Run `session` for every connection, each on its own thread.
+    ///
+    /// This only returns if the listener stops working. Connections that fail while being
+    /// accepted (e.g. because the client hung up) are skipped, and if the process runs out of
+    /// file descriptors, accepting waits a little and tries again. Errors setting up a single
+    /// connection just drop that connection.
+    pub fn serve<F>(self, session: F) -> io::Result<()>
+    where
+        F: Fn(App<TelnetBackend>, SocketAddr) + Send + Sync + 'static,
+    {
+        let session = Arc::new(session);
+        loop {
+            let (stream, addr) = match self.listener.accept() {
+                Ok(accepted) => accepted,
+                Err(err) => match accept_retry(&err) {
+                    Some(wait) => {
+                        thread::sleep(wait);
+                        continue;
+                    }
+                    None => return Err(err),
+                },
+            };
+            let (builder, session) = (self.builder.clone(), session.clone());
+            thread::spawn(move || {
+                if let Ok(app) = connect(builder, stream) {
+                    session(app, addr);
+                }
+            });
+        }
+    }
+}
+
+/// How long to wait before trying again after accepting a connection fails, or `None` if the
+/// error means the listener itself is broken.
+fn accept_retry(err: &io::Error) -> Option<Duration> {
+    match err.raw_os_error() {
+        Some(libc::EMFILE | libc::ENFILE | libc::ENOBUFS | libc::ENOMEM) => {
+            return Some(ACCEPT_BACKOFF)
+        }
+        // Linux reports pending network errors on the new connection from `accept`.
+        Some(
+            libc::EPROTO
+            | libc::ENOPROTOOPT
+            | libc::EHOSTDOWN
+            | libc::EHOSTUNREACH
+            | libc::ENETDOWN
+            | libc::ENETUNREACH
+            | libc::EOPNOTSUPP,
+        ) => return Some(Duration::ZERO),
+        _ => (),
+    }
+    match err.kind() {
+        io::ErrorKind::ConnectionAborted
+        | io::ErrorKind::ConnectionReset
+        | io::ErrorKind::Interrupted
+        | io::ErrorKind::WouldBlock
+        | io::ErrorKind::TimedOut => Some(Duration::ZERO),
+        _ => None,
+    }
+}
+
+fn connect(mut builder: AppBuilder, stream: TcpStream) -> Result<App<TelnetBackend>, Error> {
+    builder
+        .capabilities
+        .get_or_insert_with(|| Capabilities::xterm(ColorSupport::Indexed256));
+    builder.build_with_backend(WriterBackend::telnet(stream)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(input: &[u8]) -> (Vec<u8>, Option<(usize, usize)>) {
+        let window: WindowSize = Default::default();
+        let mut decoder = Decoder::new(window.clone());
+        let mut out = Vec::new();
+        // Feed the input a byte at a time, since commands can be split across reads.
+        for byte in input {
+            decoder.decode(&[*byte], &mut out);
+        }
+        let size = *window.0.lock().unwrap();
+        (out, size)
+    }
+
+    #[test]
+    fn commands_are_stripped() {
+        let input = [
+            b'a', IAC, WILL, OPT_ECHO, b'b', IAC, IAC, b'c', IAC, 241, b'd',
+        ];
+        assert_eq!(decode(&input).0, [b'a', b'b', IAC, b'c', b'd']);
+    }
+
+    #[test]
+    fn enter_is_a_carriage_return() {
+        assert_eq!(decode(b"a\r\0b\r\nc").0, b"a\rb\rc");
+    }
+
+    #[test]
+    fn window_size_is_read() {
+        let input = [b'x', IAC, SB, OPT_NAWS, 0, 120, 0, 40, IAC, SE, b'y'];
+        assert_eq!(decode(&input), (b"xy".to_vec(), Some((120, 40))));
+        // An escaped IAC inside the subnegotiation.
+        let input = [IAC, SB, OPT_NAWS, 0, IAC, IAC, 0, 40, IAC, SE];
+        assert_eq!(decode(&input).1, Some((255, 40)));
+    }
+
+    #[test]
+    fn long_subnegotiations_are_capped_and_ignored() {
+        let window: WindowSize = Default::default();
+        let mut decoder = Decoder::new(window.clone());
+        let mut out = Vec::new();
+        decoder.decode(&[IAC, SB, OPT_NAWS, 0, 80, 0, 24], &mut out);
+        for _ in 0..1000 {
+            decoder.decode(&[1; 64], &mut out);
+        }
+        assert_eq!(decoder.subnegotiation.len(), MAX_SUBNEGOTIATION);
+        decoder.decode(&[IAC, SE, b'z'], &mut out);
+        assert_eq!(out, b"z");
+        assert_eq!(*window.0.lock().unwrap(), None);
+    }
+
+    #[test]
+    fn transient_accept_errors_are_retried() {
+        let os = io::Error::from_raw_os_error;
+        assert_eq!(accept_retry(&os(libc::EMFILE)), Some(ACCEPT_BACKOFF));
+        assert_eq!(accept_retry(&os(libc::ECONNABORTED)), Some(Duration::ZERO));
+        assert_eq!(accept_retry(&os(libc::EINTR)), Some(Duration::ZERO));
+        assert_eq!(accept_retry(&os(libc::EPROTO)), Some(Duration::ZERO));
+        assert_eq!(accept_retry(&os(libc::EBADF)), None);
+        assert_eq!(accept_retry(&os(libc::EINVAL)), None);
+    }
+}
@@ -0,0 +1,200 @@
+use std::env;
+
+/// The longest clipboard reply we'll wait for the end of. Anything longer is given up on.
+pub(crate) const MAX_REPLY_LEN: usize = 1 << 20;
+
+/// The start of the terminal's reply to a clipboard request.
+const CLIPBOARD_REPLY: &[u8] = b"\x1b]52;";
+
+/// The longest string GNU screen will pass through in one go.
+const SCREEN_CHUNK: usize = 76;
+
+/// Whether we're running inside GNU screen, which swallows OSC escapes unless they are sent
+/// through in DCS chunks.
+fn in_screen() -> bool {
+    env::var_os("TMUX").is_none() && env::var("TERM").is_ok_and(|term| term.starts_with("screen"))
+}
+
+/// Wrap an escape sequence so it reaches the outer terminal, if needed.
+fn pass_through(sequence: &str) -> String {
+    if !in_screen() {
+        return sequence.to_string();
+    }
+    let mut out = String::new();
+    // The sequence is ASCII, so it can be split anywhere.
+    for chunk in sequence.as_bytes().chunks(SCREEN_CHUNK) {
+        out.push_str("\x1bP");
+        out.push_str(std::str::from_utf8(chunk).unwrap());
+        out.push_str("\x1b\\");
+    }
+    out
+}
+
+/// The OSC 52 escape to copy text to the system clipboard.
+pub(crate) fn set_clipboard(text: &str) -> String {
+    pass_through(&format!("\x1b]52;c;{}\x07", base64(text.as_bytes())))
+}
+
+/// The escape to show a desktop notification.
+///
+/// Terminals that support OSC 777 (rxvt, foot, ghostty, ...) get a title and body. Others get
+/// OSC 9, which only has a message, so the title and body are joined.
+pub(crate) fn notify(title: &str, body: &str) -> String {
+    let term = env::var("TERM").unwrap_or_default();
+    // Control characters would end the escape early.
+    let clean = |s: &str| s.chars().filter(|c| !c.is_control()).collect::<String>();
+    let sequence = if ["rxvt", "foot", "ghostty"]
+        .iter()
+        .any(|name| term.contains(name))
+    {
+        // Fields are separated by semicolons, so the title can't contain any.
+        let title = clean(title).replace(';', ",");
+        format!("\x1b]777;notify;{};{}\x07", title, clean(body))
+    } else if title.is_empty() {
+        format!("\x1b]9;{}\x07", clean(body))
+    } else {
+        format!("\x1b]9;{}: {}\x07", clean(title), clean(body))
+    };
+    pass_through(&sequence)
+}
+
+/// The OSC 52 escape to ask the terminal for the contents of the clipboard.
+pub(crate) fn request_clipboard() -> String {
+    pass_through("\x1b]52;c;?\x07")
+}
+
+/// If `bytes` starts with a clipboard reply, its length, or `Some(None)` if the end hasn't
+/// arrived yet.
+pub(crate) fn reply_len(bytes: &[u8]) -> Option<Option<usize>> {
+    if !bytes.starts_with(CLIPBOARD_REPLY) {
+        return None;
+    }
+    // The reply ends with BEL or ST (ESC \\).
+    let end = bytes.iter().enumerate().find_map(|(i, &b)| match b {
+        0x07 => Some(i + 1),
+        0x1b if bytes.get(i + 1) == Some(&b'\\') => Some(i + 2),
+        _ => None,
+    });
+    Some(end)
+}
+
+/// The text in a clipboard reply found by `reply_len`.
+pub(crate) fn parse_clipboard_reply(reply: &[u8]) -> Option<String> {
+    let body = reply.strip_prefix(CLIPBOARD_REPLY)?;
+    let end = body.iter().position(|&b| b == 0x07 || b == 0x1b)?;
+    // Skip the selection, e.g. `c;`.
+    let data = body[..end].splitn(2, |&b| b == b';').nth(1)?;
+    let bytes = base64_decode(data)?;
+    Some(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// Read the clipboard with whichever of the usual command-line tools is installed.
+#[cfg(feature = "local-clipboard")]
+pub(crate) fn read_local_clipboard() -> Option<String> {
+    use std::process::{Command, Stdio};
+    const COMMANDS: [&[&str]; 4] = [
+        &["pbpaste"],
+        &["wl-paste", "--no-newline"],
+        &["xclip", "-selection", "clipboard", "-o"],
+        &["xsel", "--clipboard", "--output"],
+    ];
+    COMMANDS.iter().find_map(|command| {
+        let output = Command::new(command[0])
+            .args(&command[1..])
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .output()
+            .ok()?;
+        if output.status.success() {
+            String::from_utf8(output.stdout).ok()
+        } else {
+            None
+        }
+    })
+}
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn base64(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &b)| n | u32::from(b) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+fn base64_decode(data: &[u8]) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(data.len() / 4 * 3);
+    let (mut n, mut bits) = (0u32, 0);
+    for &b in data.iter().filter(|b| !b.is_ascii_whitespace()) {
+        if b == b'=' {
+            break;
+        }
+        let value = ALPHABET.iter().position(|&c| c == b)? as u32;
+        n = n << 6 | value;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((n >> bits) as u8);
+        }
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn base64_round_trips() {
+        for (plain, encoded) in [
+            ("", ""),
+            ("f", "Zg=="),
+            ("fo", "Zm8="),
+            ("foo", "Zm9v"),
+            ("foob", "Zm9vYg=="),
+            ("hello, wörld", "aGVsbG8sIHfDtnJsZA=="),
+        ] {
+            assert_eq!(base64(plain.as_bytes()), encoded);
+            assert_eq!(base64_decode(encoded.as_bytes()).unwrap(), plain.as_bytes());
+        }
+        assert_eq!(base64_decode(b"Zm9v\nYg==").unwrap(), b"foob");
+        assert_eq!(base64_decode(b"Zm9v!"), None);
+    }
+
+    #[test]
+    fn clipboard_replies_are_found() {
+        assert_eq!(reply_len(b"\x1b[A"), None);
+        assert_eq!(reply_len(b"\x1b]52;c;Zm9v"), Some(None));
+        assert_eq!(reply_len(b"\x1b]52;c;Zm9v\x07rest"), Some(Some(12)));
+        assert_eq!(reply_len(b"\x1b]52;c;Zm9v\x1b\\rest"), Some(Some(13)));
+    }
+
+    #[test]
+    fn clipboard_replies_are_decoded() {
+        assert_eq!(
+            parse_clipboard_reply(b"\x1b]52;c;aGVsbG8=\x07").as_deref(),
+            Some("hello")
+        );
+        assert_eq!(
+            parse_clipboard_reply(b"\x1b]52;p;aGVsbG8=\x1b\\").as_deref(),
+            Some("hello")
+        );
+        assert_eq!(
+            parse_clipboard_reply(b"\x1b]52;c;\x07").as_deref(),
+            Some("")
+        );
+        assert_eq!(parse_clipboard_reply(b"\x1b]52;c;aGVsbG8="), None);
+        assert_eq!(parse_clipboard_reply(b"\x1b]52;c;*!*\x07"), None);
+    }
+}
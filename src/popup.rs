@@ -0,0 +1,130 @@
+use crate::block::Block;
+use crate::layers::Layers;
+use crate::layout::{resolve, Constraint};
+use crate::rect::Rect;
+use crate::region::Region;
+use crate::screen::{Attributes, Char, Color, Frame, Style};
+
+/// The tint used to dim whatever is underneath a popup on a layer.
+const DIM_TINT: Color = Color::Rgba(0, 0, 0, 128);
+
+/// Where a `Popup` goes in the area it is shown over.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+pub enum Anchor {
+    #[default]
+    Center,
+    Top,
+    Bottom,
+    Left,
+    Right,
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+    /// With its top-left corner at this position, moved back if needed to fit.
+    At(usize, usize),
+}
+
+/// What happens to everything underneath a `Popup`.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+pub enum Backdrop {
+    /// It is left alone.
+    #[default]
+    None,
+    /// It is dimmed, so the popup stands out.
+    Dim,
+}
+
+/// A bordered box drawn over the rest of the UI, like a dialog or menu.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct Popup<'a> {
+    pub height: Constraint,
+    pub width: Constraint,
+    pub anchor: Anchor,
+    /// The border and title.
+    pub block: Block<'a>,
+    /// The style the inside of the popup is cleared to.
+    pub style: Style,
+    pub backdrop: Backdrop,
+}
+
+impl<'a> Popup<'a> {
+    pub fn new(height: Constraint, width: Constraint) -> Popup<'a> {
+        Popup {
+            height,
+            width,
+            anchor: Anchor::Center,
+            block: Block::new(),
+            style: Style::default(),
+            backdrop: Backdrop::None,
+        }
+    }
+
+    /// The area the popup covers when shown over `area`.
+    pub fn area(&self, area: Rect) -> Rect {
+        let height = resolve(&[self.height], area.height)[0].min(area.height);
+        let width = resolve(&[self.width], area.width)[0].min(area.width);
+        let (free_rows, free_cols) = (area.height - height, area.width - width);
+        let (row, col) = match self.anchor {
+            Anchor::Center => (free_rows / 2, free_cols / 2),
+            Anchor::Top => (0, free_cols / 2),
+            Anchor::Bottom => (free_rows, free_cols / 2),
+            Anchor::Left => (free_rows / 2, 0),
+            Anchor::Right => (free_rows / 2, free_cols),
+            Anchor::TopLeft => (0, 0),
+            Anchor::TopRight => (0, free_cols),
+            Anchor::BottomLeft => (free_rows, 0),
+            Anchor::BottomRight => (free_rows, free_cols),
+            Anchor::At(row, col) => (
+                row.saturating_sub(area.row).min(free_rows),
+                col.saturating_sub(area.col).min(free_cols),
+            ),
+        };
+        Rect::new(area.row + row, area.col + col, height, width)
+    }
+
+    /// Draw the popup over `area` of the frame, and return a region for its content.
+    ///
+    /// With `Backdrop::Dim`, the rest of the area is drawn with `Attributes::DIM`.
+    pub fn render_into<'f>(&self, frame: &'f mut Frame, area: Rect) -> Region<'f> {
+        let area = area.intersection(frame.area());
+        if self.backdrop == Backdrop::Dim {
+            for row in area.row..area.bottom() {
+                for col in area.col..area.right() {
+                    frame[(row, col)].attrs.insert(Attributes::DIM);
+                }
+            }
+        }
+        self.draw(frame, area)
+    }
+
+    /// Draw the popup on the top layer, so it shows up over everything below, and return a
+    /// region for its content. Will panic if there are no layers.
+    ///
+    /// With `Backdrop::Dim`, the rest of the layer is filled with transparent cells that darken
+    /// whatever is underneath.
+    pub fn render_on<'l>(&self, layers: &'l mut Layers) -> Region<'l> {
+        let layer = layers.layer_mut(layers.len() - 1);
+        let area = layer.area();
+        if self.backdrop == Backdrop::Dim {
+            let tint = Char {
+                color_bg: DIM_TINT,
+                ..Char::TRANSPARENT
+            };
+            layer.fill_rect(area.row, area.col, area.height, area.width, tint);
+        }
+        self.draw(layer, area)
+    }
+
+    fn draw<'f>(&self, frame: &'f mut Frame, area: Rect) -> Region<'f> {
+        let popup = self.area(area);
+        frame.fill_rect(
+            popup.row,
+            popup.col,
+            popup.height,
+            popup.width,
+            self.style.char(' '),
+        );
+        self.block.render_into(frame, popup)
+    }
+}
@@ -0,0 +1,85 @@
+use crate::rect::Rect;
+use crate::screen::{Attributes, Frame, Style};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// Blocks for eighths of a cell, filled from the left.
+const PARTIAL_BLOCKS: [char; 7] = ['▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+
+/// A horizontal progress bar, with an optional label in the middle.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProgressBar<'a> {
+    /// How far along the bar is, from 0 to 1.
+    pub ratio: f64,
+    /// Text to show in the middle of the bar.
+    pub label: Option<&'a str>,
+    /// The style of the filled part. It is drawn with blocks in the foreground color.
+    pub filled: Style,
+    /// The style of the rest of the bar.
+    pub unfilled: Style,
+    /// Whether to use partial blocks to show progress in eighths of a cell, rather than whole
+    /// cells.
+    pub smooth: bool,
+}
+
+impl<'a> ProgressBar<'a> {
+    pub fn new(ratio: f64) -> ProgressBar<'a> {
+        ProgressBar {
+            ratio,
+            label: None,
+            filled: Style::default(),
+            unfilled: Style::default(),
+            smooth: true,
+        }
+    }
+
+    /// Draw the bar to fill a region of a frame. The label goes on the middle row.
+    pub fn render_into(&self, frame: &mut Frame, region: Rect) {
+        let region = region.intersection(frame.area());
+        let ratio = if self.ratio.is_nan() {
+            0.0
+        } else {
+            self.ratio.clamp(0.0, 1.0)
+        };
+        let mut eighths = (ratio * (region.width * 8) as f64).round() as usize;
+        if !self.smooth {
+            eighths = (eighths + 4) / 8 * 8;
+        }
+        for row in region.row..region.bottom() {
+            for (i, col) in (region.col..region.right()).enumerate() {
+                let ch = match eighths.saturating_sub(i * 8).min(8) {
+                    0 => self.unfilled.char(' '),
+                    8 => self.filled.char('█'),
+                    partial => Style {
+                        color_fg: self.filled.color_fg,
+                        ..self.unfilled.clone()
+                    }
+                    .char(PARTIAL_BLOCKS[partial - 1]),
+                };
+                frame.set_clipped(row, col, ch);
+            }
+        }
+        let label = match self.label {
+            Some(label) if !region.is_empty() => label,
+            _ => return,
+        };
+        let row = region.row + region.height / 2;
+        let mut col = region.col + region.width.saturating_sub(label.width()) / 2;
+        for glyph in label.graphemes(true) {
+            let width = glyph.width();
+            if col + width > region.right() {
+                break;
+            }
+            // Over the filled part, the label is drawn in reverse so it shows up.
+            let ch = if (col - region.col + 1) * 8 <= eighths {
+                let mut style = self.filled.clone();
+                style.attrs.insert(Attributes::REVERSE);
+                style.char(glyph)
+            } else {
+                self.unfilled.char(glyph)
+            };
+            frame.set_clipped(row, col, ch);
+            col += width.max(1);
+        }
+    }
+}
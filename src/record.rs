@@ -0,0 +1,93 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+/// Records terminal output to an asciinema v2 cast file.
+///
+/// Output is collected until it is flushed, and then written as a single event.
+pub(crate) struct Recorder {
+    file: BufWriter<File>,
+    start: Instant,
+    pending: Vec<u8>,
+}
+
+impl Recorder {
+    pub(crate) fn create(path: &Path, cols: usize, rows: usize) -> io::Result<Recorder> {
+        let mut file = BufWriter::new(File::create(path)?);
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        writeln!(
+            file,
+            r#"{{"version": 2, "width": {}, "height": {}, "timestamp": {}}}"#,
+            cols, rows, timestamp
+        )?;
+        Ok(Recorder {
+            file,
+            start: Instant::now(),
+            pending: Vec::new(),
+        })
+    }
+
+    fn write(&mut self, buf: &[u8]) {
+        self.pending.extend_from_slice(buf);
+    }
+
+    /// Write out the output collected since the last flush as an event.
+    fn flush(&mut self) -> io::Result<()> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+        let time = self.start.elapsed().as_secs_f64();
+        let data = json_string(&String::from_utf8_lossy(&self.pending));
+        self.pending.clear();
+        writeln!(self.file, r#"[{:.6}, "o", {}]"#, time, data)?;
+        self.file.flush()
+    }
+}
+
+/// Quote a string for JSON.
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 || c == '\u{7f}' => {
+                out.push_str(&format!("\\u{:04x}", c as u32));
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// The terminal output, copied to the recorder if there is one.
+pub(crate) struct Output<'a, W: Write> {
+    pub(crate) writer: &'a mut W,
+    pub(crate) recorder: Option<&'a mut Recorder>,
+}
+
+impl<'a, W: Write> Write for Output<'a, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let len = self.writer.write(buf)?;
+        if let Some(recorder) = &mut self.recorder {
+            recorder.write(&buf[..len]);
+        }
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()?;
+        if let Some(recorder) = &mut self.recorder {
+            recorder.flush()?;
+        }
+        Ok(())
+    }
+}
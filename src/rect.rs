@@ -0,0 +1,48 @@
+/// A rectangular area of a frame, in cells.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct Rect {
+    pub row: usize,
+    pub col: usize,
+    pub height: usize,
+    pub width: usize,
+}
+
+impl Rect {
+    pub fn new(row: usize, col: usize, height: usize, width: usize) -> Rect {
+        Rect {
+            row,
+            col,
+            height,
+            width,
+        }
+    }
+
+    /// The row one past the bottom of the rectangle.
+    pub fn bottom(&self) -> usize {
+        self.row + self.height
+    }
+
+    /// The column one past the right of the rectangle.
+    pub fn right(&self) -> usize {
+        self.col + self.width
+    }
+
+    /// Whether the rectangle covers no cells.
+    pub fn is_empty(&self) -> bool {
+        self.height == 0 || self.width == 0
+    }
+
+    /// Whether the given cell is inside the rectangle.
+    pub fn contains(&self, row: usize, col: usize) -> bool {
+        row >= self.row && row < self.bottom() && col >= self.col && col < self.right()
+    }
+
+    /// The overlap of two rectangles (which may be empty).
+    pub fn intersection(&self, other: Rect) -> Rect {
+        let row = self.row.max(other.row);
+        let col = self.col.max(other.col);
+        let bottom = self.bottom().min(other.bottom()).max(row);
+        let right = self.right().min(other.right()).max(col);
+        Rect::new(row, col, bottom - row, right - col)
+    }
+}
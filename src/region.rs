@@ -0,0 +1,139 @@
+use crate::rect::Rect;
+use crate::screen::{Attributes, Char, Color, Frame, Style};
+use crate::span::Line;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// A rectangular part of a frame, with its own coordinates starting at `(0, 0)` in its top-left
+/// corner.
+///
+/// Anything drawn outside the region is silently cut off, so widgets can draw into a region
+/// without knowing where it is on the screen or worrying about its edges. Create one with
+/// `Frame::region` (or on a `Draw`, which derefs to a `Frame`).
+#[derive(Debug)]
+pub struct Region<'a> {
+    frame: &'a mut Frame,
+    /// The area of the frame covered, always inside the frame.
+    area: Rect,
+}
+
+impl Frame {
+    /// A view of part of the frame. The area is cut down to fit inside the frame.
+    pub fn region(&mut self, area: Rect) -> Region<'_> {
+        let area = area.intersection(self.area());
+        Region { frame: self, area }
+    }
+}
+
+impl<'a> Region<'a> {
+    /// The area of the frame this region covers, in frame coordinates.
+    pub fn area(&self) -> Rect {
+        self.area
+    }
+
+    /// The number of rows in the region.
+    pub fn rows(&self) -> usize {
+        self.area.height
+    }
+
+    /// The number of columns in the region.
+    pub fn columns(&self) -> usize {
+        self.area.width
+    }
+
+    /// A smaller view inside this one, with `area` in this region's coordinates. The area is cut
+    /// down to fit inside this region.
+    pub fn region(&mut self, area: Rect) -> Region<'_> {
+        let area = self.to_frame(area).intersection(self.area);
+        Region {
+            frame: self.frame,
+            area,
+        }
+    }
+
+    /// The cell at the given position, or `None` if it is outside the region.
+    pub fn get(&self, row: usize, col: usize) -> Option<Char> {
+        if row < self.area.height && col < self.area.width {
+            Some(self.frame.get(self.area.row + row, self.area.col + col))
+        } else {
+            None
+        }
+    }
+
+    /// Set the cell at the given position, doing nothing if it is outside the region.
+    ///
+    /// A wide glyph in the last column of the region doesn't fit, so it isn't drawn.
+    pub fn set(&mut self, row: usize, col: usize, ch: Char) {
+        if row < self.area.height && col < self.area.width {
+            self.frame.write_run(
+                self.area.row + row,
+                self.area.col + col,
+                self.area.right(),
+                Some(ch),
+            );
+        }
+    }
+
+    /// Write a string starting at the given position, using the given colors.
+    ///
+    /// Anything that falls outside the region is dropped. Returns the number of cells written.
+    pub fn set_str(&mut self, row: usize, col: usize, s: &str, fg: Color, bg: Color) -> usize {
+        if row >= self.area.height || col >= self.area.width {
+            return 0;
+        }
+        let style = Style::new(fg, bg, Attributes::empty());
+        self.frame.write_run(
+            self.area.row + row,
+            self.area.col + col,
+            self.area.right(),
+            s.graphemes(true).map(|glyph| style.char(glyph)),
+        )
+    }
+
+    /// Write a line of spans starting at the given position.
+    ///
+    /// Anything that falls outside the region is dropped. Returns the number of cells written.
+    pub fn set_line(&mut self, row: usize, col: usize, line: &Line) -> usize {
+        if row >= self.area.height || col >= self.area.width {
+            return 0;
+        }
+        self.frame.write_line(
+            self.area.row + row,
+            self.area.col + col,
+            self.area.right(),
+            line,
+        )
+    }
+
+    /// Fill the whole region with the given character.
+    pub fn fill(&mut self, ch: Char) {
+        let area = self.area;
+        self.frame
+            .fill_rect(area.row, area.col, area.height, area.width, ch);
+    }
+
+    /// Fill a rectangle (in region coordinates) with the given character.
+    ///
+    /// Any part of the rectangle that lies outside the region is ignored.
+    pub fn fill_rect(&mut self, row: usize, col: usize, height: usize, width: usize, ch: Char) {
+        let rect = self
+            .to_frame(Rect::new(row, col, height, width))
+            .intersection(self.area);
+        self.frame
+            .fill_rect(rect.row, rect.col, rect.height, rect.width, ch);
+    }
+
+    /// The whole frame this region is part of, for drawing outside the region.
+    pub(crate) fn frame_mut(&mut self) -> &mut Frame {
+        self.frame
+    }
+
+    /// Convert a rectangle from region coordinates to frame coordinates.
+    fn to_frame(&self, rect: Rect) -> Rect {
+        Rect::new(
+            self.area.row + rect.row,
+            self.area.col + rect.col,
+            rect.height,
+            rect.width,
+        )
+    }
+}
@@ -0,0 +1,646 @@
+use crate::backend::{Backend, TestBackend};
+use crate::event::{Event, Mouse, MouseAction};
+use crate::kitty::{KeyCode, KeyEvent, KeyEventKind, Modifiers};
+use crate::signal::JobControl;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use std::str::FromStr;
+use std::task::Waker;
+use std::thread;
+use std::time::{Duration, Instant};
+use termion::event::{Key, MouseButton};
+
+/// A list of events with the time they happened, relative to the start of a session.
+///
+/// Logs can be saved to and loaded from a simple line-based text format, with one event per
+/// line.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EventLog {
+    events: Vec<(Duration, Event)>,
+}
+
+impl EventLog {
+    pub fn new() -> EventLog {
+        EventLog::default()
+    }
+
+    /// Add an event that happened at `time`. Events must be added in time order.
+    pub fn push(&mut self, time: Duration, event: Event) {
+        self.events.push((time, event));
+    }
+
+    pub fn events(&self) -> &[(Duration, Event)] {
+        &self.events
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_string())
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<EventLog> {
+        fs::read_to_string(path)?.parse()
+    }
+}
+
+impl std::fmt::Display for EventLog {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for (time, event) in &self.events {
+            writeln!(f, "{} {}", time.as_millis(), format_event(event))?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for EventLog {
+    type Err = io::Error;
+
+    fn from_str(s: &str) -> io::Result<EventLog> {
+        let mut log = EventLog::new();
+        for (i, line) in s.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let invalid = || {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("invalid event on line {}: {:?}", i + 1, line),
+                )
+            };
+            let mut words = line.split_whitespace();
+            let millis = words
+                .next()
+                .and_then(|w| w.parse().ok())
+                .ok_or_else(invalid)?;
+            let event = parse_event(&mut words).ok_or_else(invalid)?;
+            log.push(Duration::from_millis(millis), event);
+        }
+        Ok(log)
+    }
+}
+
+fn format_event(event: &Event) -> String {
+    match event {
+        Event::Key(key) => format!("key {}", format_key(*key)),
+        Event::KeyEvent(KeyEvent {
+            code,
+            modifiers,
+            kind,
+        }) => {
+            let kind = match kind {
+                KeyEventKind::Press => "press",
+                KeyEventKind::Repeat => "repeat",
+                KeyEventKind::Release => "release",
+            };
+            format!(
+                "key-event {} {} {}",
+                kind,
+                modifiers.bits(),
+                format_key_code(*code)
+            )
+        }
+        Event::Mouse(Mouse { action, row, col }) => match action {
+            MouseAction::Press(button) => {
+                let button = match button {
+                    MouseButton::Left => "left",
+                    MouseButton::Right => "right",
+                    MouseButton::Middle => "middle",
+                    MouseButton::WheelUp => "wheel-up",
+                    MouseButton::WheelDown => "wheel-down",
+                };
+                format!("mouse press {} {} {}", button, row, col)
+            }
+            MouseAction::Release => format!("mouse release {} {}", row, col),
+            MouseAction::Hold => format!("mouse hold {} {}", row, col),
+        },
+        Event::Unsupported(bytes) => {
+            let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+            format!("unsupported {}", hex)
+        }
+        Event::Clipboard(text) => {
+            let hex: String = text.bytes().map(|b| format!("{:02x}", b)).collect();
+            format!("clipboard {}", hex)
+        }
+        Event::Resize(cols, rows) => format!("resize {} {}", cols, rows),
+        Event::Tick => "tick".into(),
+        Event::Suspend => "suspend".into(),
+        Event::Resume => "resume".into(),
+        Event::FocusGained => "focus-gained".into(),
+        Event::FocusLost => "focus-lost".into(),
+    }
+}
+
+/// Keys that are written as a single word.
+const NAMED_KEYS: [(Key, &str); 14] = [
+    (Key::Backspace, "backspace"),
+    (Key::Left, "left"),
+    (Key::Right, "right"),
+    (Key::Up, "up"),
+    (Key::Down, "down"),
+    (Key::Home, "home"),
+    (Key::End, "end"),
+    (Key::PageUp, "page-up"),
+    (Key::PageDown, "page-down"),
+    (Key::BackTab, "back-tab"),
+    (Key::Delete, "delete"),
+    (Key::Insert, "insert"),
+    (Key::Null, "null"),
+    (Key::Esc, "esc"),
+];
+
+/// Characters are written as their code point in hex, so whitespace survives.
+fn format_key(key: Key) -> String {
+    match key {
+        Key::F(n) => format!("f {}", n),
+        Key::Char(c) => format!("char {:x}", c as u32),
+        Key::Alt(c) => format!("alt {:x}", c as u32),
+        Key::Ctrl(c) => format!("ctrl {:x}", c as u32),
+        key => NAMED_KEYS
+            .iter()
+            .find(|(k, _)| *k == key)
+            .map_or("null", |(_, name)| name)
+            .into(),
+    }
+}
+
+/// Key codes that are written as a single word.
+const NAMED_KEY_CODES: [(KeyCode, &str); 14] = [
+    (KeyCode::Enter, "enter"),
+    (KeyCode::Tab, "tab"),
+    (KeyCode::Backspace, "backspace"),
+    (KeyCode::Esc, "esc"),
+    (KeyCode::Left, "left"),
+    (KeyCode::Right, "right"),
+    (KeyCode::Up, "up"),
+    (KeyCode::Down, "down"),
+    (KeyCode::Home, "home"),
+    (KeyCode::End, "end"),
+    (KeyCode::PageUp, "page-up"),
+    (KeyCode::PageDown, "page-down"),
+    (KeyCode::Insert, "insert"),
+    (KeyCode::Delete, "delete"),
+];
+
+fn format_key_code(code: KeyCode) -> String {
+    match code {
+        KeyCode::Char(c) => format!("char {:x}", c as u32),
+        KeyCode::F(n) => format!("f {}", n),
+        KeyCode::Other(n) => format!("other {}", n),
+        code => NAMED_KEY_CODES
+            .iter()
+            .find(|(k, _)| *k == code)
+            .map_or("other 0", |(_, name)| name)
+            .into(),
+    }
+}
+
+fn parse_event<'a>(words: &mut impl Iterator<Item = &'a str>) -> Option<Event> {
+    let event = match words.next()? {
+        "key" => Event::Key(parse_key(words)?),
+        "key-event" => {
+            let kind = match words.next()? {
+                "press" => KeyEventKind::Press,
+                "repeat" => KeyEventKind::Repeat,
+                "release" => KeyEventKind::Release,
+                _ => return None,
+            };
+            let modifiers = Modifiers::from_bits(words.next()?.parse().ok()?);
+            Event::KeyEvent(KeyEvent {
+                code: parse_key_code(words)?,
+                modifiers,
+                kind,
+            })
+        }
+        "mouse" => {
+            let action = match words.next()? {
+                "press" => MouseAction::Press(match words.next()? {
+                    "left" => MouseButton::Left,
+                    "right" => MouseButton::Right,
+                    "middle" => MouseButton::Middle,
+                    "wheel-up" => MouseButton::WheelUp,
+                    "wheel-down" => MouseButton::WheelDown,
+                    _ => return None,
+                }),
+                "release" => MouseAction::Release,
+                "hold" => MouseAction::Hold,
+                _ => return None,
+            };
+            let row = parse_num(words)?;
+            let col = parse_num(words)?;
+            Event::Mouse(Mouse { action, row, col })
+        }
+        "unsupported" => Event::Unsupported(parse_hex(words)?),
+        "clipboard" => Event::Clipboard(String::from_utf8(parse_hex(words)?).ok()?),
+        "resize" => Event::Resize(parse_num(words)?, parse_num(words)?),
+        "tick" => Event::Tick,
+        "suspend" => Event::Suspend,
+        "resume" => Event::Resume,
+        "focus-gained" => Event::FocusGained,
+        "focus-lost" => Event::FocusLost,
+        _ => return None,
+    };
+    Some(event)
+}
+
+fn parse_key<'a>(words: &mut impl Iterator<Item = &'a str>) -> Option<Key> {
+    let key = match words.next()? {
+        "f" => Key::F(words.next()?.parse().ok()?),
+        "char" => Key::Char(parse_char(words)?),
+        "alt" => Key::Alt(parse_char(words)?),
+        "ctrl" => Key::Ctrl(parse_char(words)?),
+        name => NAMED_KEYS.iter().find(|(_, n)| *n == name)?.0,
+    };
+    Some(key)
+}
+
+fn parse_key_code<'a>(words: &mut impl Iterator<Item = &'a str>) -> Option<KeyCode> {
+    let code = match words.next()? {
+        "char" => KeyCode::Char(parse_char(words)?),
+        "f" => KeyCode::F(words.next()?.parse().ok()?),
+        "other" => KeyCode::Other(words.next()?.parse().ok()?),
+        name => NAMED_KEY_CODES.iter().find(|(_, n)| *n == name)?.0,
+    };
+    Some(code)
+}
+
+fn parse_hex<'a>(words: &mut impl Iterator<Item = &'a str>) -> Option<Vec<u8>> {
+    let hex = words.next().unwrap_or("");
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+fn parse_char<'a>(words: &mut impl Iterator<Item = &'a str>) -> Option<char> {
+    char::from_u32(u32::from_str_radix(words.next()?, 16).ok()?)
+}
+
+fn parse_num<'a>(words: &mut impl Iterator<Item = &'a str>) -> Option<usize> {
+    words.next()?.parse().ok()
+}
+
+/// A backend that records the events read from the wrapped backend in an `EventLog`, with the
+/// time each one arrived, so a session can be replayed later with `ReplayBackend`.
+///
+/// Changes in the size of the terminal are recorded as `Event::Resize`. Events made by the app
+/// itself, like `Event::Tick`, aren't recorded, since it will make them again when replaying.
+pub struct RecordingBackend<B: Backend> {
+    inner: B,
+    log: EventLog,
+    start: Instant,
+    size: Option<(usize, usize)>,
+}
+
+impl<B: Backend> RecordingBackend<B> {
+    /// Start recording. Times in the log are from when the backend is created, by the clock of
+    /// the wrapped backend (see `Backend::now`).
+    pub fn new(inner: B) -> RecordingBackend<B> {
+        RecordingBackend {
+            size: inner.size().ok(),
+            start: inner.now(),
+            inner,
+            log: EventLog::new(),
+        }
+    }
+
+    /// The events recorded so far.
+    pub fn log(&self) -> &EventLog {
+        &self.log
+    }
+
+    /// Take the events recorded so far, e.g. to save them, leaving the log empty.
+    pub fn take_log(&mut self) -> EventLog {
+        std::mem::take(&mut self.log)
+    }
+
+    pub fn inner(&self) -> &B {
+        &self.inner
+    }
+
+    pub fn inner_mut(&mut self) -> &mut B {
+        &mut self.inner
+    }
+
+    /// Add an event from the wrapped backend to the log, after any change of size.
+    fn record(&mut self, event: Option<io::Result<Event>>) -> Option<io::Result<Event>> {
+        if let Ok(size) = self.inner.size() {
+            if self.size != Some(size) {
+                self.size = Some(size);
+                let time = self.elapsed();
+                self.log.push(time, Event::Resize(size.0, size.1));
+            }
+        }
+        if let Some(Ok(event)) = &event {
+            self.log.push(self.elapsed(), event.clone());
+        }
+        event
+    }
+
+    fn elapsed(&self) -> Duration {
+        self.inner.now().saturating_duration_since(self.start)
+    }
+}
+
+impl<B: Backend> Write for RecordingBackend<B> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.inner.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl<B: Backend> Backend for RecordingBackend<B> {
+    fn size(&self) -> io::Result<(usize, usize)> {
+        self.inner.size()
+    }
+
+    fn enable_raw_mode(&mut self) -> io::Result<()> {
+        self.inner.enable_raw_mode()
+    }
+
+    fn disable_raw_mode(&mut self) -> io::Result<()> {
+        self.inner.disable_raw_mode()
+    }
+
+    fn next_event(&mut self) -> Option<io::Result<Event>> {
+        let event = self.inner.next_event();
+        self.record(event)
+    }
+
+    fn wait_event(&mut self, timeout: Option<Duration>) -> Option<io::Result<Event>> {
+        let event = self.inner.wait_event(timeout);
+        self.record(event)
+    }
+
+    fn set_waker(&mut self, waker: Waker) {
+        self.inner.set_waker(waker)
+    }
+
+    fn take_job_control(&mut self) -> Option<JobControl> {
+        self.inner.take_job_control()
+    }
+
+    fn stop(&mut self) -> io::Result<()> {
+        self.inner.stop()
+    }
+
+    fn now(&self) -> Instant {
+        self.inner.now()
+    }
+
+    fn sleep(&mut self, duration: Duration) {
+        self.inner.sleep(duration)
+    }
+
+    fn can_resize(&self) -> bool {
+        self.inner.can_resize()
+    }
+}
+
+/// A backend that replays input from an `EventLog`, with the original timing, instead of
+/// reading it. Output goes to the wrapped backend.
+///
+/// `Event::Resize` events in the log change the size reported by the backend rather than
+/// being passed on directly, so the app sees them in the usual way. Anything else is passed
+/// on as-is.
+pub struct ReplayBackend<B: Backend> {
+    inner: B,
+    events: std::vec::IntoIter<(Duration, Event)>,
+    next: Option<(Duration, Event)>,
+    start: Instant,
+    speed: f64,
+    size: Option<(usize, usize)>,
+}
+
+impl<B: Backend> ReplayBackend<B> {
+    /// Replay the log at its original speed. Timing starts when the backend is created, and
+    /// goes by the clock of the wrapped backend (see `Backend::now`).
+    pub fn new(inner: B, log: EventLog) -> ReplayBackend<B> {
+        let mut events = log.events.into_iter();
+        ReplayBackend {
+            start: inner.now(),
+            inner,
+            next: events.next(),
+            events,
+            speed: 1.0,
+            size: None,
+        }
+    }
+
+    /// Replay faster (or slower) than the original, e.g. `2.0` for double speed.
+    ///
+    /// Use `f64::INFINITY` to replay all the events immediately.
+    pub fn speed(mut self, speed: f64) -> Self {
+        self.speed = speed;
+        self
+    }
+
+    /// Whether all the events in the log have been replayed.
+    pub fn is_finished(&self) -> bool {
+        self.next.is_none()
+    }
+
+    pub fn inner(&self) -> &B {
+        &self.inner
+    }
+
+    pub fn inner_mut(&mut self) -> &mut B {
+        &mut self.inner
+    }
+
+    /// How long until the next event is due, or `None` if there are no more.
+    fn time_until_next(&self) -> Option<Duration> {
+        let (time, _) = self.next.as_ref()?;
+        if self.speed.is_infinite() {
+            return Some(Duration::ZERO);
+        }
+        let due = time.div_f64(self.speed);
+        let elapsed = self.inner.now().saturating_duration_since(self.start);
+        Some(due.saturating_sub(elapsed))
+    }
+}
+
+impl ReplayBackend<TestBackend> {
+    /// Replay a log to a `TestBackend` of the given size, with every event available straight
+    /// away, so the app sees the same events in the same order every time, e.g. to test a
+    /// whole interaction.
+    ///
+    /// The app sees a resize before any of the events after it, but only on the next call to
+    /// `App::events`, so keep calling it until `is_finished` returns `true`.
+    pub fn test(log: EventLog, cols: usize, rows: usize) -> ReplayBackend<TestBackend> {
+        ReplayBackend::new(TestBackend::new(cols, rows), log).speed(f64::INFINITY)
+    }
+}
+
+impl<B: Backend> Write for ReplayBackend<B> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.inner.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl<B: Backend> Backend for ReplayBackend<B> {
+    fn size(&self) -> io::Result<(usize, usize)> {
+        match self.size {
+            Some(size) => Ok(size),
+            None => self.inner.size(),
+        }
+    }
+
+    fn enable_raw_mode(&mut self) -> io::Result<()> {
+        self.inner.enable_raw_mode()
+    }
+
+    fn disable_raw_mode(&mut self) -> io::Result<()> {
+        self.inner.disable_raw_mode()
+    }
+
+    fn next_event(&mut self) -> Option<io::Result<Event>> {
+        if self.time_until_next()? > Duration::ZERO {
+            return None;
+        }
+        let (_, event) = self.next.take()?;
+        self.next = self.events.next();
+        match event {
+            Event::Resize(cols, rows) => {
+                // Stop here, so the app notices the new size before any later events.
+                self.size = Some((cols, rows));
+                None
+            }
+            event => Some(Ok(event)),
+        }
+    }
+
+    fn wait_event(&mut self, timeout: Option<Duration>) -> Option<io::Result<Event>> {
+        let wait = match (self.time_until_next(), timeout) {
+            (Some(until), Some(timeout)) => until.min(timeout),
+            (Some(until), None) => until,
+            (None, Some(timeout)) => timeout,
+            (None, None) => return None,
+        };
+        self.inner.sleep(wait);
+        self.next_event()
+    }
+
+    fn set_waker(&mut self, waker: Waker) {
+        // We have no thread of our own, so make sure the task is woken for the next event.
+        if let Some(until) = self.time_until_next() {
+            thread::spawn(move || {
+                thread::sleep(until);
+                waker.wake();
+            });
+        }
+    }
+
+    fn take_job_control(&mut self) -> Option<JobControl> {
+        self.inner.take_job_control()
+    }
+
+    fn stop(&mut self) -> io::Result<()> {
+        self.inner.stop()
+    }
+
+    fn now(&self) -> Instant {
+        self.inner.now()
+    }
+
+    fn sleep(&mut self, duration: Duration) {
+        self.inner.sleep(duration)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::SimBackend;
+    use crate::App;
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    /// Read events until the backend has nothing more to give, with the time each arrived.
+    fn run<B: Backend>(app: &mut App<B>, finished: impl Fn(&B) -> bool) -> Vec<(Duration, Event)> {
+        let start = app.backend().now();
+        let mut events = Vec::new();
+        while !finished(app.backend()) {
+            if let Some(evt) = app.poll_event(ms(1000)).unwrap() {
+                events.push((app.backend().now() - start, evt));
+            }
+        }
+        events
+    }
+
+    #[test]
+    fn recording_a_simulated_session_uses_simulated_time() {
+        let mut sim = SimBackend::new(20, 5);
+        sim.push_event_after(ms(100), Event::Key(Key::Char('a')));
+        sim.resize_after(ms(250), 30, 10);
+        sim.push_event_after(ms(300), Event::Key(Key::Char('b')));
+        let mut app = App::builder()
+            .build_with_backend(RecordingBackend::new(sim))
+            .unwrap();
+        run(&mut app, |backend| backend.inner().is_finished());
+        let log = app.backend_mut().take_log();
+        assert_eq!(
+            log.events(),
+            [
+                (ms(100), Event::Key(Key::Char('a'))),
+                (ms(250), Event::Resize(30, 10)),
+                (ms(300), Event::Key(Key::Char('b'))),
+            ]
+        );
+    }
+
+    #[test]
+    fn replaying_goes_by_the_backend_clock() {
+        let mut log = EventLog::new();
+        log.push(ms(100), Event::Key(Key::Char('a')));
+        log.push(ms(250), Event::Resize(30, 10));
+        log.push(ms(300), Event::Key(Key::Ctrl('c')));
+        let backend = ReplayBackend::new(SimBackend::new(20, 5), log);
+        let mut app = App::builder().build_with_backend(backend).unwrap();
+        let events = run(&mut app, |backend| backend.is_finished());
+        assert_eq!(
+            events,
+            [
+                (ms(100), Event::Key(Key::Char('a'))),
+                (ms(250), Event::Resize(30, 10)),
+                (ms(300), Event::Key(Key::Ctrl('c'))),
+            ]
+        );
+    }
+
+    #[test]
+    fn logs_round_trip_through_text() {
+        let mut log = EventLog::new();
+        log.push(ms(0), Event::Key(Key::Char(' ')));
+        log.push(ms(5), Event::Key(Key::Alt('x')));
+        log.push(ms(10), Event::Key(Key::F(5)));
+        log.push(ms(20), Event::Resize(100, 40));
+        log.push(ms(30), Event::Clipboard("two words\nand a line".into()));
+        log.push(
+            ms(35),
+            Event::Mouse(crate::Mouse {
+                action: crate::MouseAction::Press(MouseButton::WheelUp),
+                row: 3,
+                col: 7,
+            }),
+        );
+        log.push(ms(36), Event::Unsupported(vec![0x1b, b'[', b'Z']));
+        log.push(ms(40), Event::FocusLost);
+        let text = log.to_string();
+        assert_eq!(text.parse::<EventLog>().unwrap(), log);
+    }
+
+    #[test]
+    fn invalid_lines_are_reported() {
+        let err = "10 key a\nnonsense".parse::<EventLog>().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -1,123 +1,767 @@
+use crate::color::ColorSupport;
+use crate::glyph::Glyph;
+use crate::link::Link;
+use crate::rect::Rect;
+use crate::stats::RenderStats;
+use crate::sync;
+use std::cmp::Ordering;
+use std::collections::hash_map::DefaultHasher;
+#[cfg(feature = "serde")]
+use std::convert::TryFrom;
+use std::fmt;
+use std::hash::{Hash, Hasher};
 use std::io::{self, Write};
 use std::mem;
+use std::ops::{BitAnd, BitOr, BitOrAssign, Index, IndexMut};
+use std::time::Instant;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// How many more rows scrolling must save redrawing before we scroll (see
+/// `AppBuilder::scroll_detection`).
+const SCROLL_THRESHOLD: usize = 2;
+
+/// The number of changed blank cells at the end of a row above which it is cheaper to erase to
+/// the end of the line than to write them.
+const ERASE_THRESHOLD: usize = 3;
+
+/// The default percentage of changed cells above which the whole screen is redrawn (see
+/// `AppBuilder::redraw_threshold`).
+pub(crate) const DEFAULT_REDRAW_THRESHOLD: u8 = 50;
 
 #[derive(Debug)]
 pub(crate) struct Screen {
     pub(crate) previous: Frame,
     pub(crate) next: Frame,
+    /// Colors are downgraded to what the terminal supports when they are written.
+    pub(crate) color_support: ColorSupport,
+    /// The attributes the terminal can display. Others are left out of the output.
+    pub(crate) attributes: Attributes,
+    /// Whether the terminal contents are unknown, so the next render must redraw everything.
+    invalid: bool,
+    /// Whether to wrap each render in a synchronized update, so the terminal doesn't show a
+    /// partly drawn frame.
+    pub(crate) synchronized_output: bool,
+    /// Whether to look for content that has scrolled, and scroll the terminal rather than
+    /// redrawing it.
+    pub(crate) scroll_detection: bool,
+    /// Whether we draw in some of the rows of the terminal, rather than the whole screen (see
+    /// `AppBuilder::inline`).
+    pub(crate) inline: bool,
+    /// The row of the terminal the top of the frame is drawn at.
+    pub(crate) origin: usize,
+    /// Whether to print frames as plain text, rather than drawing them (see
+    /// `AppBuilder::plain`).
+    pub(crate) plain: bool,
+    /// The percentage of cells that must change for a render to redraw everything rather than
+    /// only the changes.
+    pub(crate) redraw_threshold: u8,
+    /// Where the cursor should be shown after the next frame is drawn (see `Draw::set_cursor`).
+    pub(crate) cursor: Option<(usize, usize)>,
+    /// Whether the cursor is shown at all (see `App::show_cursor`).
+    pub(crate) cursor_visible: bool,
+    /// Where the terminal is showing the cursor, or `None` if it is hidden.
+    shown_cursor: Option<(usize, usize)>,
+    /// Measurements of the last render.
+    pub(crate) stats: RenderStats,
+    /// Images to draw over the frame (see `Draw::image`).
+    #[cfg(feature = "images")]
+    pub(crate) images: crate::images::Images,
+    /// Reused between renders to collect the output.
+    output: Vec<u8>,
 }
 
 impl Screen {
-    pub(crate) fn new(rows: usize, cols: usize) -> Self {
+    pub(crate) fn new(rows: usize, cols: usize, color_support: ColorSupport) -> Self {
         Screen {
             previous: Frame::new(rows, cols),
             next: Frame::new(rows, cols),
+            color_support,
+            attributes: Attributes::from_bits(!0),
+            invalid: false,
+            synchronized_output: false,
+            scroll_detection: false,
+            inline: false,
+            origin: 0,
+            plain: false,
+            redraw_threshold: DEFAULT_REDRAW_THRESHOLD,
+            cursor: None,
+            cursor_visible: false,
+            shown_cursor: None,
+            stats: RenderStats::default(),
+            #[cfg(feature = "images")]
+            images: Default::default(),
+            output: Vec::with_capacity(rows * cols * 4),
         }
     }
     pub(crate) fn prepare_next_frame(&mut self, rows: usize, cols: usize) {
         mem::swap(&mut self.next, &mut self.previous);
         self.next.reset(rows, cols);
+        // Keep any settings changed while drawing the last frame.
+        self.next.control_policy = self.previous.control_policy;
+        self.next.clip_writes = self.previous.clip_writes;
+        self.cursor = None;
+        #[cfg(feature = "images")]
+        self.images.clear();
+    }
+
+    pub(crate) fn set_control_policy(&mut self, policy: ControlPolicy) {
+        self.previous.control_policy = policy;
+        self.next.control_policy = policy;
+    }
+
+    pub(crate) fn set_clip_writes(&mut self, clip_writes: bool) {
+        self.previous.clip_writes = clip_writes;
+        self.next.clip_writes = clip_writes;
+    }
+
+    /// Whether a row is (almost certainly) the same in the previous and next frames, going by
+    /// the row hashes.
+    fn row_unchanged(&self, row: usize) -> bool {
+        let hash = self.next.row_state.hash(row);
+        hash.is_some() && hash == self.previous.row_state.hash(row)
+    }
+
+    /// Look for a shift of the rows of the previous frame that would match more rows of the next
+    /// frame than are already the same, returning how many rows the content has moved up (or
+    /// down if negative).
+    fn detect_scroll(&self) -> Option<isize> {
+        let rows = self.next.rows;
+        let matches = |shift: isize| {
+            (0..rows)
+                .filter(|&row| {
+                    let prev_row = row as isize + shift;
+                    let hash = self.next.row_state.hash(row);
+                    // Blank rows are cheap to draw anyway, so they don't count.
+                    prev_row >= 0
+                        && hash != Some(self.next.row_state.blank)
+                        && hash.is_some()
+                        && hash == self.previous.row_state.hash(prev_row as usize)
+                })
+                .count()
+        };
+        let unchanged = matches(0);
+        let (shift, best) = (1..rows as isize)
+            .flat_map(|n| [n, -n])
+            .map(|shift| (shift, matches(shift)))
+            .max_by_key(|&(shift, count)| (count, -shift.abs()))?;
+        // Only scroll if it saves redrawing a good number of rows.
+        if best > unchanged + SCROLL_THRESHOLD {
+            Some(shift)
+        } else {
+            None
+        }
+    }
+
+    /// The number of cells that differ between the previous and next frames.
+    fn changed_cells(&self) -> usize {
+        (0..self.next.rows)
+            .filter(|&row| !self.row_unchanged(row))
+            .map(|row| {
+                let prev = self.previous.row(row);
+                self.next
+                    .row(row)
+                    .iter()
+                    .zip(prev)
+                    .filter(|(next, prev)| next != prev)
+                    .count()
+            })
+            .sum()
+    }
+
+    /// Whether so much of the frame has changed that redrawing it all is cheaper than moving
+    /// the cursor to each change.
+    fn mostly_changed(&self, changed: usize) -> bool {
+        let cells = self.next.rows * self.next.cols;
+        changed * 100 >= cells * usize::from(self.redraw_threshold)
+    }
+
+    /// Scroll the terminal, and the previous frame to match, so the diff only has to draw the
+    /// rows that were scrolled in.
+    fn scroll(&mut self, shift: isize, writer: &mut impl Write) -> io::Result<()> {
+        // New rows are filled with the current background, so reset it first.
+        write!(writer, "{}", termion::style::Reset)?;
+        if shift > 0 {
+            write!(writer, "{}", termion::scroll::Up(shift as u16))?;
+        } else {
+            write!(writer, "{}", termion::scroll::Down(-shift as u16))?;
+        }
+        self.previous.shift_rows(shift);
+        Ok(())
+    }
+
+    /// Move the frame to start at a different row of the terminal.
+    pub(crate) fn set_origin(&mut self, origin: usize) {
+        if origin != self.origin {
+            self.origin = origin;
+            self.invalid = true;
+        }
+    }
+
+    /// Force the next render to redraw everything.
+    ///
+    /// This is used after setting up the terminal, which hides the cursor.
+    pub(crate) fn invalidate(&mut self) {
+        self.invalid = true;
+        self.shown_cursor = None;
+    }
+
+    /// Where the cursor should be shown, if anywhere.
+    fn wanted_cursor(&self) -> Option<(usize, usize)> {
+        let (row, col) = self.cursor.filter(|_| self.cursor_visible)?;
+        if row < self.next.rows && col < self.next.cols {
+            Some((row, col))
+        } else {
+            None
+        }
+    }
+
+    /// Show or hide the cursor if it isn't already as it should be.
+    pub(crate) fn update_cursor(&mut self, writer: &mut impl Write) -> io::Result<()> {
+        let wanted = self.wanted_cursor();
+        if wanted == self.shown_cursor || self.plain {
+            return Ok(());
+        }
+        match wanted {
+            Some((row, col)) => {
+                write!(writer, "{}", self.goto(row, col))?;
+                if self.shown_cursor.is_none() {
+                    write!(writer, "{}", termion::cursor::Show)?;
+                }
+            }
+            None => write!(writer, "{}", termion::cursor::Hide)?,
+        }
+        self.shown_cursor = wanted;
+        Ok(())
     }
 
     /// Render the frame to the terminal
-    pub(crate) fn render(&self, writer: &mut impl Write) -> io::Result<()> {
-        if self.next.dims() != self.previous.dims() {
+    ///
+    /// The output is collected in a buffer and written in one go, rather than as lots of small
+    /// writes.
+    pub(crate) fn render(&mut self, writer: &mut impl Write) -> io::Result<()> {
+        let start = Instant::now();
+        let mut output = mem::take(&mut self.output);
+        output.clear();
+        let result = self
+            .render_to(&mut output)
+            .and_then(|()| writer.write_all(&output));
+        self.stats.bytes_written = output.len();
+        self.stats.duration = start.elapsed();
+        // Keep the buffer around so it doesn't need to grow again next time.
+        self.output = output;
+        result
+    }
+
+    /// Work out the output for a render, updating the stats.
+    fn render_to(&mut self, output: &mut Vec<u8>) -> io::Result<()> {
+        self.stats = RenderStats::default();
+        let full_redraw = mem::take(&mut self.invalid) || self.next.dims() != self.previous.dims();
+        if self.plain {
+            // Print each new frame after the last, with a blank line in between.
+            if full_redraw || !(0..self.next.rows).all(|row| self.row_unchanged(row)) {
+                self.next.write_text(output)?;
+                writeln!(output)?;
+            }
+            return Ok(());
+        }
+        // Only the cursor needs updating if the frame hasn't changed.
+        if !full_redraw
+            && (0..self.next.rows).all(|row| self.row_unchanged(row))
+            && !self.images_changed()
+        {
+            return self.update_cursor(output);
+        }
+        if self.synchronized_output {
+            output.extend_from_slice(sync::BEGIN.as_bytes());
+        }
+        // Hide the cursor while drawing, so it isn't seen jumping around.
+        if self.shown_cursor.take().is_some() {
+            write!(output, "{}", termion::cursor::Hide)?;
+        }
+        if full_redraw {
             // We need to redraw
-            self.redraw(writer)
+            self.redraw(output)?;
+            self.stats.cells_changed = self.next.rows * self.next.cols;
+            self.stats.full_redraw = true;
         } else {
-            // We can do incremental update
-            self.redraw_diff(writer)
+            let mut scrolled = false;
+            // Scrolling would move the whole terminal, not only the rows we draw in, and would
+            // move images along with the cells.
+            if self.scroll_detection && !self.inline && !self.has_images() {
+                if let Some(shift) = self.detect_scroll() {
+                    self.scroll(shift, output)?;
+                    scrolled = true;
+                }
+            }
+            let changed = self.changed_cells();
+            self.stats.cells_changed = changed;
+            if !scrolled && self.mostly_changed(changed) {
+                self.redraw(output)?;
+                self.stats.full_redraw = true;
+            } else {
+                // We can do incremental update
+                self.redraw_diff(output)?;
+            }
         }
+        self.draw_images(output, self.stats.full_redraw)?;
+        self.update_cursor(output)?;
+        if self.synchronized_output {
+            output.extend_from_slice(sync::END.as_bytes());
+        }
+        Ok(())
     }
 
     pub(crate) fn redraw(&self, writer: &mut impl Write) -> io::Result<()> {
-        use termion::cursor::Goto;
-        write!(writer, "{}", termion::clear::All)?;
-        assert!(
-            self.next.rows < u16::max_value().into(),
-            "rows must fit in u16"
-        );
+        // Reset first, so the screen is cleared to the default background.
+        write!(writer, "{}", termion::style::Reset)?;
+        if self.inline {
+            for row in 0..self.next.rows {
+                write!(
+                    writer,
+                    "{}{}",
+                    self.goto(row, 0),
+                    termion::clear::CurrentLine
+                )?;
+            }
+        } else {
+            write!(writer, "{}", termion::clear::All)?;
+        }
+        assert!(self.next.rows < u16::MAX.into(), "rows must fit in u16");
+        let mut prev = Style::default();
+        let mut cursor = None;
         for row in 0..self.next.rows {
-            for col in 0..self.next.cols {
-                write!(writer, "{}", Goto((col as u16) + 1, (row as u16) + 1))?; // checked col then row
+            // The end of the row is already blank from clearing the screen.
+            let end = self.next.blank_from(row);
+            let mut col = 0;
+            while col < end {
                 let current = self.next.get(row, col);
+                // So are other long runs of blank cells, which are cheaper to move past than to
+                // write.
+                let blanks = self.next.blank_run(row, col, end);
+                if blanks > ERASE_THRESHOLD {
+                    col += blanks;
+                    continue;
+                }
+                // The terminal has already drawn this cell as part of the glyph to its left.
+                if current.is_continuation() {
+                    col += 1;
+                    continue;
+                }
+                if cursor != Some((row, col)) {
+                    write!(writer, "{}", self.goto(row, col))?;
+                }
                 // Change color if we need to.
-                if let Some((prev_row, prev_col)) = self.next.prev_row_col(row, col) {
-                    let prev = self.next.get(prev_row, prev_col);
-                    if prev.color_fg != current.color_fg {
-                        current.write_fg(writer)?;
-                    }
-                    if prev.color_bg != current.color_bg {
-                        current.write_bg(writer)?;
-                    }
-                } else {
-                    current.write_fg(writer)?;
-                    current.write_bg(writer)?;
+                if prev.color_fg != current.color_fg {
+                    self.write_fg(current.color_fg, writer)?;
                 }
+                if prev.color_bg != current.color_bg {
+                    self.write_bg(current.color_bg, writer)?;
+                }
+                let attrs = current.attrs & self.attributes;
+                if prev.attrs != attrs {
+                    attrs.write_change(prev.attrs, writer)?;
+                }
+                if prev.link != current.link {
+                    Link::write_change(prev.link.as_ref(), current.link.as_ref(), writer)?;
+                }
+                prev = Style {
+                    attrs,
+                    ..current.style()
+                };
                 write!(writer, "{}", current.glyph)?;
+                cursor = cursor_after(row, col, &current);
+                col += 1;
             }
         }
-        Ok(())
+        // Don't leave a link open for anything written after the frame.
+        Link::write_change(prev.link.as_ref(), None, writer)
     }
 
     pub(crate) fn redraw_diff(&self, writer: &mut impl Write) -> io::Result<()> {
-        use termion::cursor::Goto;
-        assert!(
-            self.next.rows < u16::max_value().into(),
-            "rows must fit in u16"
-        );
+        assert!(self.next.rows < u16::MAX.into(), "rows must fit in u16");
         let mut prev_fg = Color::default();
         let mut prev_bg = Color::default();
-        prev_fg.write_fg(writer)?;
-        prev_bg.write_bg(writer)?;
+        let mut prev_attrs = Attributes::empty();
+        let mut prev_link = None;
+        write!(writer, "{}", termion::style::Reset)?;
+        self.write_fg(prev_fg, writer)?;
+        self.write_bg(prev_bg, writer)?;
+        let mut cursor = None;
         for row in 0..self.next.rows {
-            for col in 0..self.next.cols {
-                let next = self.next.get(row, col);
-                let prev = self.previous.get(row, col);
-                if next == prev {
+            // Rows that haven't been written to in either frame are blank in both.
+            if !self.next.row_state.is_dirty(row) && !self.previous.row_state.is_dirty(row) {
+                continue;
+            }
+            // Rows with the same hash (almost certainly) haven't changed.
+            if self.row_unchanged(row) {
+                continue;
+            }
+            // If a lot of the blank cells at the end of the row have changed, erase them all in
+            // one go rather than writing each one.
+            let blank_from = self.next.blank_from(row);
+            let changed_blanks = (blank_from..self.next.cols)
+                .filter(|&col| self.previous.get(row, col) != Char::default())
+                .count();
+            // Erasing would also erase any images over the end of the row.
+            let end = if changed_blanks > ERASE_THRESHOLD
+                && !(blank_from..self.next.cols).any(|col| self.hidden(row, col))
+            {
+                blank_from
+            } else {
+                self.next.cols
+            };
+            for col in 0..end {
+                let next = &self.next[(row, col)];
+                let prev = &self.previous[(row, col)];
+                if next == prev || next.is_continuation() || self.hidden(row, col) {
                     continue;
                 }
-                write!(writer, "{}", Goto((col as u16) + 1, (row as u16) + 1))?;
+                if cursor != Some((row, col)) {
+                    write!(writer, "{}", self.goto(row, col))?;
+                }
                 // Change color if we need to.
                 if next.color_fg != prev_fg {
-                    next.write_fg(writer)?;
+                    self.write_fg(next.color_fg, writer)?;
                     prev_fg = next.color_fg
                 }
                 if next.color_bg != prev_bg {
-                    next.write_bg(writer)?;
+                    self.write_bg(next.color_bg, writer)?;
                     prev_bg = next.color_bg
                 }
+                let attrs = next.attrs & self.attributes;
+                if attrs != prev_attrs {
+                    attrs.write_change(prev_attrs, writer)?;
+                    prev_attrs = attrs
+                }
+                if next.link.as_ref() != prev_link {
+                    Link::write_change(prev_link, next.link.as_ref(), writer)?;
+                    prev_link = next.link.as_ref()
+                }
                 write!(writer, "{}", next.glyph)?;
+                cursor = cursor_after(row, col, next);
+            }
+            if end < self.next.cols {
+                if cursor != Some((row, end)) {
+                    write!(writer, "{}", self.goto(row, end))?;
+                }
+                // Erasing fills with the current background, and blank cells have default style.
+                if prev_bg != Color::default() {
+                    self.write_bg(Color::default(), writer)?;
+                    prev_bg = Color::default();
+                }
+                if !prev_attrs.is_empty() {
+                    Attributes::empty().write_change(prev_attrs, writer)?;
+                    prev_attrs = Attributes::empty();
+                }
+                if prev_link.is_some() {
+                    Link::write_change(prev_link, None, writer)?;
+                    prev_link = None;
+                }
+                write!(writer, "{}", termion::clear::UntilNewline)?;
             }
         }
+        Link::write_change(prev_link, None, writer)
+    }
+
+    /// Move the cursor to a cell of the frame.
+    pub(crate) fn goto(&self, row: usize, col: usize) -> termion::cursor::Goto {
+        termion::cursor::Goto(col as u16 + 1, (self.origin + row) as u16 + 1)
+    }
+
+    pub(crate) fn write_fg(&self, color: Color, writer: &mut impl Write) -> io::Result<()> {
+        if self.color_support == ColorSupport::NoColor {
+            return Ok(());
+        }
+        color.downgrade(self.color_support).write_fg(writer)
+    }
+
+    pub(crate) fn write_bg(&self, color: Color, writer: &mut impl Write) -> io::Result<()> {
+        if self.color_support == ColorSupport::NoColor {
+            return Ok(());
+        }
+        color.downgrade(self.color_support).write_bg(writer)
+    }
+}
+
+/// Without the `images` feature, there are never any images to draw.
+#[cfg(not(feature = "images"))]
+impl Screen {
+    fn images_changed(&self) -> bool {
+        false
+    }
+
+    fn has_images(&self) -> bool {
+        false
+    }
+
+    fn hidden(&self, _row: usize, _col: usize) -> bool {
+        false
+    }
+
+    fn draw_images(&mut self, _output: &mut Vec<u8>, _cleared: bool) -> io::Result<()> {
         Ok(())
     }
 }
 
-#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+/// Where the terminal's cursor will be after writing a cell, if we can be sure.
+///
+/// Terminals don't always agree with us about the width of glyphs made of several characters
+/// (e.g. emoji sequences), so we don't guess where the cursor ends up after those.
+pub(crate) fn cursor_after(row: usize, col: usize, ch: &Char) -> Option<(usize, usize)> {
+    if ch.glyph.as_str().chars().nth(1).is_some() {
+        return None;
+    }
+    Some((row, col + ch.width()))
+}
+
+/// A grid of cells to draw.
+///
+/// Frames are compared and hashed by their size and cells only, not by settings like the
+/// control policy.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "FrameData"))]
 pub struct Frame {
     rows: usize,
     cols: usize,
     buffer: Vec<Char>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    control_policy: ControlPolicy,
+    #[cfg_attr(feature = "serde", serde(default))]
+    clip_writes: bool,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    row_state: RowState,
+}
+
+/// A frame as it is deserialized, before it has been checked.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct FrameData {
+    rows: usize,
+    cols: usize,
+    buffer: Vec<Char>,
+    #[serde(default)]
+    control_policy: ControlPolicy,
+    #[serde(default)]
+    clip_writes: bool,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<FrameData> for Frame {
+    type Error = String;
+
+    fn try_from(data: FrameData) -> Result<Frame, String> {
+        let FrameData {
+            rows,
+            cols,
+            buffer,
+            control_policy,
+            clip_writes,
+        } = data;
+        if rows.checked_mul(cols) != Some(buffer.len()) {
+            return Err(format!(
+                "a {}x{} frame can't have {} cells",
+                cols,
+                rows,
+                buffer.len()
+            ));
+        }
+        let mut row_state = RowState::new(rows, cols);
+        for (idx, ch) in buffer.iter().enumerate() {
+            row_state.update(idx / cols, idx % cols, &Char::default(), ch);
+        }
+        Ok(Frame {
+            rows,
+            cols,
+            buffer,
+            control_policy,
+            clip_writes,
+            row_state,
+        })
+    }
+}
+
+/// Which rows of a frame have been written to since it was reset, and a hash of the contents
+/// of each row, so rendering can skip rows that haven't changed.
+#[derive(Debug, Clone, Default)]
+struct RowState {
+    dirty: Vec<bool>,
+    /// The XOR of `cell_hash` for every cell in the row, so it can be updated as cells change,
+    /// or `None` if the row may have been changed without us seeing.
+    hashes: Vec<Option<u64>>,
+    /// The hash of a blank row.
+    blank: u64,
+}
+
+impl RowState {
+    fn new(rows: usize, cols: usize) -> RowState {
+        let mut state = RowState::default();
+        state.reset(rows, cols);
+        state
+    }
+
+    fn reset(&mut self, rows: usize, cols: usize) {
+        self.blank = (0..cols).fold(0, |hash, col| hash ^ cell_hash(col, &Char::default()));
+        self.dirty.clear();
+        self.dirty.resize(rows, false);
+        self.hashes.clear();
+        self.hashes.resize(rows, Some(self.blank));
+    }
+
+    /// Move rows up by `shift` (or down if negative), leaving blank rows behind.
+    fn shift(&mut self, shift: isize) {
+        let n = shift.unsigned_abs().min(self.dirty.len());
+        if shift > 0 {
+            self.dirty.rotate_left(n);
+            self.hashes.rotate_left(n);
+        } else {
+            self.dirty.rotate_right(n);
+            self.hashes.rotate_right(n);
+        }
+        let exposed = if shift > 0 {
+            self.dirty.len() - n..self.dirty.len()
+        } else {
+            0..n
+        };
+        for row in exposed {
+            self.dirty[row] = false;
+            self.hashes[row] = Some(self.blank);
+        }
+    }
+
+    /// Record that a cell has changed from `old` to `new`.
+    fn update(&mut self, row: usize, col: usize, old: &Char, new: &Char) {
+        if let Some(dirty) = self.dirty.get_mut(row) {
+            *dirty = true;
+        }
+        if old != new {
+            if let Some(Some(hash)) = self.hashes.get_mut(row) {
+                *hash ^= cell_hash(col, old) ^ cell_hash(col, new);
+            }
+        }
+    }
+
+    /// Rows we don't know about count as dirty.
+    fn is_dirty(&self, row: usize) -> bool {
+        self.dirty.get(row).copied().unwrap_or(true)
+    }
+
+    fn hash(&self, row: usize) -> Option<u64> {
+        self.hashes.get(row).copied().flatten()
+    }
+
+    /// Record that a row may have been changed in some unknown way.
+    fn invalidate(&mut self, row: usize) {
+        if let Some(dirty) = self.dirty.get_mut(row) {
+            *dirty = true;
+        }
+        if let Some(hash) = self.hashes.get_mut(row) {
+            *hash = None;
+        }
+    }
+}
+
+fn cell_hash(col: usize, ch: &Char) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    (col, ch).hash(&mut hasher);
+    hasher.finish()
+}
+
+impl PartialEq for Frame {
+    fn eq(&self, other: &Frame) -> bool {
+        self.dims() == other.dims() && self.buffer == other.buffer
+    }
+}
+
+impl Eq for Frame {}
+
+impl PartialOrd for Frame {
+    fn partial_cmp(&self, other: &Frame) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Frame {
+    fn cmp(&self, other: &Frame) -> Ordering {
+        self.dims()
+            .cmp(&other.dims())
+            .then_with(|| self.buffer.cmp(&other.buffer))
+    }
+}
+
+impl Hash for Frame {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.dims().hash(state);
+        self.buffer.hash(state);
+    }
+}
+
+/// What to do with control characters (such as `'\t'` or `'\n'`) written to a frame.
+///
+/// Control characters would move the terminal's cursor if they were output, messing up the
+/// rest of the frame, so they are never stored as they are.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ControlPolicy {
+    /// Replace each control character with a space.
+    #[default]
+    Replace,
+    /// Expand tabs with spaces up to the next multiple of the given number of columns (counted
+    /// from the left of the frame), and replace other control characters with a space.
+    ///
+    /// A tab written with `Frame::set` takes up a single cell.
+    ExpandTabs(usize),
+    /// Drop control characters, leaving the cell untouched.
+    Reject,
+}
+
+/// The error returned when trying to write outside a frame.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct OutOfBounds {
+    pub row: usize,
+    pub col: usize,
+}
+
+impl fmt::Display for OutOfBounds {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "cell ({}, {}) is outside the frame", self.row, self.col)
+    }
+}
+
+impl std::error::Error for OutOfBounds {}
+
+/// A cell that differs between two frames (see `Frame::diff`).
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CellChange {
+    pub row: usize,
+    pub col: usize,
+    pub old: Char,
+    pub new: Char,
 }
 
 impl Frame {
-    fn new(rows: usize, cols: usize) -> Frame {
+    /// Create a blank frame of the given size.
+    pub fn new(rows: usize, cols: usize) -> Frame {
         Frame {
             rows,
             cols,
             buffer: vec![Default::default(); rows * cols],
+            control_policy: ControlPolicy::default(),
+            clip_writes: false,
+            row_state: RowState::new(rows, cols),
         }
     }
 
     /// Reset the size and clear the contents of the screen
-    fn reset(&mut self, rows: usize, cols: usize) {
-        self.buffer.clear();
+    ///
+    /// The buffer is reused, so this doesn't allocate unless the frame has grown.
+    pub(crate) fn reset(&mut self, rows: usize, cols: usize) {
         self.rows = rows;
         self.cols = cols;
-        for _ in 0..(rows * cols) {
-            self.buffer.push(Default::default());
-        }
+        self.buffer.fill(Char::default());
+        self.buffer.resize(rows * cols, Char::default());
+        self.row_state.reset(rows, cols);
     }
 
     /// The number of rows on the screen.
@@ -130,34 +774,414 @@ impl Frame {
         self.cols
     }
 
+    /// The whole frame as a rectangle.
+    pub fn area(&self) -> Rect {
+        Rect::new(0, 0, self.rows, self.cols)
+    }
+
+    /// How control characters written to the frame are handled.
+    pub fn control_policy(&self) -> ControlPolicy {
+        self.control_policy
+    }
+
+    /// Change how control characters written to the frame are handled.
+    ///
+    /// When drawing an `App`, the policy is kept from one frame to the next.
+    pub fn set_control_policy(&mut self, policy: ControlPolicy) {
+        self.control_policy = policy;
+    }
+
+    /// Whether writes outside the frame are ignored, rather than panicking.
+    pub fn clip_writes(&self) -> bool {
+        self.clip_writes
+    }
+
+    /// Choose whether writes outside the frame (with `set` and `set_str`) are ignored, rather
+    /// than panicking. This is useful when the terminal might shrink while drawing.
+    ///
+    /// When drawing an `App`, this is kept from one frame to the next.
+    pub fn set_clip_writes(&mut self, clip_writes: bool) {
+        self.clip_writes = clip_writes;
+    }
+
+    /// Apply the control policy to a cell, returning `None` if it should not be written.
+    fn sanitize(&self, ch: Char) -> Option<Char> {
+        if !ch.glyph.is_control() {
+            return Some(ch);
+        }
+        match self.control_policy {
+            ControlPolicy::Replace | ControlPolicy::ExpandTabs(_) => Some(Char {
+                glyph: Glyph::from(' '),
+                ..ch
+            }),
+            ControlPolicy::Reject => None,
+        }
+    }
+
     /// Private shorthand for comparing dims.
     fn dims(&self) -> (usize, usize) {
         (self.rows, self.cols)
     }
 
-    /// Will panic if the row or column is out of bounds.
+    /// Will panic if the row or column is out of bounds, unless the frame clips writes (see
+    /// `Frame::set_clip_writes`).
+    ///
+    /// A wide glyph (e.g. CJK or emoji) also takes up the cell to its right, which is marked as a
+    /// continuation. A wide glyph in the last column is replaced with a space. Control characters
+    /// are handled according to the frame's `ControlPolicy`.
     pub fn set(&mut self, row: usize, col: usize, ch: Char) {
+        if self.clip_writes {
+            return self.set_clipped(row, col, ch);
+        }
         self.check_dims(row, col);
-        self.buffer[row * self.cols + col] = ch;
+        self.put(row, col, ch);
+    }
+
+    /// Like `set`, but returns an error rather than panicking if the row or column is out of
+    /// bounds.
+    pub fn try_set(&mut self, row: usize, col: usize, ch: Char) -> Result<(), OutOfBounds> {
+        if row >= self.rows || col >= self.cols {
+            return Err(OutOfBounds { row, col });
+        }
+        self.put(row, col, ch);
+        Ok(())
+    }
+
+    /// Like `set`, but does nothing if the row or column is out of bounds.
+    pub(crate) fn set_clipped(&mut self, row: usize, col: usize, ch: Char) {
+        if row < self.rows && col < self.cols {
+            self.put(row, col, ch);
+        }
+    }
+
+    /// Set a cell that is known to be in bounds, returning the number of cells used.
+    fn put(&mut self, row: usize, col: usize, ch: Char) -> usize {
+        let ch = match self.sanitize(ch) {
+            Some(ch) => ch,
+            None => return 0,
+        };
+        self.split_wide(row, col);
+        if ch.width() < 2 {
+            self.write_cell(row, col, ch);
+            return 1;
+        }
+        if col + 1 == self.cols {
+            self.write_cell(
+                row,
+                col,
+                Char {
+                    glyph: Glyph::from(' '),
+                    ..ch
+                },
+            );
+            return 1;
+        }
+        self.split_wide(row, col + 1);
+        let continuation = Char {
+            glyph: Glyph::from('\0'),
+            ..ch.clone()
+        };
+        self.write_cell(row, col, ch);
+        self.write_cell(row, col + 1, continuation);
+        2
+    }
+
+    /// Move the contents of the frame up by `shift` rows (or down if negative), leaving blank
+    /// rows behind.
+    fn shift_rows(&mut self, shift: isize) {
+        let n = shift.unsigned_abs().min(self.rows) * self.cols;
+        let len = self.buffer.len();
+        if shift > 0 {
+            self.buffer.rotate_left(n);
+            self.buffer[len - n..].fill(Char::default());
+        } else {
+            self.buffer.rotate_right(n);
+            self.buffer[..n].fill(Char::default());
+        }
+        self.row_state.shift(shift);
+    }
+
+    /// Move the contents of the frame up by `n` rows, leaving blank rows at the bottom.
+    pub fn scroll_up(&mut self, n: usize) {
+        self.shift_rows(n.min(self.rows) as isize);
+    }
+
+    /// Move the contents of the frame down by `n` rows, leaving blank rows at the top.
+    pub fn scroll_down(&mut self, n: usize) {
+        self.shift_rows(-(n.min(self.rows) as isize));
+    }
+
+    /// Move the contents of a region of the frame up by `n` rows (or down if negative), leaving
+    /// blank rows behind. The rest of the frame is untouched.
+    ///
+    /// Wide glyphs cut in half by the sides of the region are replaced with spaces first.
+    pub fn scroll_region(&mut self, region: Rect, n: isize) {
+        let region = region.intersection(self.area());
+        if region.is_empty() || n == 0 {
+            return;
+        }
+        if region.col == 0 && region.width == self.cols && region.height == self.rows {
+            return self.shift_rows(n);
+        }
+        self.split_edges(region);
+        let rows = region.row..region.bottom();
+        let order: Box<dyn Iterator<Item = usize>> = if n > 0 {
+            Box::new(rows.clone())
+        } else {
+            Box::new(rows.clone().rev())
+        };
+        for row in order {
+            let src = row as isize + n;
+            for col in region.col..region.right() {
+                let ch = if src >= 0 && rows.contains(&(src as usize)) {
+                    self.buffer[src as usize * self.cols + col].clone()
+                } else {
+                    Char::default()
+                };
+                self.write_cell(row, col, ch);
+            }
+        }
+    }
+
+    /// Replace any wide glyphs that are cut in half by the sides of a rectangle with spaces.
+    fn split_edges(&mut self, rect: Rect) {
+        for row in rect.row..rect.bottom() {
+            if self.buffer[row * self.cols + rect.col].is_continuation() {
+                self.blank(row, rect.col);
+            }
+            if rect.right() < self.cols
+                && self.buffer[row * self.cols + rect.right()].is_continuation()
+            {
+                self.blank(row, rect.right() - 1);
+            }
+        }
+    }
+
+    /// Replace a cell with a space of the same style, along with the other half of a wide glyph.
+    fn blank(&mut self, row: usize, col: usize) {
+        let ch = Char {
+            glyph: Glyph::from(' '),
+            ..self.buffer[row * self.cols + col].clone()
+        };
+        self.put(row, col, ch);
+    }
+
+    /// Overwrite a cell that is known to be in bounds, keeping the row state up to date.
+    fn write_cell(&mut self, row: usize, col: usize, ch: Char) {
+        let idx = row * self.cols + col;
+        self.row_state.update(row, col, &self.buffer[idx], &ch);
+        self.buffer[idx] = ch;
+    }
+
+    /// If the cell is half of a wide glyph, blank out the other half, since the glyph is about to
+    /// be overwritten.
+    fn split_wide(&mut self, row: usize, col: usize) {
+        let idx = row * self.cols + col;
+        let other = if self.buffer[idx].is_continuation() {
+            col.checked_sub(1)
+        } else if col + 1 < self.cols && self.buffer[idx + 1].is_continuation() {
+            Some(col + 1)
+        } else {
+            None
+        };
+        if let Some(other) = other {
+            let ch = Char {
+                glyph: Glyph::from(' '),
+                ..self.buffer[row * self.cols + other].clone()
+            };
+            self.write_cell(row, other, ch);
+        }
+    }
+
+    /// Write cells along a row from `col` up to (but not including) `end`, allowing for wide
+    /// glyphs. Zero-width glyphs are skipped, and tabs are expanded if the control policy says
+    /// so. Returns the number of cells written.
+    pub(crate) fn write_run(
+        &mut self,
+        row: usize,
+        col: usize,
+        end: usize,
+        chars: impl IntoIterator<Item = Char>,
+    ) -> usize {
+        let end = end.min(self.cols);
+        let mut pos = col;
+        for ch in chars {
+            let width = ch.width();
+            if width == 0 {
+                continue;
+            }
+            if let (true, ControlPolicy::ExpandTabs(stop)) = (ch.glyph == '\t', self.control_policy)
+            {
+                let next = (pos / stop.max(1) + 1) * stop.max(1);
+                let blank = Char {
+                    glyph: Glyph::from(' '),
+                    ..ch
+                };
+                while pos < next.min(end) {
+                    pos += self.put(row, pos, blank.clone());
+                }
+                continue;
+            }
+            if pos + width > end {
+                break;
+            }
+            pos += self.put(row, pos, ch);
+        }
+        pos.saturating_sub(col)
     }
 
     pub fn get(&self, row: usize, col: usize) -> Char {
         self.check_dims(row, col);
-        self.buffer[row * self.cols + col]
+        self.buffer[row * self.cols + col].clone()
     }
 
-    fn prev_row_col(&self, row: usize, col: usize) -> Option<(usize, usize)> {
-        if row == 0 && col == 0 {
-            None
+    /// Like `get`, but returns `None` rather than panicking if the row or column is out of
+    /// bounds.
+    pub fn try_get(&self, row: usize, col: usize) -> Option<Char> {
+        if row < self.rows && col < self.cols {
+            Some(self.buffer[row * self.cols + col].clone())
         } else {
-            match col {
-                0 => Some((row - 1, self.cols - 1)),
-                n => Some((row, n - 1)),
+            None
+        }
+    }
+
+    /// The cells of a row, from left to right. Will panic if the row is out of bounds.
+    pub fn row(&self, row: usize) -> &[Char] {
+        if row >= self.rows {
+            self.check_dims(row, 0);
+        }
+        &self.buffer[row * self.cols..(row + 1) * self.cols]
+    }
+
+    /// The cells that are different in `other`, a row at a time.
+    ///
+    /// Every cell of `other` is compared, with any outside this frame counted as blank, so
+    /// setting each `new` cell in a blank frame of the same size turns it into `other`. Wide
+    /// glyphs are listed along with their continuation cells.
+    pub fn diff(&self, other: &Frame) -> Vec<CellChange> {
+        other
+            .iter()
+            .filter_map(|(row, col, new)| {
+                let old = self.try_get(row, col).unwrap_or_default();
+                (old != *new).then_some(CellChange {
+                    row,
+                    col,
+                    old,
+                    new: new.clone(),
+                })
+            })
+            .collect()
+    }
+
+    /// Iterate over the cells of the frame as `(row, col, cell)`, a row at a time.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, usize, &Char)> + '_ {
+        let cols = self.cols;
+        self.buffer
+            .iter()
+            .enumerate()
+            .map(move |(idx, ch)| (idx / cols, idx % cols, ch))
+    }
+
+    /// Iterate over the cells of the frame as `(row, col, cell)`, a row at a time, with mutable
+    /// access to each cell.
+    ///
+    /// As with indexing, it's up to you to keep wide glyphs and their continuation cells
+    /// together, and not to write control characters.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (usize, usize, &mut Char)> + '_ {
+        let cols = self.cols;
+        for row in 0..self.rows {
+            self.row_state.invalidate(row);
+        }
+        self.buffer
+            .iter_mut()
+            .enumerate()
+            .map(move |(idx, ch)| (idx / cols, idx % cols, ch))
+    }
+
+    /// Write a string starting at the given position, using the given colors.
+    ///
+    /// Control characters are handled according to the frame's `ControlPolicy`.
+    ///
+    /// Characters that would fall past the right edge of the frame are dropped. Returns the
+    /// number of cells written. Will panic if the row is out of bounds, unless the frame clips
+    /// writes.
+    pub fn set_str(&mut self, row: usize, col: usize, s: &str, fg: Color, bg: Color) -> usize {
+        if row >= self.rows {
+            if self.clip_writes {
+                return 0;
             }
+            self.check_dims(row, 0);
         }
+        let style = Style::new(fg, bg, Attributes::empty());
+        self.write_run(
+            row,
+            col,
+            self.cols,
+            s.graphemes(true).map(|glyph| style.char(glyph)),
+        )
+    }
+
+    /// Fill a rectangle with the given character.
+    ///
+    /// Any part of the rectangle that lies outside the frame is ignored.
+    pub fn fill_rect(&mut self, row: usize, col: usize, height: usize, width: usize, ch: Char) {
+        let ch = match self.sanitize(ch) {
+            Some(ch) => ch,
+            None => return,
+        };
+        let rect = Rect::new(row, col, height, width).intersection(self.area());
+        if rect.is_empty() {
+            return;
+        }
+        self.split_edges(rect);
+        // A wide character takes two cells, and one that doesn't fit at the end of a row is
+        // replaced with a space, as with `set`.
+        let wide = ch.width() >= 2;
+        let continuation = Char {
+            glyph: Glyph::from('\0'),
+            ..ch.clone()
+        };
+        let space = Char {
+            glyph: Glyph::from(' '),
+            ..ch.clone()
+        };
+        for row in rect.row..rect.bottom() {
+            let mut col = rect.col;
+            while col < rect.right() {
+                if !wide {
+                    self.write_cell(row, col, ch.clone());
+                    col += 1;
+                } else if col + 1 < rect.right() {
+                    self.write_cell(row, col, ch.clone());
+                    self.write_cell(row, col + 1, continuation.clone());
+                    col += 2;
+                } else {
+                    self.write_cell(row, col, space.clone());
+                    col += 1;
+                }
+            }
+        }
+    }
+
+    /// The column from which the rest of the row is blank (default) cells.
+    fn blank_from(&self, row: usize) -> usize {
+        let start = row * self.cols;
+        self.buffer[start..start + self.cols]
+            .iter()
+            .rposition(|ch| *ch != Char::default())
+            .map_or(0, |col| col + 1)
+    }
+
+    /// The number of blank (default) cells in a row from `col`, stopping at `end`.
+    fn blank_run(&self, row: usize, col: usize, end: usize) -> usize {
+        let start = row * self.cols;
+        self.buffer[start + col..start + end]
+            .iter()
+            .take_while(|ch| **ch == Char::default())
+            .count()
     }
 
-    fn check_dims(&self, row: usize, col: usize) {
+    pub(crate) fn check_dims(&self, row: usize, col: usize) {
         if row >= self.rows {
             panic!(
                 "Row {} is out of bounds (number of rows: {})",
@@ -173,19 +1197,89 @@ impl Frame {
     }
 }
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+/// Index by `(row, col)`. Will panic if the row or column is out of bounds.
+impl Index<(usize, usize)> for Frame {
+    type Output = Char;
+
+    fn index(&self, (row, col): (usize, usize)) -> &Char {
+        self.check_dims(row, col);
+        &self.buffer[row * self.cols + col]
+    }
+}
+
+/// Index by `(row, col)`. Will panic if the row or column is out of bounds.
+///
+/// Unlike `Frame::set`, this gives direct access to the cell, so it's up to you to keep wide
+/// glyphs and their continuation cells together, and not to write control characters.
+impl IndexMut<(usize, usize)> for Frame {
+    fn index_mut(&mut self, (row, col): (usize, usize)) -> &mut Char {
+        self.check_dims(row, col);
+        self.row_state.invalidate(row);
+        &mut self.buffer[row * self.cols + col]
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Char {
-    pub glyph: char,
+    pub glyph: Glyph,
     pub color_fg: Color,
     pub color_bg: Color,
+    pub attrs: Attributes,
+    /// A hyperlink to open when the cell is clicked, in terminals that support it.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub link: Option<Link>,
 }
 
 impl Char {
-    pub fn new(glyph: char) -> Char {
+    /// A cell that lets whatever is below it show through when compositing `Layers`.
+    ///
+    /// Transparent cells take up no space, so they shouldn't be drawn to the terminal.
+    pub const TRANSPARENT: Char = Char {
+        glyph: Glyph::EMPTY,
+        color_fg: Color::Default,
+        color_bg: Color::Default,
+        attrs: Attributes { bits: 0 },
+        link: None,
+    };
+
+    pub fn new(glyph: impl Into<Glyph>) -> Char {
         Char {
-            glyph,
+            glyph: glyph.into(),
             color_fg: Color::default(),
             color_bg: Color::default(),
+            attrs: Attributes::empty(),
+            link: None,
+        }
+    }
+
+    /// The number of terminal columns the glyph takes up (0, 1 or 2).
+    ///
+    /// Control characters are counted as 1.
+    pub fn width(&self) -> usize {
+        if self.is_continuation() {
+            return 0;
+        }
+        self.glyph.width()
+    }
+
+    /// Whether this is a transparent cell (see `Char::TRANSPARENT`).
+    pub fn is_transparent(&self) -> bool {
+        self.glyph == Glyph::EMPTY
+    }
+
+    /// Whether this cell is the second half of a wide glyph in the cell to its left.
+    pub fn is_continuation(&self) -> bool {
+        self.glyph.is_nul()
+    }
+
+    /// The colors, attributes and link of this cell.
+    pub fn style(&self) -> Style {
+        Style {
+            color_fg: self.color_fg,
+            color_bg: self.color_bg,
+            attrs: self.attrs,
+            link: self.link.clone(),
         }
     }
 
@@ -201,19 +1295,201 @@ impl Char {
 impl Default for Char {
     fn default() -> Self {
         Char {
-            glyph: ' ',
+            glyph: Glyph::from(' '),
             color_fg: Color::default(),
             color_bg: Color::default(),
+            attrs: Attributes::empty(),
+            link: None,
+        }
+    }
+}
+
+/// The colors, attributes and link of a cell, without the glyph.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Style {
+    pub color_fg: Color,
+    pub color_bg: Color,
+    pub attrs: Attributes,
+    /// A hyperlink for the cells drawn with this style (see `Link`).
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub link: Option<Link>,
+}
+
+impl Style {
+    pub fn new(color_fg: Color, color_bg: Color, attrs: Attributes) -> Style {
+        Style {
+            color_fg,
+            color_bg,
+            attrs,
+            link: None,
+        }
+    }
+
+    /// A cell with this style and the given glyph.
+    pub fn char(&self, glyph: impl Into<Glyph>) -> Char {
+        Char {
+            glyph: glyph.into(),
+            color_fg: self.color_fg,
+            color_bg: self.color_bg,
+            attrs: self.attrs,
+            link: self.link.clone(),
+        }
+    }
+}
+
+/// Text style attributes (bold, italic, etc.) for a cell.
+///
+/// Attributes can be combined with `|`, e.g. `Attributes::BOLD | Attributes::UNDERLINE`.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Attributes {
+    bits: u8,
+}
+
+/// All the bits used by attributes.
+const ATTRIBUTE_BITS: u8 = (1 << 7) - 1;
+
+impl Attributes {
+    pub const BOLD: Attributes = Attributes { bits: 1 };
+    pub const DIM: Attributes = Attributes { bits: 1 << 1 };
+    pub const ITALIC: Attributes = Attributes { bits: 1 << 2 };
+    pub const UNDERLINE: Attributes = Attributes { bits: 1 << 3 };
+    pub const REVERSE: Attributes = Attributes { bits: 1 << 4 };
+    pub const STRIKETHROUGH: Attributes = Attributes { bits: 1 << 5 };
+    /// Many terminals don't support blinking, or have it turned off.
+    pub const BLINK: Attributes = Attributes { bits: 1 << 6 };
+
+    /// No attributes set.
+    pub const fn empty() -> Attributes {
+        Attributes { bits: 0 }
+    }
+
+    /// The raw bit representation of the attributes.
+    pub fn bits(self) -> u8 {
+        self.bits
+    }
+
+    /// Attributes from their raw bit representation. Unknown bits are ignored.
+    pub fn from_bits(bits: u8) -> Attributes {
+        Attributes {
+            bits: bits & ATTRIBUTE_BITS,
+        }
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.bits == 0
+    }
+
+    /// Whether all the attributes in `other` are also set in `self`.
+    pub fn contains(self, other: Attributes) -> bool {
+        self.bits & other.bits == other.bits
+    }
+
+    pub fn insert(&mut self, other: Attributes) {
+        self.bits |= other.bits;
+    }
+
+    pub fn remove(&mut self, other: Attributes) {
+        self.bits &= !other.bits;
+    }
+
+    /// Write the SGR escapes needed to go from the `prev` attributes to these ones.
+    pub(crate) fn write_change(self, prev: Attributes, writer: &mut impl Write) -> io::Result<()> {
+        use termion::style;
+        let removed = Attributes {
+            bits: prev.bits & !self.bits,
+        };
+        // There is no reliable way to turn off only one of bold or dim (SGR 21 is double
+        // underline on some terminals), so turn both off and reapply whichever is still set.
+        let intensity_reset =
+            removed.contains(Attributes::BOLD) || removed.contains(Attributes::DIM);
+        if intensity_reset {
+            write!(writer, "{}", style::NoFaint)?;
+        }
+        if removed.contains(Attributes::ITALIC) {
+            write!(writer, "{}", style::NoItalic)?;
+        }
+        if removed.contains(Attributes::UNDERLINE) {
+            write!(writer, "{}", style::NoUnderline)?;
+        }
+        if removed.contains(Attributes::REVERSE) {
+            write!(writer, "{}", style::NoInvert)?;
+        }
+        if removed.contains(Attributes::STRIKETHROUGH) {
+            write!(writer, "{}", style::NoCrossedOut)?;
         }
+        if removed.contains(Attributes::BLINK) {
+            write!(writer, "{}", style::NoBlink)?;
+        }
+        let mut added = Attributes {
+            bits: self.bits & !prev.bits,
+        };
+        if intensity_reset {
+            added.insert(Attributes {
+                bits: self.bits & (Attributes::BOLD | Attributes::DIM).bits,
+            });
+        }
+        if added.contains(Attributes::BOLD) {
+            write!(writer, "{}", style::Bold)?;
+        }
+        if added.contains(Attributes::DIM) {
+            write!(writer, "{}", style::Faint)?;
+        }
+        if added.contains(Attributes::ITALIC) {
+            write!(writer, "{}", style::Italic)?;
+        }
+        if added.contains(Attributes::UNDERLINE) {
+            write!(writer, "{}", style::Underline)?;
+        }
+        if added.contains(Attributes::REVERSE) {
+            write!(writer, "{}", style::Invert)?;
+        }
+        if added.contains(Attributes::STRIKETHROUGH) {
+            write!(writer, "{}", style::CrossedOut)?;
+        }
+        if added.contains(Attributes::BLINK) {
+            write!(writer, "{}", style::Blink)?;
+        }
+        Ok(())
     }
 }
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+impl BitOr for Attributes {
+    type Output = Attributes;
+    fn bitor(self, other: Attributes) -> Attributes {
+        Attributes {
+            bits: self.bits | other.bits,
+        }
+    }
+}
+
+impl BitAnd for Attributes {
+    type Output = Attributes;
+    fn bitand(self, other: Attributes) -> Attributes {
+        Attributes {
+            bits: self.bits & other.bits,
+        }
+    }
+}
+
+impl BitOrAssign for Attributes {
+    fn bitor_assign(&mut self, other: Attributes) {
+        self.bits |= other.bits;
+    }
+}
+
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Color {
+    #[default]
     Default,
     Black,
     Blue,
     Cyan,
+    Green,
+    /// An entry in the terminal's 256-color palette.
+    Indexed(u8),
     LightBlack,
     LightBlue,
     LightCyan,
@@ -225,18 +1501,25 @@ pub enum Color {
     Magenta,
     Red,
     Rgb(u8, u8, u8),
+    /// An RGB color with an alpha channel (0 is fully transparent, 255 fully opaque).
+    ///
+    /// When frames are composited (see `Frame::blit`), the color is blended with the one below
+    /// it. Otherwise the alpha channel is ignored.
+    Rgba(u8, u8, u8, u8),
     White,
     Yellow,
 }
 
 impl Color {
-    fn write_fg(&self, writer: &mut impl Write) -> io::Result<()> {
+    pub(crate) fn write_fg(&self, writer: &mut impl Write) -> io::Result<()> {
         use termion::color;
         match self {
             Color::Default => write!(writer, "{}", color::Fg(color::Reset)),
             Color::Black => write!(writer, "{}", color::Fg(color::Black)),
             Color::Blue => write!(writer, "{}", color::Fg(color::Blue)),
             Color::Cyan => write!(writer, "{}", color::Fg(color::Cyan)),
+            Color::Green => write!(writer, "{}", color::Fg(color::Green)),
+            Color::Indexed(idx) => write!(writer, "{}", color::Fg(color::AnsiValue(*idx))),
             Color::LightBlack => write!(writer, "{}", color::Fg(color::LightBlack)),
             Color::LightBlue => write!(writer, "{}", color::Fg(color::LightBlue)),
             Color::LightCyan => write!(writer, "{}", color::Fg(color::LightCyan)),
@@ -247,18 +1530,22 @@ impl Color {
             Color::LightYellow => write!(writer, "{}", color::Fg(color::LightYellow)),
             Color::Magenta => write!(writer, "{}", color::Fg(color::Magenta)),
             Color::Red => write!(writer, "{}", color::Fg(color::Red)),
-            Color::Rgb(r, g, b) => write!(writer, "{}", color::Fg(color::Rgb(*r, *g, *b))),
+            Color::Rgb(r, g, b) | Color::Rgba(r, g, b, _) => {
+                write!(writer, "{}", color::Fg(color::Rgb(*r, *g, *b)))
+            }
             Color::White => write!(writer, "{}", color::Fg(color::White)),
             Color::Yellow => write!(writer, "{}", color::Fg(color::Yellow)),
         }
     }
-    fn write_bg(&self, writer: &mut impl Write) -> io::Result<()> {
+    pub(crate) fn write_bg(&self, writer: &mut impl Write) -> io::Result<()> {
         use termion::color;
         match self {
             Color::Default => write!(writer, "{}", color::Bg(color::Reset)),
             Color::Black => write!(writer, "{}", color::Bg(color::Black)),
             Color::Blue => write!(writer, "{}", color::Bg(color::Blue)),
             Color::Cyan => write!(writer, "{}", color::Bg(color::Cyan)),
+            Color::Green => write!(writer, "{}", color::Bg(color::Green)),
+            Color::Indexed(idx) => write!(writer, "{}", color::Bg(color::AnsiValue(*idx))),
             Color::LightBlack => write!(writer, "{}", color::Bg(color::LightBlack)),
             Color::LightBlue => write!(writer, "{}", color::Bg(color::LightBlue)),
             Color::LightCyan => write!(writer, "{}", color::Bg(color::LightCyan)),
@@ -269,19 +1556,15 @@ impl Color {
             Color::LightYellow => write!(writer, "{}", color::Bg(color::LightYellow)),
             Color::Magenta => write!(writer, "{}", color::Bg(color::Magenta)),
             Color::Red => write!(writer, "{}", color::Bg(color::Red)),
-            Color::Rgb(r, g, b) => write!(writer, "{}", color::Bg(color::Rgb(*r, *g, *b))),
+            Color::Rgb(r, g, b) | Color::Rgba(r, g, b, _) => {
+                write!(writer, "{}", color::Bg(color::Rgb(*r, *g, *b)))
+            }
             Color::White => write!(writer, "{}", color::Bg(color::White)),
             Color::Yellow => write!(writer, "{}", color::Bg(color::Yellow)),
         }
     }
 }
 
-impl Default for Color {
-    fn default() -> Self {
-        Color::Default
-    }
-}
-
 #[macro_export]
 macro_rules! char {
     () => {
@@ -292,16 +1575,231 @@ macro_rules! char {
     };
     ($glyph:expr, $fg:expr) => {
         $crate::Char {
-            glyph: $glyph,
+            glyph: $crate::Glyph::from($glyph),
             color_fg: $fg,
-            color_bg: Color::default(),
+            color_bg: $crate::Color::default(),
+            attrs: $crate::Attributes::empty(),
+            link: None,
         }
     };
     ($glyph:expr, $fg:expr, $bg:expr) => {
         $crate::Char {
-            glyph: $glyph,
+            glyph: $crate::Glyph::from($glyph),
             color_fg: $fg,
             color_bg: $bg,
+            attrs: $crate::Attributes::empty(),
+            link: None,
         }
     };
+    ($glyph:expr, $fg:expr, $bg:expr, $attrs:expr) => {
+        $crate::Char {
+            glyph: $crate::Glyph::from($glyph),
+            color_fg: $fg,
+            color_bg: $bg,
+            attrs: $attrs,
+            link: None,
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The glyphs of a row, with continuations as `"\0"`.
+    fn glyphs(frame: &Frame, row: usize) -> Vec<&str> {
+        frame.row(row).iter().map(|ch| ch.glyph.as_str()).collect()
+    }
+
+    /// Draw the next frame of a screen and render it, returning the output.
+    fn render(screen: &mut Screen, draw: impl FnOnce(&mut Frame)) -> String {
+        let (rows, cols) = screen.next.dims();
+        screen.prepare_next_frame(rows, cols);
+        draw(&mut screen.next);
+        let mut output = Vec::new();
+        screen.render(&mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    fn text(frame: &mut Frame, row: usize, s: &str) {
+        frame.set_str(row, 0, s, Color::Default, Color::Default);
+    }
+
+    #[test]
+    fn fill_rect_splits_wide_glyphs_at_the_edges() {
+        let mut frame = Frame::new(1, 6);
+        frame.set(0, 0, Char::new('中'));
+        frame.set(0, 4, Char::new('中'));
+        frame.fill_rect(0, 1, 1, 4, Char::new('x'));
+        assert_eq!(glyphs(&frame, 0), [" ", "x", "x", "x", "x", " "]);
+    }
+
+    #[test]
+    fn frames_compare_by_contents_only() {
+        use std::collections::hash_map::DefaultHasher;
+        let hash = |frame: &Frame| {
+            let mut hasher = DefaultHasher::new();
+            frame.hash(&mut hasher);
+            hasher.finish()
+        };
+        let mut a = Frame::new(2, 3);
+        let mut b = Frame::new(2, 3);
+        b.set_clip_writes(true);
+        b.set_control_policy(ControlPolicy::Reject);
+        a.set(1, 1, Char::new('x'));
+        b.set(1, 1, Char::new('x'));
+        assert_eq!(a, b);
+        assert_eq!(hash(&a), hash(&b));
+        assert_eq!(a.cmp(&b), Ordering::Equal);
+        b.set(0, 0, Char::new('y'));
+        assert_ne!(a, b);
+        assert_ne!(Frame::new(2, 3), Frame::new(3, 2));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn deserialized_frames_are_checked() {
+        let data = |rows, cols, buffer: Vec<Char>| FrameData {
+            rows,
+            cols,
+            buffer,
+            control_policy: ControlPolicy::default(),
+            clip_writes: false,
+        };
+        assert!(Frame::try_from(data(2, 3, vec![Char::default(); 5])).is_err());
+        assert!(Frame::try_from(data(usize::MAX, 2, Vec::new())).is_err());
+
+        let mut buffer = vec![Char::default(); 6];
+        buffer[4] = Char::new('x');
+        let frame = Frame::try_from(data(2, 3, buffer)).unwrap();
+        let mut expected = Frame::new(2, 3);
+        expected.set(1, 1, Char::new('x'));
+        assert_eq!(frame, expected);
+        for row in 0..2 {
+            assert_eq!(frame.row_state.hash(row), expected.row_state.hash(row));
+        }
+    }
+
+    #[test]
+    fn fill_rect_with_a_wide_glyph() {
+        let mut frame = Frame::new(1, 5);
+        frame.fill_rect(0, 0, 1, 5, Char::new('中'));
+        assert_eq!(glyphs(&frame, 0), ["中", "\0", "中", "\0", " "]);
+    }
+
+    #[test]
+    fn only_changes_are_rendered() {
+        let mut screen = Screen::new(3, 10, ColorSupport::TrueColor);
+        render(&mut screen, |frame| text(frame, 0, "hello"));
+        let output = render(&mut screen, |frame| {
+            text(frame, 0, "hello");
+            frame.set(1, 2, Char::new('x'));
+        });
+        assert!(output.contains("\x1b[2;3Hx"), "{:?}", output);
+        assert!(!output.contains("hello"), "{:?}", output);
+        assert!(!screen.stats.full_redraw);
+        assert_eq!(screen.stats.cells_changed, 1);
+        let output = render(&mut screen, |frame| {
+            text(frame, 0, "hello");
+            frame.set(1, 2, Char::new('x'));
+        });
+        assert_eq!(output, "");
+    }
+
+    #[test]
+    fn mostly_changed_frames_are_redrawn() {
+        let mut screen = Screen::new(2, 4, ColorSupport::TrueColor);
+        render(&mut screen, |_| ());
+        let output = render(&mut screen, |frame| {
+            text(frame, 0, "abcd");
+            text(frame, 1, "efgh");
+        });
+        assert!(output.contains(&termion::clear::All.to_string()));
+        assert!(screen.stats.full_redraw);
+    }
+
+    #[test]
+    fn scrolled_content_is_scrolled_on_the_terminal() {
+        let mut screen = Screen::new(10, 12, ColorSupport::TrueColor);
+        screen.scroll_detection = true;
+        render(&mut screen, |frame| {
+            for row in 0..10 {
+                text(frame, row, &format!("line {}", row));
+            }
+        });
+        let output = render(&mut screen, |frame| {
+            for row in 0..10 {
+                text(frame, row, &format!("line {}", row + 1));
+            }
+        });
+        assert!(output.contains(&termion::scroll::Up(1).to_string()));
+        // Only the row scrolled in is drawn.
+        assert!(
+            output.ends_with("\x1b[10;1Hline\x1b[10;6H10"),
+            "{:?}",
+            output
+        );
+    }
+
+    #[test]
+    fn scrolling_isnt_used_for_a_few_rows() {
+        let mut screen = Screen::new(10, 12, ColorSupport::TrueColor);
+        screen.scroll_detection = true;
+        render(&mut screen, |frame| {
+            text(frame, 0, "one");
+            text(frame, 1, "two");
+        });
+        let output = render(&mut screen, |frame| text(frame, 0, "two"));
+        assert!(!output.contains(&termion::scroll::Up(1).to_string()));
+    }
+
+    #[test]
+    fn blank_ends_of_rows_are_erased() {
+        let mut screen = Screen::new(4, 20, ColorSupport::TrueColor);
+        let keep = |frame: &mut Frame| {
+            for row in 1..4 {
+                text(frame, row, "keep");
+            }
+        };
+        render(&mut screen, |frame| {
+            text(frame, 0, &"x".repeat(20));
+            keep(frame);
+        });
+        let output = render(&mut screen, |frame| {
+            text(frame, 0, "ab");
+            keep(frame);
+        });
+        assert!(!screen.stats.full_redraw);
+        assert!(
+            output.ends_with(&format!("ab{}", termion::clear::UntilNewline)),
+            "{:?}",
+            output
+        );
+        assert!(!output.contains('x'), "{:?}", output);
+    }
+
+    #[test]
+    fn a_few_blank_cells_are_written() {
+        let mut screen = Screen::new(4, 20, ColorSupport::TrueColor);
+        render(&mut screen, |frame| text(frame, 0, "abcde"));
+        let output = render(&mut screen, |frame| text(frame, 0, "ab"));
+        assert!(!output.contains(&termion::clear::UntilNewline.to_string()));
+        assert!(output.ends_with("\x1b[1;3H   "), "{:?}", output);
+    }
+
+    #[test]
+    fn wide_glyphs_are_rendered_once() {
+        let mut screen = Screen::new(2, 6, ColorSupport::TrueColor);
+        render(&mut screen, |_| ());
+        let output = render(&mut screen, |frame| frame.set(0, 0, Char::new('中')));
+        assert!(output.contains('中'), "{:?}", output);
+        assert!(!output.contains('\0'), "{:?}", output);
+        // Writing over the second half of the glyph blanks the first.
+        let output = render(&mut screen, |frame| {
+            frame.set(0, 0, Char::new('中'));
+            frame.set(0, 1, Char::new('a'));
+        });
+        assert_eq!(glyphs(&screen.next, 0), [" ", "a", " ", " ", " ", " "]);
+        assert!(output.contains(" a"), "{:?}", output);
+    }
 }
@@ -0,0 +1,60 @@
+//! Job control signal handling for the termion backend.
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Once;
+
+static SUSPEND_REQUESTED: AtomicBool = AtomicBool::new(false);
+static CONTINUED: AtomicBool = AtomicBool::new(false);
+static INSTALL: Once = Once::new();
+
+/// A job control signal received by the process.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum JobControl {
+    /// The process was asked to suspend itself (`SIGTSTP`).
+    Suspend,
+    /// The process was continued after being stopped (`SIGCONT`).
+    Continue,
+}
+
+extern "C" fn on_tstp(_: libc::c_int) {
+    SUSPEND_REQUESTED.store(true, Ordering::SeqCst);
+}
+
+extern "C" fn on_cont(_: libc::c_int) {
+    CONTINUED.store(true, Ordering::SeqCst);
+}
+
+/// Install handlers for `SIGTSTP` and `SIGCONT`. Only the first call does anything.
+pub(crate) fn install() {
+    INSTALL.call_once(|| unsafe {
+        libc::signal(
+            libc::SIGTSTP,
+            on_tstp as extern "C" fn(libc::c_int) as libc::sighandler_t,
+        );
+        libc::signal(
+            libc::SIGCONT,
+            on_cont as extern "C" fn(libc::c_int) as libc::sighandler_t,
+        );
+    });
+}
+
+/// The job control signal received since the last call, if any.
+pub(crate) fn take() -> Option<JobControl> {
+    if SUSPEND_REQUESTED.swap(false, Ordering::SeqCst) {
+        Some(JobControl::Suspend)
+    } else if CONTINUED.swap(false, Ordering::SeqCst) {
+        Some(JobControl::Continue)
+    } else {
+        None
+    }
+}
+
+/// Stop the process until it is continued.
+pub(crate) fn stop() -> io::Result<()> {
+    if unsafe { libc::raise(libc::SIGSTOP) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // We've been continued, but we already know about it.
+    CONTINUED.store(false, Ordering::SeqCst);
+    Ok(())
+}
@@ -0,0 +1,211 @@
+use crate::backend::Backend;
+use crate::event::Event;
+use crate::replay::EventLog;
+use std::collections::VecDeque;
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+/// Something scripted to happen to the terminal.
+#[derive(Debug, Clone)]
+enum Step {
+    Event(Event),
+    Resize(usize, usize),
+}
+
+/// A backend simulating a terminal, where the size, the passing of time and the input are all
+/// controlled by a script, so apps can be tested end to end in CI without a terminal.
+///
+/// Time only moves on when told to with `advance`, or when the app waits. Waiting for input
+/// jumps straight to the next scripted event or to the end of the timeout, so nothing ever
+/// sleeps, and ticks (see `AppBuilder::tick_rate`) come at exactly the same times on every run.
+///
+/// Once the script has run out, waiting for input fails with an `UnexpectedEof` error, as with
+/// a terminal whose input has been closed, so `App::wait_event` can't wait forever. After that,
+/// waits just run to the end of their timeout.
+#[derive(Debug, Clone)]
+pub struct SimBackend {
+    cols: usize,
+    rows: usize,
+    epoch: Instant,
+    elapsed: Duration,
+    /// Steps waiting for their time to come, in time order.
+    script: VecDeque<(Duration, Step)>,
+    /// Steps whose time has come, but haven't been read yet.
+    ready: VecDeque<Step>,
+    raw_mode: bool,
+    output: Vec<u8>,
+    /// Whether the end of the script has been reported.
+    closed: bool,
+}
+
+impl SimBackend {
+    /// Simulate a terminal of the given size, with no input yet.
+    pub fn new(cols: usize, rows: usize) -> SimBackend {
+        SimBackend {
+            cols,
+            rows,
+            epoch: Instant::now(),
+            elapsed: Duration::ZERO,
+            script: VecDeque::new(),
+            ready: VecDeque::new(),
+            raw_mode: false,
+            output: Vec::new(),
+            closed: false,
+        }
+    }
+
+    /// Schedule all the events in a log, with the times in the log counted from now.
+    ///
+    /// `Event::Resize` events change the size of the terminal, and the app notices in the usual
+    /// way.
+    pub fn script(&mut self, log: &EventLog) {
+        for (time, event) in log.events() {
+            let step = match event {
+                Event::Resize(cols, rows) => Step::Resize(*cols, *rows),
+                event => Step::Event(event.clone()),
+            };
+            self.schedule(*time, step);
+        }
+    }
+
+    /// Queue an event to be read straight away.
+    pub fn push_event(&mut self, event: Event) {
+        self.ready.push_back(Step::Event(event));
+    }
+
+    /// Schedule an event to arrive `delay` from now.
+    pub fn push_event_after(&mut self, delay: Duration, event: Event) {
+        self.schedule(delay, Step::Event(event));
+    }
+
+    /// Change the size of the terminal now.
+    pub fn resize(&mut self, cols: usize, rows: usize) {
+        self.cols = cols;
+        self.rows = rows;
+    }
+
+    /// Schedule a change in the size of the terminal, `delay` from now.
+    pub fn resize_after(&mut self, delay: Duration, cols: usize, rows: usize) {
+        self.schedule(delay, Step::Resize(cols, rows));
+    }
+
+    /// Move the clock forward, making anything scheduled in the meantime ready to be read.
+    pub fn advance(&mut self, duration: Duration) {
+        self.elapsed += duration;
+        while let Some((time, _)) = self.script.front() {
+            if *time > self.elapsed {
+                break;
+            }
+            let (_, step) = self.script.pop_front().unwrap();
+            self.ready.push_back(step);
+        }
+    }
+
+    /// How much simulated time has passed since the backend was created.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// Whether everything scripted has happened, and been read by the app.
+    pub fn is_finished(&self) -> bool {
+        self.script.is_empty() && self.ready.is_empty()
+    }
+
+    /// Whether the terminal is currently in raw mode.
+    pub fn is_raw_mode(&self) -> bool {
+        self.raw_mode
+    }
+
+    /// All the bytes written to the terminal so far.
+    pub fn output(&self) -> &[u8] {
+        &self.output
+    }
+
+    /// Take the bytes written to the terminal so far, e.g. to check what a single frame wrote.
+    pub fn take_output(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.output)
+    }
+
+    fn schedule(&mut self, delay: Duration, step: Step) {
+        let time = self.elapsed + delay;
+        let idx = self
+            .script
+            .iter()
+            .position(|(t, _)| *t > time)
+            .unwrap_or(self.script.len());
+        self.script.insert(idx, (time, step));
+        // Anything due now is ready straight away.
+        self.advance(Duration::ZERO);
+    }
+}
+
+impl Write for SimBackend {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.output.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Backend for SimBackend {
+    fn size(&self) -> io::Result<(usize, usize)> {
+        Ok((self.cols, self.rows))
+    }
+
+    fn enable_raw_mode(&mut self) -> io::Result<()> {
+        self.raw_mode = true;
+        Ok(())
+    }
+
+    fn disable_raw_mode(&mut self) -> io::Result<()> {
+        self.raw_mode = false;
+        Ok(())
+    }
+
+    fn next_event(&mut self) -> Option<io::Result<Event>> {
+        match self.ready.pop_front()? {
+            Step::Event(event) => Some(Ok(event)),
+            Step::Resize(cols, rows) => {
+                // Stop here, so the app notices the new size before any later events.
+                self.resize(cols, rows);
+                None
+            }
+        }
+    }
+
+    /// Moves the clock on to the next scripted event, or to the end of the timeout if that
+    /// comes first, rather than sleeping. Fails the first time it is called once the script has
+    /// run out.
+    fn wait_event(&mut self, timeout: Option<Duration>) -> Option<io::Result<Event>> {
+        if self.is_finished() && !self.closed {
+            self.closed = true;
+            return Some(Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "the simulated script has run out",
+            )));
+        }
+        if self.ready.is_empty() {
+            let next = self.script.front().map(|(time, _)| *time - self.elapsed);
+            let wait = match (next, timeout) {
+                (Some(next), Some(timeout)) => next.min(timeout),
+                (Some(next), None) => next,
+                (None, Some(timeout)) => timeout,
+                (None, None) => return None,
+            };
+            self.advance(wait);
+        }
+        self.next_event()
+    }
+
+    fn now(&self) -> Instant {
+        self.epoch + self.elapsed
+    }
+
+    /// Moves the clock on rather than sleeping.
+    fn sleep(&mut self, duration: Duration) {
+        self.advance(duration);
+    }
+}
@@ -0,0 +1,169 @@
+use crate::rect::Rect;
+use crate::screen::{Frame, Style};
+use std::borrow::Cow;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// A piece of text drawn with a single style.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Hash)]
+pub struct Span<'a> {
+    pub text: Cow<'a, str>,
+    pub style: Style,
+}
+
+impl<'a> Span<'a> {
+    pub fn new(text: impl Into<Cow<'a, str>>, style: Style) -> Span<'a> {
+        Span {
+            text: text.into(),
+            style,
+        }
+    }
+
+    /// A span with the default style.
+    pub fn raw(text: impl Into<Cow<'a, str>>) -> Span<'a> {
+        Span::new(text, Style::default())
+    }
+
+    /// The number of cells the span takes up.
+    pub fn width(&self) -> usize {
+        self.text.width()
+    }
+}
+
+impl<'a> From<&'a str> for Span<'a> {
+    fn from(text: &'a str) -> Span<'a> {
+        Span::raw(text)
+    }
+}
+
+impl From<String> for Span<'static> {
+    fn from(text: String) -> Span<'static> {
+        Span::raw(text)
+    }
+}
+
+/// A single line of styled text, made up of spans.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Hash)]
+pub struct Line<'a> {
+    pub spans: Vec<Span<'a>>,
+}
+
+impl<'a> Line<'a> {
+    pub fn new(spans: Vec<Span<'a>>) -> Line<'a> {
+        Line { spans }
+    }
+
+    /// The number of cells the line takes up.
+    pub fn width(&self) -> usize {
+        self.spans.iter().map(Span::width).sum()
+    }
+}
+
+impl<'a> From<Span<'a>> for Line<'a> {
+    fn from(span: Span<'a>) -> Line<'a> {
+        Line { spans: vec![span] }
+    }
+}
+
+impl<'a> From<&'a str> for Line<'a> {
+    fn from(text: &'a str) -> Line<'a> {
+        Line::from(Span::raw(text))
+    }
+}
+
+impl<'a> From<Vec<Span<'a>>> for Line<'a> {
+    fn from(spans: Vec<Span<'a>>) -> Line<'a> {
+        Line { spans }
+    }
+}
+
+/// Several lines of styled text.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Hash)]
+pub struct Text<'a> {
+    pub lines: Vec<Line<'a>>,
+}
+
+impl<'a> Text<'a> {
+    pub fn new(lines: Vec<Line<'a>>) -> Text<'a> {
+        Text { lines }
+    }
+
+    /// The number of cells the widest line takes up.
+    pub fn width(&self) -> usize {
+        self.lines.iter().map(Line::width).max().unwrap_or(0)
+    }
+
+    pub fn height(&self) -> usize {
+        self.lines.len()
+    }
+}
+
+impl<'a> From<Line<'a>> for Text<'a> {
+    fn from(line: Line<'a>) -> Text<'a> {
+        Text { lines: vec![line] }
+    }
+}
+
+impl<'a> From<&'a str> for Text<'a> {
+    fn from(text: &'a str) -> Text<'a> {
+        Text {
+            lines: text.lines().map(Line::from).collect(),
+        }
+    }
+}
+
+impl Frame {
+    /// Write a span starting at the given position.
+    ///
+    /// Characters that would fall past the right edge of the frame are dropped. Returns the
+    /// number of cells written. Will panic if the row is out of bounds.
+    pub fn set_span(&mut self, row: usize, col: usize, span: &Span) -> usize {
+        self.set_line(row, col, &Line::from(span.clone()))
+    }
+
+    /// Write a line of spans starting at the given position.
+    ///
+    /// Characters that would fall past the right edge of the frame are dropped. Returns the
+    /// number of cells written. Will panic if the row is out of bounds.
+    pub fn set_line(&mut self, row: usize, col: usize, line: &Line) -> usize {
+        if row >= self.rows() {
+            self.check_dims(row, 0);
+        }
+        self.write_line(row, col, self.columns(), line)
+    }
+
+    /// Draw text into a region, one line per row. Anything outside the region is cut off.
+    pub fn set_text(&mut self, region: Rect, text: &Text) {
+        let region = region.intersection(self.area());
+        for (row, line) in (region.row..region.bottom()).zip(&text.lines) {
+            self.write_line(row, region.col, region.right(), line);
+        }
+    }
+
+    /// Write a line from `col` up to (but not including) `end`.
+    pub(crate) fn write_line(&mut self, row: usize, col: usize, end: usize, line: &Line) -> usize {
+        let glyphs = line.spans.iter().flat_map(|span| {
+            span.text
+                .graphemes(true)
+                .map(move |glyph| span.style.char(glyph))
+        });
+        self.write_run(row, col, end, glyphs)
+    }
+
+    /// Like `write_line`, but if the line doesn't fit, the last cell written is replaced with
+    /// `…` to show that it has been cut off.
+    pub(crate) fn write_line_truncated(
+        &mut self,
+        row: usize,
+        col: usize,
+        end: usize,
+        line: &Line,
+    ) -> usize {
+        let written = self.write_line(row, col, end, line);
+        if written > 0 && line.width() > end.min(self.columns()).saturating_sub(col) {
+            let last = col + written - 1;
+            self.set_clipped(row, last, self.get(row, last).style().char('…'));
+        }
+        written
+    }
+}
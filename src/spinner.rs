@@ -0,0 +1,104 @@
+use crate::rect::Rect;
+use crate::screen::{Frame, Style};
+use std::time::{Duration, Instant};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// A built-in set of frames for a `Spinner`.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+pub enum SpinnerStyle {
+    /// `⠋⠙⠹⠸⠼⠴⠦⠧⠇⠏`
+    #[default]
+    Braille,
+    /// `.`, `..`, `...` (3 cells wide)
+    Dots,
+    /// `-\|/`
+    Line,
+    /// `◐◓◑◒`
+    Circle,
+}
+
+impl SpinnerStyle {
+    /// The frames of the animation.
+    pub fn frames(self) -> &'static [&'static str] {
+        match self {
+            SpinnerStyle::Braille => &["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"],
+            SpinnerStyle::Dots => &[".  ", ".. ", "...", "   "],
+            SpinnerStyle::Line => &["-", "\\", "|", "/"],
+            SpinnerStyle::Circle => &["◐", "◓", "◑", "◒"],
+        }
+    }
+}
+
+/// A little animation to show that something is happening.
+///
+/// By default the spinner moves on a frame every time it is drawn. Use `Spinner::with_clock` to
+/// have it move at a steady rate however often it is drawn.
+#[derive(Debug, Clone)]
+pub struct Spinner {
+    frames: &'static [&'static str],
+    index: usize,
+    /// When the spinner started, and how long each frame lasts.
+    clock: Option<(Instant, Duration)>,
+    pub style: Style,
+}
+
+impl Spinner {
+    /// A spinner using one of the built-in sets of frames.
+    pub fn new(style: SpinnerStyle) -> Spinner {
+        Spinner::with_frames(style.frames())
+    }
+
+    /// A spinner using the given frames, which should not be empty.
+    pub fn with_frames(frames: &'static [&'static str]) -> Spinner {
+        Spinner {
+            frames,
+            index: 0,
+            clock: None,
+            style: Style::default(),
+        }
+    }
+
+    /// Move on a frame every `interval`, starting now, rather than every time the spinner is
+    /// drawn.
+    pub fn with_clock(mut self, interval: Duration) -> Spinner {
+        self.clock = Some((Instant::now(), interval));
+        self
+    }
+
+    /// Move on to the next frame.
+    pub fn tick(&mut self) {
+        self.index = (self.index + 1) % self.frames.len().max(1);
+    }
+
+    /// The frame that will be drawn next.
+    pub fn current(&self) -> &'static str {
+        let index = match self.clock {
+            Some((start, interval)) => {
+                let elapsed = start.elapsed().as_nanos() / interval.as_nanos().max(1);
+                (elapsed % self.frames.len().max(1) as u128) as usize
+            }
+            None => self.index,
+        };
+        self.frames.get(index).copied().unwrap_or("")
+    }
+
+    /// Draw the current frame at the top-left of a region of a frame, cut off at the edge of
+    /// the region, and move on to the next frame (unless the spinner runs on a clock).
+    pub fn render_into(&mut self, frame: &mut Frame, region: Rect) {
+        let region = region.intersection(frame.area());
+        if !region.is_empty() {
+            let style = &self.style;
+            frame.write_run(
+                region.row,
+                region.col,
+                region.right(),
+                self.current()
+                    .graphemes(true)
+                    .map(|glyph| style.char(glyph)),
+            );
+        }
+        if self.clock.is_none() {
+            self.tick();
+        }
+    }
+}
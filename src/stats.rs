@@ -0,0 +1,60 @@
+use crate::screen::{Attributes, Color, Frame, Style};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Measurements of the last frame rendered to the terminal (see `App::stats`).
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+pub struct RenderStats {
+    /// The number of cells that differed from the previous frame. A redraw after the terminal
+    /// was set up or resized counts every cell.
+    pub cells_changed: usize,
+    /// The number of bytes written to the terminal, including escapes.
+    pub bytes_written: usize,
+    /// How long it took to work out and write the output.
+    pub duration: Duration,
+    /// Whether the whole screen was redrawn, rather than only the changes.
+    pub full_redraw: bool,
+}
+
+/// How long frames are counted for when working out the frame rate.
+const FPS_WINDOW: Duration = Duration::from_secs(1);
+
+/// Shows the frame rate and the last `RenderStats` in the top right corner of each frame (see
+/// `App::set_debug_overlay`).
+#[derive(Debug, Clone, Default)]
+pub(crate) struct DebugOverlay {
+    /// When each frame in the last `FPS_WINDOW` was drawn.
+    frames: VecDeque<Instant>,
+}
+
+impl DebugOverlay {
+    /// Draw the overlay over a frame that is about to be rendered.
+    ///
+    /// The stats are for the previous frame, since this one hasn't been rendered yet.
+    pub(crate) fn draw(&mut self, frame: &mut Frame, stats: RenderStats) {
+        let now = Instant::now();
+        while self
+            .frames
+            .front()
+            .is_some_and(|&time| now.duration_since(time) >= FPS_WINDOW)
+        {
+            self.frames.pop_front();
+        }
+        self.frames.push_back(now);
+        let text = format!(
+            " {} fps {:.2} ms {} cells {} B{} ",
+            self.frames.len(),
+            stats.duration.as_secs_f64() * 1000.0,
+            stats.cells_changed,
+            stats.bytes_written,
+            if stats.full_redraw { " full" } else { "" },
+        );
+        if frame.rows() == 0 {
+            return;
+        }
+        let style = Style::new(Color::Default, Color::Default, Attributes::REVERSE);
+        let col = frame.columns().saturating_sub(text.len());
+        frame.fill_rect(0, col, 1, text.len(), style.char(' '));
+        frame.write_run(0, col, frame.columns(), text.chars().map(|c| style.char(c)));
+    }
+}
@@ -0,0 +1,227 @@
+//! An async interface to events, enabled with the `stream` feature.
+//!
+//! This doesn't depend on any particular async runtime. `EventStream::poll_next` has the same
+//! signature as `futures::Stream::poll_next`, so it can be adapted with
+//! `futures::stream::poll_fn` and used in e.g. a `tokio::select!` loop.
+use crate::{App, Backend, Error, Event, RESIZE_POLL_INTERVAL};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Condvar, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// A stream of events from an `App`. See `App::event_stream`.
+pub struct EventStream<'a, B: Backend> {
+    app: &'a mut App<B>,
+    /// Wakes the task to check for resizes and ticks, started the first time it is needed.
+    timer: Option<Timer>,
+    /// When the timer was last set to go off, by the backend's clock.
+    wake_at: Option<Instant>,
+}
+
+impl<B: Backend> App<B> {
+    /// Receive events asynchronously.
+    pub fn event_stream(&mut self) -> EventStream<'_, B> {
+        EventStream {
+            app: self,
+            timer: None,
+            wake_at: None,
+        }
+    }
+}
+
+/// A thread that wakes a task at a deadline, which can be moved without starting a new thread.
+struct Timer {
+    shared: Arc<(Mutex<TimerState>, Condvar)>,
+}
+
+#[derive(Default)]
+struct TimerState {
+    deadline: Option<Instant>,
+    waker: Option<Waker>,
+    stopped: bool,
+}
+
+impl Timer {
+    fn spawn() -> Timer {
+        let shared = Arc::new((Mutex::new(TimerState::default()), Condvar::new()));
+        let thread_shared = shared.clone();
+        thread::spawn(move || {
+            let (state, condvar) = &*thread_shared;
+            let mut state = state.lock().unwrap();
+            while !state.stopped {
+                let now = Instant::now();
+                state = match state.deadline {
+                    Some(deadline) if deadline <= now => {
+                        state.deadline = None;
+                        if let Some(waker) = state.waker.take() {
+                            waker.wake();
+                        }
+                        state
+                    }
+                    Some(deadline) => condvar.wait_timeout(state, deadline - now).unwrap().0,
+                    None => condvar.wait(state).unwrap(),
+                };
+            }
+        });
+        Timer { shared }
+    }
+
+    /// Wake `waker` after `wait`, replacing any earlier request.
+    fn set(&self, wait: Duration, waker: &Waker) {
+        let (state, condvar) = &*self.shared;
+        let mut state = state.lock().unwrap();
+        state.deadline = Some(Instant::now() + wait);
+        match &mut state.waker {
+            Some(old) => old.clone_from(waker),
+            None => state.waker = Some(waker.clone()),
+        }
+        condvar.notify_one();
+    }
+
+    /// Whether the last request has gone off.
+    fn fired(&self) -> bool {
+        self.shared.0.lock().unwrap().deadline.is_none()
+    }
+}
+
+impl Drop for Timer {
+    fn drop(&mut self) {
+        let (state, condvar) = &*self.shared;
+        state.lock().unwrap().stopped = true;
+        condvar.notify_one();
+    }
+}
+
+impl<'a, B: Backend> EventStream<'a, B> {
+    /// Attempt to get the next event, registering the current task to be woken when one might
+    /// be available.
+    ///
+    /// The stream never ends, so this never returns `Poll::Ready(None)`.
+    pub fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Event, Error>>> {
+        let this = self.get_mut();
+        let app = &mut *this.app;
+        // The timer waits in real time, so once it goes off, let the backend's clock catch up,
+        // for backends that simulate time.
+        if this.timer.as_ref().is_some_and(Timer::fired) {
+            if let Some(wake_at) = this.wake_at.take() {
+                let now = app.backend.now();
+                if now < wake_at {
+                    app.backend.sleep(wake_at - now);
+                }
+            }
+        }
+        if let Some(evt) = app.check_job_control() {
+            return Poll::Ready(Some(evt.map_err(Error::from)));
+        }
+        if let Some(evt) = app.queued.pop_front() {
+            return Poll::Ready(Some(Ok(evt)));
+        }
+        if let Some(resize) = app.check_resize() {
+            return Poll::Ready(Some(resize.map_err(Error::from)));
+        }
+        if let Some(tick) = app.check_tick() {
+            return Poll::Ready(Some(Ok(tick)));
+        }
+        // Register for input before checking for it, so none is missed in between.
+        app.backend.set_waker(cx.waker().clone());
+        if let Some(evt) = app.backend.next_event() {
+            return Poll::Ready(Some(evt.map_err(Error::from)));
+        }
+        // Make sure we're woken to check for resizes and ticks.
+        let now = app.backend.now();
+        let mut wait = RESIZE_POLL_INTERVAL;
+        if let Some(ticker) = &app.ticker {
+            wait = wait.min(ticker.next.saturating_duration_since(now));
+        }
+        this.wake_at = Some(now + wait);
+        this.timer
+            .get_or_insert_with(Timer::spawn)
+            .set(wait, cx.waker());
+        Poll::Pending
+    }
+
+    /// A future resolving to the next event.
+    pub fn next_event(&mut self) -> NextEvent<'_, 'a, B> {
+        NextEvent { stream: self }
+    }
+}
+
+/// The future returned by `EventStream::next_event`.
+pub struct NextEvent<'s, 'a, B: Backend> {
+    stream: &'s mut EventStream<'a, B>,
+}
+
+impl<'s, 'a, B: Backend> Future for NextEvent<'s, 'a, B> {
+    type Output = Option<Result<Event, Error>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut *self.get_mut().stream).poll_next(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::task::Wake;
+
+    struct ThreadWaker(thread::Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    fn block_on<F: Future>(fut: F) -> F::Output {
+        let mut fut = Box::pin(fut);
+        let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        loop {
+            if let Poll::Ready(out) = fut.as_mut().poll(&mut cx) {
+                return out;
+            }
+            thread::park();
+        }
+    }
+
+    #[test]
+    fn the_timer_wakes_the_task_for_ticks() {
+        let mut app = App::builder()
+            .tick_rate(Duration::from_millis(20))
+            .test_backend(10, 5);
+        let mut stream = app.event_stream();
+        for _ in 0..3 {
+            let evt = block_on(stream.next_event()).unwrap().unwrap();
+            assert_eq!(evt, Event::Tick);
+        }
+    }
+
+    #[test]
+    fn ticks_go_by_the_backend_clock() {
+        let mut app = App::builder()
+            .tick_rate(Duration::from_millis(20))
+            .build_with_backend(crate::SimBackend::new(10, 5))
+            .unwrap();
+        let mut stream = app.event_stream();
+        for _ in 0..3 {
+            let evt = block_on(stream.next_event()).unwrap().unwrap();
+            assert_eq!(evt, Event::Tick);
+        }
+        assert_eq!(app.backend().elapsed(), Duration::from_millis(60));
+    }
+
+    #[test]
+    fn backend_input_is_returned() {
+        let mut app = App::builder().test_backend(10, 5);
+        app.backend_mut()
+            .push_event(Event::Key(crate::Key::Char('a')));
+        let evt = block_on(app.event_stream().next_event()).unwrap().unwrap();
+        assert_eq!(evt, Event::Key(crate::Key::Char('a')));
+    }
+}
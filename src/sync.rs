@@ -0,0 +1,30 @@
+use std::env;
+
+/// Begin a synchronized update: the terminal holds off drawing until the update ends.
+pub(crate) const BEGIN: &str = "\x1b[?2026h";
+/// End a synchronized update.
+pub(crate) const END: &str = "\x1b[?2026l";
+
+/// Guess whether the terminal supports synchronized output (DEC private mode 2026) from the
+/// `TERM` and `TERM_PROGRAM` environment variables.
+///
+/// Terminals that don't support it should ignore the escapes anyway, so this only needs to
+/// avoid sending them to terminals that might misbehave.
+pub(crate) fn detect() -> bool {
+    let term = env::var("TERM").unwrap_or_default();
+    let term_program = env::var("TERM_PROGRAM").unwrap_or_default();
+    from_env(&term, &term_program)
+}
+
+fn from_env(term: &str, term_program: &str) -> bool {
+    const TERMS: [&str; 6] = [
+        "alacritty",
+        "contour",
+        "foot",
+        "ghostty",
+        "kitty",
+        "wezterm",
+    ];
+    const PROGRAMS: [&str; 3] = ["WezTerm", "ghostty", "iTerm.app"];
+    TERMS.iter().any(|name| term.contains(name)) || PROGRAMS.contains(&term_program)
+}
@@ -0,0 +1,91 @@
+use crate::layout::{resolve, Constraint};
+use crate::rect::Rect;
+use crate::screen::{Color, Frame, Style};
+use crate::span::Line;
+
+/// A table of styled text, with an optional header row.
+///
+/// Cells that are too wide for their column are cut off, with `…` in the last cell.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Hash)]
+pub struct Table<'a> {
+    pub header: Option<Vec<Line<'a>>>,
+    pub rows: Vec<Vec<Line<'a>>>,
+    /// The width of each column. Cells beyond the last constraint aren't shown.
+    pub widths: Vec<Constraint>,
+    /// The number of blank cells between columns.
+    pub column_spacing: usize,
+    /// Applied to the header row, underneath the styles of its spans.
+    pub header_style: Style,
+    /// The index of a row (not counting the header) to draw with `highlight_style`.
+    pub highlight: Option<usize>,
+    /// Applied to the highlighted row, underneath the styles of its spans.
+    pub highlight_style: Style,
+}
+
+impl<'a> Table<'a> {
+    pub fn new(rows: Vec<Vec<Line<'a>>>, widths: Vec<Constraint>) -> Table<'a> {
+        Table {
+            rows,
+            widths,
+            column_spacing: 1,
+            ..Table::default()
+        }
+    }
+
+    /// Work out the width of each column, given the width of the table.
+    pub fn column_widths(&self, width: usize) -> Vec<usize> {
+        let spacing = self.column_spacing * self.widths.len().saturating_sub(1);
+        resolve(&self.widths, width.saturating_sub(spacing))
+    }
+
+    /// Draw the table into a region of a frame. Rows that don't fit are cut off.
+    pub fn render_into(&self, frame: &mut Frame, region: Rect) {
+        let region = region.intersection(frame.area());
+        let widths = self.column_widths(region.width);
+        let plain = Style::default();
+        let header = self.header.iter().map(|row| (row, &self.header_style));
+        let rows = self.rows.iter().enumerate().map(|(i, row)| {
+            let style = if self.highlight == Some(i) {
+                &self.highlight_style
+            } else {
+                &plain
+            };
+            (row, style)
+        });
+        for (row, (cells, style)) in (region.row..region.bottom()).zip(header.chain(rows)) {
+            if *style != plain {
+                frame.fill_rect(row, region.col, 1, region.width, style.char(' '));
+            }
+            let mut col = region.col;
+            for (line, &width) in cells.iter().zip(&widths) {
+                let end = (col + width).min(region.right());
+                if col >= end {
+                    break;
+                }
+                frame.write_line_truncated(row, col, end, line);
+                col = end + self.column_spacing;
+            }
+            if *style != plain {
+                apply_style(frame, row, region, style);
+            }
+        }
+    }
+}
+
+/// Apply a style to a row, underneath whatever styles the cells already have: default colors
+/// are replaced, and attributes are added.
+pub(crate) fn apply_style(frame: &mut Frame, row: usize, region: Rect, style: &Style) {
+    for col in region.col..region.right() {
+        let cell = &mut frame[(row, col)];
+        if cell.color_fg == Color::Default {
+            cell.color_fg = style.color_fg;
+        }
+        if cell.color_bg == Color::Default {
+            cell.color_bg = style.color_bg;
+        }
+        cell.attrs.insert(style.attrs);
+        if cell.link.is_none() {
+            cell.link = style.link.clone();
+        }
+    }
+}
@@ -0,0 +1,493 @@
+//! Reading the capabilities of the terminal from its terminfo entry.
+use crate::color::ColorSupport;
+use crate::screen::Attributes;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+/// The magic number of terminfo files with 16-bit numbers.
+const MAGIC_LEGACY: u16 = 0o432;
+/// The magic number of terminfo files with 32-bit numbers.
+const MAGIC_32BIT: u16 = 0o1036;
+
+/// Indexes of the numbers and strings we use, in the order terminfo stores them.
+const MAX_COLORS: usize = 13;
+const CURSOR_ADDRESS: usize = 10;
+const CURSOR_INVISIBLE: usize = 13;
+const ENTER_BLINK_MODE: usize = 26;
+const ENTER_BOLD_MODE: usize = 27;
+const ENTER_CA_MODE: usize = 28;
+const ENTER_DIM_MODE: usize = 30;
+const ENTER_REVERSE_MODE: usize = 34;
+const ENTER_UNDERLINE_MODE: usize = 36;
+const ENTER_ITALICS_MODE: usize = 311;
+
+/// What the terminal can do, as far as drawing is concerned (see `App::capabilities`).
+///
+/// Unsupported features are left out of the output: colors are downgraded, attributes the
+/// terminal doesn't have are dropped, and so on.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Capabilities {
+    pub colors: ColorSupport,
+    /// The attributes the terminal can display.
+    pub attributes: Attributes,
+    /// Whether the cursor can be moved to any position. Nothing can be drawn without this, so
+    /// `AppBuilder::build` assumes xterm escapes instead, or fails with
+    /// `Error::UnsupportedTerminal` if `AppBuilder::require_cursor_addressing` is set.
+    pub cursor_addressing: bool,
+    /// Whether the cursor can be hidden.
+    pub hide_cursor: bool,
+    /// Whether there is an alternate screen to draw on (see `AppBuilder::alternate_screen`).
+    pub alternate_screen: bool,
+    /// Whether the terminal can show sixel graphics (see `Draw::image`).
+    pub sixel: bool,
+    /// Whether the terminal supports the kitty graphics protocol (see `Draw::kitty_image`).
+    pub kitty_graphics: bool,
+}
+
+impl Capabilities {
+    /// A terminal that understands the usual xterm escapes, with the given colors.
+    ///
+    /// Not all such terminals can show images, so none are drawn.
+    pub fn xterm(colors: ColorSupport) -> Capabilities {
+        Capabilities {
+            colors,
+            attributes: Attributes::from_bits(!0),
+            cursor_addressing: true,
+            hide_cursor: true,
+            alternate_screen: true,
+            sixel: false,
+            kitty_graphics: false,
+        }
+    }
+
+    /// A terminal that can't do anything but show text, such as `TERM=dumb`.
+    pub fn dumb() -> Capabilities {
+        Capabilities {
+            colors: ColorSupport::NoColor,
+            attributes: Attributes::empty(),
+            cursor_addressing: false,
+            hide_cursor: false,
+            alternate_screen: false,
+            sixel: false,
+            kitty_graphics: false,
+        }
+    }
+
+    /// Look up the capabilities of the terminal named by the `TERM` environment variable in the
+    /// terminfo database.
+    ///
+    /// If `TERM` isn't set or is `dumb`, the terminal is assumed to be dumb. If there is no
+    /// terminfo entry for it, it is assumed to understand xterm escapes. Colors are detected as
+    /// in `ColorSupport::detect`, except that the terminfo entry is used when `COLORTERM`
+    /// doesn't say.
+    ///
+    /// terminfo doesn't cover graphics, so support for them is guessed from `TERM` and
+    /// `TERM_PROGRAM`.
+    pub fn detect() -> Capabilities {
+        let term = env::var("TERM").unwrap_or_default();
+        if term.is_empty() || term == "dumb" {
+            return Capabilities::dumb();
+        }
+        let term_program = env::var("TERM_PROGRAM").unwrap_or_default();
+        let kitty_window = env::var_os("KITTY_WINDOW_ID").is_some();
+        let (sixel, kitty_graphics) = graphics_from_env(&term, &term_program, kitty_window);
+        let mut colors = ColorSupport::detect();
+        let entry = match find_entry(&term).and_then(|bytes| Entry::parse(&bytes)) {
+            Some(entry) => entry,
+            None => {
+                return Capabilities {
+                    sixel,
+                    kitty_graphics,
+                    ..Capabilities::xterm(colors)
+                }
+            }
+        };
+        let colorterm = env::var("COLORTERM").unwrap_or_default();
+        let truecolor = colorterm == "truecolor" || colorterm == "24bit";
+        if colors != ColorSupport::NoColor && !truecolor {
+            colors = entry.colors();
+        }
+        Capabilities {
+            colors,
+            attributes: entry.attributes(),
+            cursor_addressing: entry.has_string(CURSOR_ADDRESS),
+            hide_cursor: entry.has_string(CURSOR_INVISIBLE),
+            alternate_screen: entry.has_string(ENTER_CA_MODE),
+            sixel,
+            kitty_graphics,
+        }
+    }
+}
+
+/// Guess whether the terminal supports sixel graphics and the kitty graphics protocol, in that
+/// order.
+fn graphics_from_env(term: &str, term_program: &str, kitty_window: bool) -> (bool, bool) {
+    const SIXEL_TERMS: [&str; 4] = ["contour", "foot", "mlterm", "wezterm"];
+    const SIXEL_PROGRAMS: [&str; 2] = ["WezTerm", "iTerm.app"];
+    const KITTY_TERMS: [&str; 3] = ["ghostty", "kitty", "wezterm"];
+    const KITTY_PROGRAMS: [&str; 2] = ["WezTerm", "ghostty"];
+    let sixel = SIXEL_TERMS.iter().any(|name| term.contains(name))
+        || SIXEL_PROGRAMS.contains(&term_program);
+    // A terminal multiplexer running in kitty would pass on the window id, but not images.
+    let multiplexed = term.starts_with("screen") || term.starts_with("tmux");
+    let kitty = (kitty_window && !multiplexed)
+        || KITTY_TERMS.iter().any(|name| term.contains(name))
+        || KITTY_PROGRAMS.contains(&term_program);
+    (sixel, kitty)
+}
+
+/// Read the compiled terminfo entry for a terminal, from the usual places.
+fn find_entry(term: &str) -> Option<Vec<u8>> {
+    let first = term.chars().next()?;
+    if term.contains('/') {
+        return None;
+    }
+    let mut dirs = Vec::new();
+    if let Some(dir) = env::var_os("TERMINFO") {
+        dirs.push(PathBuf::from(dir));
+    }
+    if let Some(home) = env::var_os("HOME") {
+        dirs.push(PathBuf::from(home).join(".terminfo"));
+    }
+    if let Ok(list) = env::var("TERMINFO_DIRS") {
+        dirs.extend(
+            list.split(':')
+                .filter(|dir| !dir.is_empty())
+                .map(PathBuf::from),
+        );
+    }
+    for dir in [
+        "/etc/terminfo",
+        "/lib/terminfo",
+        "/usr/share/terminfo",
+        "/usr/lib/terminfo",
+    ] {
+        dirs.push(PathBuf::from(dir));
+    }
+    // Entries are filed under their first letter, or its hex code on some systems (e.g. macOS).
+    let subdirs = [first.to_string(), format!("{:02x}", u32::from(first))];
+    dirs.iter()
+        .flat_map(|dir| subdirs.iter().map(move |sub| dir.join(sub).join(term)))
+        .find_map(|path| fs::read(path).ok())
+}
+
+/// The parts of a compiled terminfo entry we look at.
+#[derive(Debug, Default)]
+struct Entry {
+    numbers: Vec<i32>,
+    strings: Vec<Option<Vec<u8>>>,
+    /// Extended (user-defined) capabilities, by name. Numbers and strings are only recorded as
+    /// being present.
+    extended: Vec<String>,
+}
+
+impl Entry {
+    /// Parse a compiled terminfo entry (see `term(5)`), returning `None` if it is malformed.
+    fn parse(bytes: &[u8]) -> Option<Entry> {
+        let mut reader = Reader { bytes, pos: 0 };
+        let number_size = match reader.u16()? {
+            MAGIC_LEGACY => 2,
+            MAGIC_32BIT => 4,
+            _ => return None,
+        };
+        let name_size = reader.count()?;
+        let bool_count = reader.count()?;
+        let num_count = reader.count()?;
+        let str_count = reader.count()?;
+        let table_size = reader.count()?;
+        reader.skip(name_size + bool_count)?;
+        reader.align();
+        let numbers = (0..num_count)
+            .map(|_| reader.number(number_size))
+            .collect::<Option<Vec<_>>>()?;
+        let offsets = (0..str_count)
+            .map(|_| reader.u16())
+            .collect::<Option<Vec<_>>>()?;
+        let table = reader.take(table_size)?;
+        let strings = offsets
+            .iter()
+            .map(|&offset| string_at(table, offset))
+            .collect();
+        let mut entry = Entry {
+            numbers,
+            strings,
+            extended: Vec::new(),
+        };
+        reader.align();
+        // The extended section is optional, so ignore it if it isn't there or is malformed.
+        if let Some(extended) = reader.extended(number_size) {
+            entry.extended = extended;
+        }
+        Some(entry)
+    }
+
+    fn number(&self, idx: usize) -> Option<i32> {
+        self.numbers.get(idx).copied().filter(|&n| n >= 0)
+    }
+
+    fn has_string(&self, idx: usize) -> bool {
+        matches!(self.strings.get(idx), Some(Some(_)))
+    }
+
+    fn has_extended(&self, name: &str) -> bool {
+        self.extended.iter().any(|ext| ext == name)
+    }
+
+    fn colors(&self) -> ColorSupport {
+        if self.has_extended("RGB") || self.has_extended("Tc") {
+            return ColorSupport::TrueColor;
+        }
+        match self.number(MAX_COLORS) {
+            Some(n) if n >= 256 => ColorSupport::Indexed256,
+            Some(n) if n >= 8 => ColorSupport::Ansi16,
+            _ => ColorSupport::NoColor,
+        }
+    }
+
+    fn attributes(&self) -> Attributes {
+        let mut attrs = Attributes::empty();
+        for (idx, attr) in [
+            (ENTER_BOLD_MODE, Attributes::BOLD),
+            (ENTER_DIM_MODE, Attributes::DIM),
+            (ENTER_ITALICS_MODE, Attributes::ITALIC),
+            (ENTER_UNDERLINE_MODE, Attributes::UNDERLINE),
+            (ENTER_REVERSE_MODE, Attributes::REVERSE),
+            (ENTER_BLINK_MODE, Attributes::BLINK),
+        ] {
+            if self.has_string(idx) {
+                attrs.insert(attr);
+            }
+        }
+        if self.has_extended("smxx") {
+            attrs.insert(Attributes::STRIKETHROUGH);
+        }
+        attrs
+    }
+}
+
+/// The nul-terminated string at an offset into a string table, or `None` if the capability is
+/// missing (a negative offset) or the offset is bad.
+fn string_at(table: &[u8], offset: u16) -> Option<Vec<u8>> {
+    if offset >= 0xfffe {
+        return None;
+    }
+    let rest = table.get(usize::from(offset)..)?;
+    let end = rest.iter().position(|&b| b == 0)?;
+    Some(rest[..end].to_vec())
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        let bytes = self.bytes.get(self.pos..self.pos.checked_add(len)?)?;
+        self.pos += len;
+        Some(bytes)
+    }
+
+    fn skip(&mut self, len: usize) -> Option<()> {
+        self.take(len).map(|_| ())
+    }
+
+    /// Sections start on an even byte.
+    fn align(&mut self) {
+        self.pos += self.pos % 2;
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        let bytes = self.take(2)?;
+        Some(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    /// A count or size from a header, where -1 (0xffff) means none.
+    fn count(&mut self) -> Option<usize> {
+        match self.u16()? {
+            0xffff => Some(0),
+            n if n > 0x7fff => None,
+            n => Some(usize::from(n)),
+        }
+    }
+
+    fn number(&mut self, size: usize) -> Option<i32> {
+        let bytes = self.take(size)?;
+        Some(match size {
+            2 => i32::from(i16::from_le_bytes([bytes[0], bytes[1]])),
+            _ => i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+        })
+    }
+
+    /// Read the names of the extended capabilities.
+    fn extended(&mut self, number_size: usize) -> Option<Vec<String>> {
+        let bool_count = self.count()?;
+        let num_count = self.count()?;
+        let str_count = self.count()?;
+        let _items = self.count()?;
+        let table_size = self.count()?;
+        self.skip(bool_count)?;
+        self.align();
+        self.skip(num_count * number_size)?;
+        let value_offsets = (0..str_count)
+            .map(|_| self.u16())
+            .collect::<Option<Vec<_>>>()?;
+        let name_offsets = (0..bool_count + num_count + str_count)
+            .map(|_| self.u16())
+            .collect::<Option<Vec<_>>>()?;
+        let table = self.take(table_size)?;
+        // The names come after the string values in the table.
+        let names_start = value_offsets
+            .iter()
+            .filter_map(|&offset| {
+                let value = string_at(table, offset)?;
+                Some(usize::from(offset) + value.len() + 1)
+            })
+            .max()
+            .unwrap_or(0);
+        let names = table.get(names_start..)?;
+        name_offsets
+            .iter()
+            .map(|&offset| {
+                let name = string_at(names, offset)?;
+                String::from_utf8(name).ok()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn u16s(bytes: &mut Vec<u8>, values: &[u16]) {
+        for value in values {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+    }
+
+    /// Compile a terminfo entry with the given numbers, strings (by index) and extended
+    /// booleans.
+    fn compile(
+        magic: u16,
+        numbers: &[i32],
+        strings: &[(usize, &str)],
+        extended: &[&str],
+    ) -> Vec<u8> {
+        let number_size = if magic == MAGIC_32BIT { 4 } else { 2 };
+        let str_count = strings.iter().map(|&(idx, _)| idx + 1).max().unwrap_or(0);
+        let mut offsets = vec![0xffff_u16; str_count];
+        let mut table = Vec::new();
+        for &(idx, value) in strings {
+            offsets[idx] = table.len() as u16;
+            table.extend_from_slice(value.as_bytes());
+            table.push(0);
+        }
+        let mut bytes = Vec::new();
+        let names = b"test\0";
+        let header = [
+            magic,
+            5,
+            0,
+            numbers.len() as u16,
+            str_count as u16,
+            table.len() as u16,
+        ];
+        u16s(&mut bytes, &header);
+        bytes.extend_from_slice(names);
+        bytes.push(0);
+        for &n in numbers {
+            bytes.extend_from_slice(&n.to_le_bytes()[..number_size]);
+        }
+        u16s(&mut bytes, &offsets);
+        bytes.extend_from_slice(&table);
+        if extended.is_empty() {
+            return bytes;
+        }
+        if bytes.len() % 2 == 1 {
+            bytes.push(0);
+        }
+        let mut names = Vec::new();
+        let mut name_offsets = Vec::new();
+        for name in extended {
+            name_offsets.push(names.len() as u16);
+            names.extend_from_slice(name.as_bytes());
+            names.push(0);
+        }
+        let count = extended.len() as u16;
+        u16s(&mut bytes, &[count, 0, 0, count, names.len() as u16]);
+        bytes.extend(extended.iter().map(|_| 1));
+        if bytes.len() % 2 == 1 {
+            bytes.push(0);
+        }
+        u16s(&mut bytes, &name_offsets);
+        bytes.extend_from_slice(&names);
+        bytes
+    }
+
+    #[test]
+    fn capabilities_are_read() {
+        let strings = [
+            (CURSOR_ADDRESS, "\x1b[%i%p1%d;%p2%dH"),
+            (ENTER_BOLD_MODE, "\x1b[1m"),
+            (ENTER_UNDERLINE_MODE, "\x1b[4m"),
+        ];
+        let bytes = compile(
+            MAGIC_LEGACY,
+            &[80, -1, 24, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 256],
+            &strings,
+            &[],
+        );
+        let entry = Entry::parse(&bytes).unwrap();
+        assert_eq!(entry.colors(), ColorSupport::Indexed256);
+        assert_eq!(entry.attributes(), Attributes::BOLD | Attributes::UNDERLINE);
+        assert!(entry.has_string(CURSOR_ADDRESS));
+        assert!(!entry.has_string(CURSOR_INVISIBLE));
+        assert!(!entry.has_string(ENTER_ITALICS_MODE));
+        assert_eq!(entry.number(0), Some(80));
+        assert_eq!(entry.number(1), None);
+    }
+
+    #[test]
+    fn extended_capabilities_are_read() {
+        let strings = [(ENTER_ITALICS_MODE, "\x1b[3m")];
+        let numbers = [0; MAX_COLORS + 1];
+        let bytes = compile(MAGIC_32BIT, &numbers, &strings, &["Tc", "smxx"]);
+        let entry = Entry::parse(&bytes).unwrap();
+        assert_eq!(entry.extended, ["Tc", "smxx"]);
+        assert_eq!(entry.colors(), ColorSupport::TrueColor);
+        assert_eq!(
+            entry.attributes(),
+            Attributes::ITALIC | Attributes::STRIKETHROUGH
+        );
+    }
+
+    #[test]
+    fn malformed_entries_are_rejected() {
+        let bytes = compile(MAGIC_LEGACY, &[8], &[(ENTER_BOLD_MODE, "\x1b[1m")], &["Tc"]);
+        assert!(Entry::parse(&bytes[..bytes.len() - 20]).is_none());
+        assert!(Entry::parse(&[0, 0]).is_none());
+        assert!(Entry::parse(b"not a terminfo file").is_none());
+        // A broken extended section is ignored.
+        let entry = Entry::parse(&bytes[..bytes.len() - 2]).unwrap();
+        assert!(entry.extended.is_empty());
+        assert!(entry.has_string(ENTER_BOLD_MODE));
+    }
+
+    #[test]
+    fn graphics_are_guessed_from_the_terminal() {
+        assert_eq!(
+            graphics_from_env("xterm-256color", "", false),
+            (false, false)
+        );
+        assert_eq!(graphics_from_env("xterm-kitty", "", false), (false, true));
+        assert_eq!(
+            graphics_from_env("xterm-256color", "WezTerm", false),
+            (true, true)
+        );
+        assert_eq!(graphics_from_env("xterm-256color", "", true), (false, true));
+        assert_eq!(graphics_from_env("tmux-256color", "", true), (false, false));
+        assert_eq!(graphics_from_env("foot", "", false), (true, false));
+    }
+}
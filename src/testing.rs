@@ -0,0 +1,256 @@
+//! Snapshot testing: checking frames against copies saved in files.
+//!
+//! ```no_run
+//! # use termbuffer::{assert_frame_snapshot, App, TestBackend};
+//! let mut app = App::builder().build_with_backend(TestBackend::new(20, 5)).unwrap();
+//! app.draw().set_str(0, 0, "Hello", Default::default(), Default::default());
+//! // Compares against `tests/snapshots/hello.frame` in the crate being tested.
+//! assert_frame_snapshot!(app.frame(), "hello");
+//! ```
+//!
+//! Snapshots are saved with `Frame::save`. To create or update them, run the tests with the
+//! `TERMBUFFER_UPDATE_SNAPSHOTS` environment variable set to `1`, and check the new frames
+//! look right.
+use crate::link::Link;
+use crate::screen::{Attributes, CellChange, Frame};
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// The environment variable that makes snapshot checks save the frame instead of comparing it.
+pub const UPDATE_VAR: &str = "TERMBUFFER_UPDATE_SNAPSHOTS";
+
+/// The most cells listed when a frame doesn't match its snapshot.
+const MAX_LISTED: usize = 20;
+
+/// Check a frame against a snapshot saved at `path`, as `assert_frame_snapshot!` does.
+///
+/// Returns a description of the differences if it doesn't match, or if there is no snapshot.
+/// If `TERMBUFFER_UPDATE_SNAPSHOTS` is set, the frame is saved as the snapshot instead.
+pub fn check_snapshot(frame: &Frame, path: impl AsRef<Path>) -> Result<(), String> {
+    let path = path.as_ref();
+    if update_snapshots() {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| error(path, "create the directory for", e))?;
+        }
+        return frame.save(path).map_err(|e| error(path, "save", e));
+    }
+    let expected = match Frame::load(path) {
+        Ok(expected) => expected,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            return Err(format!(
+                "no snapshot at {} (set {}=1 to create it)",
+                path.display(),
+                UPDATE_VAR
+            ))
+        }
+        Err(e) => return Err(error(path, "load", e)),
+    };
+    match diff_frames(&expected, frame) {
+        None => Ok(()),
+        Some(diff) => Err(format!(
+            "frame doesn't match the snapshot at {} (set {}=1 to update it)\n{}",
+            path.display(),
+            UPDATE_VAR,
+            diff
+        )),
+    }
+}
+
+/// Describe the differences between two frames, with the text of the rows that differ and
+/// then the cells (see `Frame::diff`), or `None` if they are the same.
+pub fn diff_frames(expected: &Frame, actual: &Frame) -> Option<String> {
+    let mut diff = String::new();
+    if (expected.rows(), expected.columns()) != (actual.rows(), actual.columns()) {
+        writeln!(
+            diff,
+            "size: expected {}x{}, found {}x{}",
+            expected.columns(),
+            expected.rows(),
+            actual.columns(),
+            actual.rows()
+        )
+        .unwrap();
+    }
+    let changes = expected.diff(actual);
+    let mut rows: Vec<usize> = changes.iter().map(|change| change.row).collect();
+    rows.dedup();
+    if actual.rows() < expected.rows() {
+        rows.extend(actual.rows()..expected.rows());
+    }
+    for row in rows {
+        writeln!(diff, "row {}:", row).unwrap();
+        writeln!(diff, "  - {}", row_text(expected, row)).unwrap();
+        writeln!(diff, "  + {}", row_text(actual, row)).unwrap();
+    }
+    if diff.is_empty() {
+        return None;
+    }
+    if !changes.is_empty() {
+        writeln!(diff, "cells:").unwrap();
+    }
+    for change in changes.iter().take(MAX_LISTED) {
+        writeln!(
+            diff,
+            "  {},{}: {}",
+            change.row,
+            change.col,
+            describe_change(change)
+        )
+        .unwrap();
+    }
+    if changes.len() > MAX_LISTED {
+        writeln!(diff, "  ...and {} more", changes.len() - MAX_LISTED).unwrap();
+    }
+    Some(diff)
+}
+
+/// The text of a row, quoted, or a note that the frame doesn't have it.
+fn row_text(frame: &Frame, row: usize) -> String {
+    if row >= frame.rows() {
+        return "(missing)".into();
+    }
+    let text: String = frame
+        .row(row)
+        .iter()
+        .filter(|ch| !ch.is_continuation())
+        .map(|ch| ch.glyph.as_str())
+        .collect();
+    format!("{:?}", text)
+}
+
+/// What changed about a cell, e.g. `'a' -> 'b', fg Red -> Blue`.
+fn describe_change(CellChange { old, new, .. }: &CellChange) -> String {
+    let mut changes = Vec::new();
+    if old.glyph != new.glyph {
+        changes.push(format!(
+            "{:?} -> {:?}",
+            old.glyph.as_str(),
+            new.glyph.as_str()
+        ));
+    }
+    if old.color_fg != new.color_fg {
+        changes.push(format!("fg {:?} -> {:?}", old.color_fg, new.color_fg));
+    }
+    if old.color_bg != new.color_bg {
+        changes.push(format!("bg {:?} -> {:?}", old.color_bg, new.color_bg));
+    }
+    if old.attrs != new.attrs {
+        changes.push(format!(
+            "attrs {} -> {}",
+            attr_names(old.attrs),
+            attr_names(new.attrs)
+        ));
+    }
+    if old.link != new.link {
+        let url = |link: &Option<Link>| link.as_ref().map_or("none", Link::url).to_string();
+        changes.push(format!("link {} -> {}", url(&old.link), url(&new.link)));
+    }
+    changes.join(", ")
+}
+
+fn attr_names(attrs: Attributes) -> String {
+    const NAMES: [(Attributes, &str); 7] = [
+        (Attributes::BOLD, "bold"),
+        (Attributes::DIM, "dim"),
+        (Attributes::ITALIC, "italic"),
+        (Attributes::UNDERLINE, "underline"),
+        (Attributes::REVERSE, "reverse"),
+        (Attributes::STRIKETHROUGH, "strikethrough"),
+        (Attributes::BLINK, "blink"),
+    ];
+    if attrs.is_empty() {
+        return "none".into();
+    }
+    NAMES
+        .iter()
+        .filter(|(attr, _)| attrs.contains(*attr))
+        .map(|(_, name)| *name)
+        .collect::<Vec<_>>()
+        .join("|")
+}
+
+fn update_snapshots() -> bool {
+    std::env::var_os(UPDATE_VAR).is_some_and(|value| !value.is_empty() && value != "0")
+}
+
+fn error(path: &Path, action: &str, e: io::Error) -> String {
+    format!(
+        "couldn't {} the snapshot at {}: {}",
+        action,
+        path.display(),
+        e
+    )
+}
+
+/// Assert that a frame matches a snapshot saved in a file, printing the differences if it
+/// doesn't (see the `testing` module).
+///
+/// With a name, the snapshot is `tests/snapshots/<name>.frame` in the crate being tested.
+/// Otherwise, give the path of the snapshot.
+#[macro_export]
+macro_rules! assert_frame_snapshot {
+    ($frame:expr, path = $path:expr) => {
+        if let Err(msg) = $crate::testing::check_snapshot(&$frame, $path) {
+            panic!("{}", msg);
+        }
+    };
+    ($frame:expr, $name:expr) => {
+        $crate::assert_frame_snapshot!(
+            $frame,
+            path = ::std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("tests")
+                .join("snapshots")
+                .join(format!("{}.frame", $name))
+        )
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::screen::Color;
+    use crate::App;
+    use std::path::PathBuf;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("termbuffer-{}-{}.frame", std::process::id(), name))
+    }
+
+    #[test]
+    fn diffs_list_rows_and_cells() {
+        let expected = Frame::new(2, 4);
+        let mut actual = Frame::new(2, 4);
+        actual.set_str(1, 1, "ab", Color::Red, Color::Default);
+        let diff = diff_frames(&expected, &actual).unwrap();
+        assert_eq!(
+            diff,
+            "row 1:\n  - \"    \"\n  + \" ab \"\ncells:\n  1,1: \" \" -> \"a\", fg Default -> Red\n  \
+             1,2: \" \" -> \"b\", fg Default -> Red\n"
+        );
+        assert_eq!(diff_frames(&actual, &actual.clone()), None);
+        let diff = diff_frames(&Frame::new(3, 4), &expected).unwrap();
+        assert!(diff.starts_with("size: expected 4x3, found 4x2\nrow 2:\n"));
+        assert!(diff.contains("  + (missing)\n"));
+    }
+
+    #[test]
+    fn snapshots_are_checked() {
+        let mut app = App::builder().test_backend(8, 2);
+        app.draw()
+            .set_str(0, 0, "Hello", Color::Green, Color::Default);
+        let path = temp_path("hello");
+        app.frame().save(&path).unwrap();
+        assert_frame_snapshot!(app.frame(), path = &path);
+
+        app.draw()
+            .set_str(0, 0, "Help", Color::Green, Color::Default);
+        let error = check_snapshot(app.frame(), &path).unwrap_err();
+        fs::remove_file(&path).unwrap();
+        assert!(error.contains("row 0:\n  - \"Hello   \"\n  + \"Help    \"\n"));
+        assert!(error.contains("0,3: \"l\" -> \"p\"\n"));
+        let error = check_snapshot(app.frame(), &path).unwrap_err();
+        assert!(error.starts_with("no snapshot at "));
+    }
+}
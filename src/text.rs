@@ -0,0 +1,212 @@
+use crate::glyph::Glyph;
+use crate::rect::Rect;
+use crate::screen::{Attributes, Char, Color, Frame};
+use std::mem;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Horizontal alignment of text.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+pub enum Align {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+/// Vertical alignment of text.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+pub enum VerticalAlign {
+    #[default]
+    Top,
+    Middle,
+    Bottom,
+}
+
+/// Options for `Frame::draw_text`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct TextOptions {
+    pub align: Align,
+    pub vertical_align: VerticalAlign,
+    /// Whether to wrap lines at word boundaries. If false, long lines are cut off at the edge of
+    /// the region.
+    pub wrap: bool,
+    pub color_fg: Color,
+    pub color_bg: Color,
+    pub attrs: Attributes,
+}
+
+impl Default for TextOptions {
+    fn default() -> Self {
+        TextOptions {
+            align: Align::Left,
+            vertical_align: VerticalAlign::Top,
+            wrap: true,
+            color_fg: Color::default(),
+            color_bg: Color::default(),
+            attrs: Attributes::empty(),
+        }
+    }
+}
+
+impl Frame {
+    /// Draw text into a region of the frame, wrapping and aligning it as requested.
+    ///
+    /// Newlines in the text always start a new line. Any text that doesn't fit in the region is
+    /// not drawn. Returns the number of lines the text needed.
+    pub fn draw_text(&mut self, region: Rect, text: &str, options: TextOptions) -> usize {
+        let lines = layout_lines(text, region.width, options.wrap);
+        let visible = lines.len().min(region.height);
+        let top = match options.vertical_align {
+            VerticalAlign::Top => 0,
+            VerticalAlign::Middle => (region.height - visible) / 2,
+            VerticalAlign::Bottom => region.height - visible,
+        };
+        for (i, line) in lines.iter().take(visible).enumerate() {
+            let len = line_width(line).min(region.width);
+            let offset = match options.align {
+                Align::Left => 0,
+                Align::Center => (region.width - len) / 2,
+                Align::Right => region.width - len,
+            };
+            let row = region.row + top + i;
+            let mut col = region.col + offset;
+            for &glyph in line {
+                let width = glyph.width();
+                if width == 0 {
+                    continue;
+                }
+                if col + width > region.col + offset + len {
+                    break;
+                }
+                self.set_clipped(
+                    row,
+                    col,
+                    Char {
+                        glyph,
+                        color_fg: options.color_fg,
+                        color_bg: options.color_bg,
+                        attrs: options.attrs,
+                        link: None,
+                    },
+                );
+                col += width;
+            }
+        }
+        lines.len()
+    }
+}
+
+/// The number of columns a line of glyphs takes up.
+fn line_width(line: &[Glyph]) -> usize {
+    line.iter().map(Glyph::width).sum()
+}
+
+/// Split text into alternating runs of whitespace and of everything else, along with whether
+/// each run is whitespace.
+fn runs(text: &str) -> impl Iterator<Item = (bool, &str)> {
+    let mut rest = text;
+    std::iter::from_fn(move || {
+        let is_space = rest.chars().next()?.is_whitespace();
+        let end = rest
+            .find(|c: char| c.is_whitespace() != is_space)
+            .unwrap_or(rest.len());
+        let (run, tail) = rest.split_at(end);
+        rest = tail;
+        Some((is_space, run))
+    })
+}
+
+/// Split text into lines no wider than `width`, breaking at whitespace where possible.
+///
+/// Whitespace is kept as it is (so indentation and runs of spaces survive), except where a line
+/// is broken, where it is dropped.
+fn layout_lines(text: &str, width: usize, wrap: bool) -> Vec<Vec<Glyph>> {
+    let mut lines = Vec::new();
+    for paragraph in text.split('\n') {
+        if !wrap || width == 0 {
+            lines.push(paragraph.graphemes(true).map(Glyph::from).collect());
+            continue;
+        }
+        let mut line: Vec<Glyph> = Vec::new();
+        // The whitespace since the last word, which is only kept if the next word fits after it.
+        let mut space: Vec<Glyph> = Vec::new();
+        // Leading whitespace is indentation on the first line, but left over from a break after.
+        let mut first = true;
+        for (is_space, run) in runs(paragraph) {
+            let mut glyphs: Vec<Glyph> = run.graphemes(true).map(Glyph::from).collect();
+            if is_space {
+                space.append(&mut glyphs);
+                continue;
+            }
+            let mut word = glyphs;
+            if (first || !line.is_empty())
+                && line_width(&line) + line_width(&space) + line_width(&word) <= width
+            {
+                line.append(&mut space);
+                line.append(&mut word);
+                continue;
+            }
+            space.clear();
+            first = false;
+            if !line.is_empty() {
+                lines.push(mem::take(&mut line));
+            }
+            // Break words that are too long to fit on a line on their own.
+            while word.len() > 1 && line_width(&word) > width {
+                let mut split = 0;
+                let mut used = 0;
+                while split < word.len() && used + word[split].width() <= width {
+                    used += word[split].width();
+                    split += 1;
+                }
+                // Always make progress, even if a single glyph is wider than the line.
+                let rest = word.split_off(split.max(1));
+                lines.push(word);
+                word = rest;
+            }
+            line = word;
+        }
+        // Keep trailing whitespace, as far as it fits.
+        let mut used = line_width(&line);
+        for glyph in space {
+            if used + glyph.width() > width {
+                break;
+            }
+            used += glyph.width();
+            line.push(glyph);
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layout(text: &str, width: usize) -> Vec<String> {
+        layout_lines(text, width, true)
+            .iter()
+            .map(|line| line.iter().map(Glyph::as_str).collect())
+            .collect()
+    }
+
+    #[test]
+    fn whitespace_is_kept_within_lines() {
+        assert_eq!(layout("  indented  twice", 20), ["  indented  twice"]);
+        assert_eq!(layout("a\tb", 20), ["a\tb"]);
+        assert_eq!(layout("    ", 2), ["  "]);
+    }
+
+    #[test]
+    fn whitespace_is_dropped_where_lines_break() {
+        assert_eq!(layout("  one   two three", 8), ["  one", "two", "three"]);
+        assert_eq!(layout("one two \n  three", 7), ["one two", "  three"]);
+    }
+
+    #[test]
+    fn long_words_are_broken() {
+        assert_eq!(layout("ab abcdefg", 3), ["ab", "abc", "def", "g"]);
+        assert_eq!(layout("\u{4e00}\u{4e00}", 1), ["\u{4e00}", "\u{4e00}"]);
+    }
+}
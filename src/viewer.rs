@@ -0,0 +1,242 @@
+use crate::rect::Rect;
+use crate::screen::{Attributes, Color, Frame, Style};
+use crate::span::Line;
+use std::ops::Range;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// A scrollable view onto lines of styled text, which can be much longer than the screen.
+///
+/// Useful for log viewers and pagers. Only the visible part of the text is drawn.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct TextView {
+    lines: Vec<Line<'static>>,
+    /// The first visible line.
+    top: usize,
+    /// The number of columns scrolled off the left edge.
+    left: usize,
+    search: Option<String>,
+    /// Applied over the styles of any text matching the search.
+    pub search_style: Style,
+    /// Whether to keep the last line in view as lines are added, like `tail -f`.
+    ///
+    /// This is turned off by scrolling up, and back on by scrolling to the bottom.
+    pub follow: bool,
+}
+
+impl Default for TextView {
+    fn default() -> TextView {
+        TextView::new()
+    }
+}
+
+impl TextView {
+    pub fn new() -> TextView {
+        TextView {
+            lines: Vec::new(),
+            top: 0,
+            left: 0,
+            search: None,
+            search_style: Style::new(Color::Default, Color::Default, Attributes::REVERSE),
+            follow: false,
+        }
+    }
+
+    pub fn lines(&self) -> &[Line<'static>] {
+        &self.lines
+    }
+
+    pub fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    pub fn push_line(&mut self, line: impl Into<Line<'static>>) {
+        self.lines.push(line.into());
+    }
+
+    /// Remove all the lines and scroll back to the start.
+    pub fn clear(&mut self) {
+        self.lines.clear();
+        self.top = 0;
+        self.left = 0;
+    }
+
+    /// The first visible line and the number of columns scrolled off the left.
+    pub fn offset(&self) -> (usize, usize) {
+        (self.top, self.left)
+    }
+
+    /// Scroll so that `line` is at the top of the view.
+    pub fn scroll_to(&mut self, line: usize) {
+        self.top = line.min(self.lines.len().saturating_sub(1));
+        self.follow = false;
+    }
+
+    pub fn scroll_up(&mut self, lines: usize) {
+        self.scroll_to(self.top.saturating_sub(lines));
+    }
+
+    pub fn scroll_down(&mut self, lines: usize) {
+        self.scroll_to(self.top.saturating_add(lines));
+    }
+
+    pub fn scroll_left(&mut self, cols: usize) {
+        self.left = self.left.saturating_sub(cols);
+    }
+
+    pub fn scroll_right(&mut self, cols: usize) {
+        self.left = self.left.saturating_add(cols);
+    }
+
+    pub fn scroll_to_top(&mut self) {
+        self.scroll_to(0);
+    }
+
+    /// Scroll so the last line is at the bottom of the view, and follow new lines.
+    pub fn scroll_to_bottom(&mut self) {
+        self.follow = true;
+    }
+
+    /// Highlight every occurrence of some text, or stop highlighting with `None`.
+    pub fn set_search(&mut self, search: Option<&str>) {
+        self.search = search.filter(|s| !s.is_empty()).map(String::from);
+    }
+
+    pub fn search(&self) -> Option<&str> {
+        self.search.as_deref()
+    }
+
+    /// The indices of the lines containing the search text.
+    pub fn matches(&self) -> Vec<usize> {
+        let search = match &self.search {
+            Some(search) => search,
+            None => return Vec::new(),
+        };
+        self.lines
+            .iter()
+            .enumerate()
+            .filter(|(_, line)| line_text(line).contains(search.as_str()))
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// Scroll to the next line containing the search text, below the top of the view.
+    ///
+    /// Returns `false` if there isn't one.
+    pub fn next_match(&mut self) -> bool {
+        match self.matches().into_iter().find(|&index| index > self.top) {
+            Some(index) => {
+                self.scroll_to(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Scroll to the previous line containing the search text, above the top of the view.
+    ///
+    /// Returns `false` if there isn't one.
+    pub fn previous_match(&mut self) -> bool {
+        match self.matches().into_iter().rfind(|&index| index < self.top) {
+            Some(index) => {
+                self.scroll_to(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Draw the visible lines into a region of a frame.
+    ///
+    /// The view won't scroll past the point where the last line is at the bottom of the region.
+    pub fn render_into(&mut self, frame: &mut Frame, region: Rect) {
+        let region = region.intersection(frame.area());
+        let last_page = self.lines.len().saturating_sub(region.height);
+        self.top = if self.follow {
+            last_page
+        } else {
+            self.top.min(last_page)
+        };
+        frame.fill_rect(
+            region.row,
+            region.col,
+            region.height,
+            region.width,
+            Style::default().char(' '),
+        );
+        let lines = self.lines.iter().skip(self.top);
+        for (row, line) in (region.row..region.bottom()).zip(lines) {
+            self.render_line(frame, row, region, line);
+        }
+    }
+
+    fn render_line(&self, frame: &mut Frame, row: usize, region: Rect, line: &Line) {
+        let matches = match &self.search {
+            Some(search) => match_ranges(&line_text(line), search),
+            None => Vec::new(),
+        };
+        let mut offset = 0;
+        let mut pos = 0;
+        let glyphs = line.spans.iter().flat_map(|span| {
+            let start = offset;
+            offset += span.text.len();
+            span.text
+                .grapheme_indices(true)
+                .map(move |(index, glyph)| (start + index, glyph, &span.style))
+        });
+        let chars = glyphs.filter_map(|(index, glyph, style)| {
+            let width = glyph.width();
+            let start = pos;
+            pos += width;
+            if pos <= self.left {
+                return None;
+            }
+            let end = index + glyph.len();
+            let style = if matches.iter().any(|m| m.start < end && index < m.end) {
+                overlay(style, &self.search_style)
+            } else {
+                style.clone()
+            };
+            // A wide glyph cut in half by the left edge is replaced with a space.
+            if start < self.left {
+                Some(style.char(' '))
+            } else {
+                Some(style.char(glyph))
+            }
+        });
+        frame.write_run(row, region.col, region.right(), chars);
+    }
+}
+
+fn line_text(line: &Line) -> String {
+    line.spans.iter().map(|span| &*span.text).collect()
+}
+
+/// The byte ranges of every occurrence of `search` in `text`.
+fn match_ranges(text: &str, search: &str) -> Vec<Range<usize>> {
+    text.match_indices(search)
+        .map(|(index, found)| index..index + found.len())
+        .collect()
+}
+
+/// Apply a style over another: colors other than the default replace the ones below, and
+/// attributes are added.
+fn overlay(below: &Style, above: &Style) -> Style {
+    let pick = |above: Color, below: Color| {
+        if above == Color::Default {
+            below
+        } else {
+            above
+        }
+    };
+    Style {
+        color_fg: pick(above.color_fg, below.color_fg),
+        color_bg: pick(above.color_bg, below.color_bg),
+        attrs: below.attrs | above.attrs,
+        link: above.link.clone().or_else(|| below.link.clone()),
+    }
+}